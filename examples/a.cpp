@@ -71,6 +71,34 @@ void test_query_neighbors(imports::storage& store) {
     }
 }
 
+void test_scan_edges() {
+    auto table = imports::data_frame::open("test_scan_edges", {
+        {"src", -1LL},
+        {"dst", -1LL},
+        {"time_stamp", -1LL},
+    }).unwrap();
+
+    // 按时间顺序扫描[start_time, start_time + duration)内的边，scanner析构时自动结束扫描
+    auto scanner = imports::edge_scanner::open("coauthor", "time_stamp", 2000, 10).unwrap();
+    while (true) {
+        auto edges = scanner.next(100).expect("next() returned 'None'");
+        if (edges.view().empty()) {
+            break;
+        }
+
+        auto src = edges.view()["src"].unwrap().as_i64();
+        auto dst = edges.view()["dst"].unwrap().as_i64();
+        auto ts = edges.view()["coauthor.time_stamp"].unwrap().as_i64();
+        for (size_t i = 0; i < src.size(); i++) {
+            table.push({
+                {"src", src[i]},
+                {"dst", dst[i]},
+                {"time_stamp", ts[i]},
+            });
+        }
+    }
+}
+
 void test_log() {
     using imports::log;
     using imports::log_info;
@@ -107,6 +135,7 @@ int main(int argc, char* argv[]) {
     test_choice_nodes(store);
     test_query_nodes(store);
     test_query_neighbors(store);
+    test_scan_edges();
     test_log();
     return 0;
 }
\ No newline at end of file
@@ -52,23 +52,54 @@ def streaming_run_iter(token: str, args_lst: List[List[str]]):
     yield StreamingRunRequest(
         config = StreamingRunRequest.Config(
             token = token,
+            unordered = True, # 按完成顺序返回结果，用request_id对应请求
         )
     )
     
     # 剩下的请求必须是Args
-    for args in args_lst:
+    for i, args in enumerate(args_lst):
         yield StreamingRunRequest(
             args = StreamingRunRequest.Args(
-                args = args
+                args = args,
+                request_id = str(i),
+                trace = i == 0, # 第一次采样附带__trace__表，记录每次host接口调用的耗时
             )
         )
 
+def handle_error(err: RunError):
+    kind = RunError.Kind.Name(err.kind)
+    if err.kind in (RunError.STORAGE_UNAVAILABLE, RunError.TIMEOUT):
+        # 存储暂时不可用或超时，可以重试
+        print(f"retryable {kind}: {err.message}")
+    elif err.kind == RunError.TRAP:
+        # 脚本自身出错，打印调用栈
+        print(f"{kind}: {err.message}")
+        for frame in err.backtrace:
+            print(f"    at {frame}")
+    else:
+        print(f"{kind}: {err.message}")
+    if err.failed_import:
+        print(f"    failed import: {err.failed_import}")
+
 def run():
     # 获取编译好的wasm字节码
     with open("./a.wasm", "rb") as f:
         program = f.read()
     
     # 连接采样服务器
+    # worker开启TLS时改用secure_channel，双向TLS还需要客户端证书(见wart-worker/gen-certs.sh)
+    # credentials = grpc.ssl_channel_credentials(
+    #     root_certificates = open("certs/ca.pem", "rb").read(),
+    #     private_key = open("certs/client.key", "rb").read(),
+    #     certificate_chain = open("certs/client.pem", "rb").read(),
+    # )
+    # channel = grpc.secure_channel("[::1]:6066", credentials,
+    #     options = [("grpc.ssl_target_name_override", "wart.local")])
+    # worker开启认证时，每次调用都要带上凭证：
+    # metadata = [("x-api-key", "...")] 或 [("authorization", "Bearer <jwt>")]
+    # 如 stub.OpenSession(request, metadata = metadata)
+    # 带上W3C traceparent时，worker的span会接在调用方的trace之下：
+    # metadata = [("traceparent", "00-<trace_id>-<span_id>-01")]
     with grpc.insecure_channel("[::1]:6066") as channel:
         # 创建采样客户端
         stub = WartWorkerStub(channel)
@@ -80,8 +111,14 @@ def run():
             # io_timeout = 1000, # 单次IO限时(废弃)
             ex_timeout = 5000, # 单次采样限时
             parallel = 64, # 并行执行
+            idle_ttl = 600, # 空闲10分钟后自动回收，空闲期间可用KeepAlive续期
         ))
         
+        if resp.HasField("err"): # 采样脚本无法运行，例如缺少导出或导入了worker不提供的函数
+            for d in resp.err.diagnostics:
+                print(f"{Diagnostic.Kind.Name(d.kind)}: {d.message}")
+            return
+        
         token = resp.ok.token
         print(f"Opened: {token}")
         
@@ -99,9 +136,9 @@ def run():
                 print(table)
             for s in resp.logs: # 打印日志
                 print(s)
-            if resp.last_err:
-                print(resp.last_err)
-            print(f"================{i}================")
+            if resp.HasField("error"): # 出错时tables和logs为出错前的部分输出
+                handle_error(resp.error)
+            print(f"================{resp.request_id}================")
             if i == len(args) - 1:
                 break
             
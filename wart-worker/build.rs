@@ -1,6 +1,18 @@
+use std::path::PathBuf;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tonic_build::compile_protos("../interface/protobuf/wart-types.proto")?;
-    tonic_build::compile_protos("../interface/protobuf/wart-worker.proto")?;
-    tonic_build::compile_protos("../interface/protobuf/wart-storage.proto")?;
+    // the descriptors served by reflection
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR")?);
+    tonic_build::configure()
+        .file_descriptor_set_path(out_dir.join("wart_descriptor.bin"))
+        .compile(
+            &[
+                "../interface/protobuf/wart-types.proto",
+                "../interface/protobuf/wart-worker.proto",
+                "../interface/protobuf/wart-storage.proto",
+                "../interface/protobuf/wart-admin.proto",
+            ],
+            &["../interface/protobuf"],
+        )?;
     Ok(())
 }
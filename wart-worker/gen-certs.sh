@@ -0,0 +1,26 @@
+#!/bin/bash
+
+# Generates a CA and a server and a client certificate signed by it into
+# ./certs, for trying TLS and mutual TLS locally. The certificates are issued
+# for the domain given as the first argument, which goes into the `domain` of
+# storage_tls and redis_tls.
+
+set -e
+
+cd `dirname $0`
+mkdir -p certs
+cd certs
+
+DOMAIN=${1:-wart.local}
+
+openssl req -x509 -newkey rsa:2048 -nodes -days 365 \
+    -keyout ca.key -out ca.pem -subj "/CN=wart-ca"
+
+for name in server client; do
+    openssl req -newkey rsa:2048 -nodes \
+        -keyout $name.key -out $name.csr -subj "/CN=$DOMAIN"
+    openssl x509 -req -in $name.csr -days 365 \
+        -CA ca.pem -CAkey ca.key -CAcreateserial -out $name.pem \
+        -extfile <(printf "subjectAltName=DNS:$DOMAIN")
+    rm $name.csr
+done
@@ -0,0 +1,196 @@
+use crate::backend::Session;
+use crate::config::{AuthConfig, PrincipalConfig};
+use crate::GLOBALS;
+
+use anyhow::Result;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::Deserialize;
+use tonic::{Request, Status};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A client authenticated by `intercept`, found in the extensions of the
+/// requests it sends.
+#[derive(Debug)]
+pub struct Principal {
+    pub name: String,
+    pub spaces: Vec<String>,
+    pub max_parallel: u32,
+    pub admin: bool,
+}
+
+impl From<&PrincipalConfig> for Principal {
+    fn from(config: &PrincipalConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            spaces: config.spaces.clone(),
+            max_parallel: config.max_parallel,
+            admin: config.admin,
+        }
+    }
+}
+
+/// The sender of a request, `None` when authentication is off.
+pub type Caller = Option<Arc<Principal>>;
+
+/// A request the caller is not allowed to make, reported as PERMISSION_DENIED.
+#[derive(Debug, thiserror::Error)]
+#[error("permission denied: {0}")]
+pub struct PermissionDenied(pub String);
+
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+}
+
+pub struct Authenticator {
+    principals: HashMap<String, Arc<Principal>>,
+    api_keys: HashMap<String, Arc<Principal>>,
+    jwt: Option<(DecodingKey, Validation)>,
+}
+
+impl Authenticator {
+    pub fn new(config: &AuthConfig) -> Self {
+        let mut principals = HashMap::new();
+        let mut api_keys = HashMap::new();
+        for principal in config.principals.iter() {
+            let shared = Arc::new(Principal::from(principal));
+            for key in principal.api_keys.iter() {
+                api_keys.insert(key.clone(), shared.clone());
+            }
+            principals.insert(principal.name.clone(), shared);
+        }
+
+        let jwt = config.jwt.as_ref().map(|jwt| {
+            let mut validation = Validation::new(Algorithm::HS256);
+            if let Some(issuer) = &jwt.issuer {
+                validation.set_issuer(&[issuer]);
+            }
+            if let Some(audience) = &jwt.audience {
+                validation.set_audience(&[audience]);
+            }
+            (DecodingKey::from_secret(jwt.secret.as_bytes()), validation)
+        });
+
+        Self {
+            principals,
+            api_keys,
+            jwt,
+        }
+    }
+
+    /// Tries the `x-api-key` header first, then an `authorization: Bearer` token.
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<Arc<Principal>, Status> {
+        let metadata = request.metadata();
+
+        if let Some(key) = metadata.get("x-api-key") {
+            let key = key
+                .to_str()
+                .map_err(|_| Status::unauthenticated("malformed api key"))?;
+            return self
+                .api_keys
+                .get(key)
+                .cloned()
+                .ok_or_else(|| Status::unauthenticated("unknown api key"));
+        }
+
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("no credentials"))?;
+        let (key, validation) = self
+            .jwt
+            .as_ref()
+            .ok_or_else(|| Status::unauthenticated("bearer tokens are not accepted"))?;
+        let claims = jsonwebtoken::decode::<Claims>(token.trim(), key, validation)
+            .map_err(|err| Status::unauthenticated(format!("invalid token: {}", err)))?
+            .claims;
+        self.principals
+            .get(&claims.sub)
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated(format!("unknown principal: {}", claims.sub)))
+    }
+}
+
+/// Interceptor of the WartWorker service, a no-op when authentication is off.
+pub fn intercept(mut request: Request<()>) -> Result<Request<()>, Status> {
+    if let Some(auth) = &GLOBALS.auth {
+        let principal = auth.authenticate(&request)?;
+        request.extensions_mut().insert(principal);
+    }
+    Ok(request)
+}
+
+/// Interceptor of the WartAdmin service, letting only admins through.
+pub fn intercept_admin(request: Request<()>) -> Result<Request<()>, Status> {
+    let request = intercept(request)?;
+    match caller(&request) {
+        Some(principal) if !principal.admin => Err(Status::permission_denied(format!(
+            "{} is not an admin",
+            principal.name
+        ))),
+        _ => Ok(request),
+    }
+}
+
+pub fn caller<T>(request: &Request<T>) -> Caller {
+    request.extensions().get::<Arc<Principal>>().cloned()
+}
+
+/// The owner recorded in the sessions the caller opens.
+pub fn owner(caller: &Caller) -> String {
+    caller
+        .as_ref()
+        .map(|principal| principal.name.clone())
+        .unwrap_or_default()
+}
+
+pub fn check_open(caller: &Caller, space_name: &str, parallel: u32) -> Result<()> {
+    let principal = match caller {
+        Some(principal) => principal,
+        None => return Ok(()),
+    };
+    if !principal
+        .spaces
+        .iter()
+        .any(|space| space == "*" || space == space_name)
+    {
+        Err(PermissionDenied(format!(
+            "{} may not open sessions on {}",
+            principal.name, space_name
+        )))?;
+    }
+    if principal.max_parallel > 0 && parallel > principal.max_parallel {
+        Err(PermissionDenied(format!(
+            "parallel {} exceeds the limit {} of {}",
+            parallel, principal.max_parallel, principal.name
+        )))?;
+    }
+    Ok(())
+}
+
+/// Sessions are only usable by the principal that opened them, sessions
+/// opened while authentication was off by no one.
+pub fn check_owner(caller: &Caller, session: &Session) -> Result<()> {
+    if let Some(principal) = caller {
+        if principal.name != session.owner {
+            Err(PermissionDenied(format!(
+                "session {} is not owned by {}",
+                session.token, principal.name
+            )))?;
+        }
+    }
+    Ok(())
+}
+
+/// Loads the session to check its owner, sparing the lookup when
+/// authentication is off.
+pub async fn check_session(caller: &Caller, token: &str) -> Result<()> {
+    if caller.is_some() {
+        let session = GLOBALS.kv.get_session(token).await?;
+        check_owner(caller, &session)?;
+    }
+    Ok(())
+}
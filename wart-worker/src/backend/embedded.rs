@@ -0,0 +1,583 @@
+use crate::bindgen::imports::MergeType;
+
+use super::{
+    unix_now, BackendError, KvStore, Session, SessionStats, SessionStore, StoreUpdate, KV_HISTORY,
+};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Store {
+    base: HashMap<String, String>,
+    epochs: BTreeMap<u64, HashMap<String, String>>,
+}
+
+impl Store {
+    fn lookup(&self, epoch: u64, field: &str) -> Option<&String> {
+        self.epochs
+            .range(..=epoch)
+            .rev()
+            .find_map(|(_, fields)| fields.get(field))
+            .or_else(|| self.base.get(field))
+    }
+}
+
+/// Adds `argum` to `value` as a `kind`.
+fn merge(value: Option<&str>, argum: &str, kind: &str) -> Result<String> {
+    match kind {
+        "int" => {
+            let value = value.map(|v| v.parse::<i64>()).unwrap_or(Ok(0))?;
+            Ok((value + argum.parse::<i64>()?).to_string())
+        }
+        "flt" => {
+            let value = value.map(|v| v.parse::<f64>()).unwrap_or(Ok(0.0))?;
+            Ok((value + argum.parse::<f64>()?).to_string())
+        }
+        "bol" => {
+            if value == Some("1") || argum == "1" {
+                Ok("1".into())
+            } else {
+                Ok("0".into())
+            }
+        }
+        "txt" => Ok(format!("{}{}", value.unwrap_or(""), argum)),
+        _ => Err(anyhow!("unknown kind: {}", kind)),
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct State {
+    sessions: HashMap<String, Session>,
+    modules: HashMap<String, Vec<u8>>,
+    stores: HashMap<String, Store>,
+
+    /// Deadlines of the sessions with an `idle_ttl`.
+    #[serde(default)]
+    deadlines: HashMap<String, u64>,
+}
+
+impl State {
+    /// Refuses runs more than `KV_HISTORY` epochs behind the session. Every
+    /// epoch is kept here, but the Redis store drops what only they could read.
+    fn check_epoch(&self, token: &str, epoch: u64) -> Result<()> {
+        let now = self.sessions.get(token).map_or(epoch, |s| s.epoch);
+        if epoch + KV_HISTORY < now {
+            Err(BackendError::invalid_argument(format!(
+                "epoch {} is more than {} epochs behind the session at {}",
+                epoch, KV_HISTORY, now
+            )))?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps sessions and KV stores in the memory of the worker, so a single
+/// worker can run without Redis. The state is optionally written to a JSON
+/// snapshot by `flush` and loaded back on start.
+pub struct EmbeddedKvStore {
+    state: RwLock<State>,
+    snapshot: Option<PathBuf>,
+    dirty: AtomicBool,
+}
+
+impl EmbeddedKvStore {
+    pub fn open(snapshot: Option<PathBuf>) -> Result<Self> {
+        let state = match &snapshot {
+            Some(path) if path.exists() => {
+                let state: State = serde_json::from_slice(&std::fs::read(path)?)?;
+                log::info!(
+                    "{} sessions loaded from {}",
+                    state.sessions.len(),
+                    path.display()
+                );
+                state
+            }
+            _ => State::default(),
+        };
+
+        Ok(Self {
+            state: RwLock::new(state),
+            snapshot,
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    fn read<R>(&self, f: impl FnOnce(&State) -> Result<R>) -> Result<R> {
+        let state = self.state.read().map_err(|err| anyhow!("{}", err))?;
+        f(&state)
+    }
+
+    fn write<R>(&self, f: impl FnOnce(&mut State) -> Result<R>) -> Result<R> {
+        let mut state = self.state.write().map_err(|err| anyhow!("{}", err))?;
+        self.dirty.store(true, Ordering::Release);
+        f(&mut state)
+    }
+}
+
+#[mobc::async_trait]
+impl SessionStore for EmbeddedKvStore {
+    async fn open_session(&self, session: &Session, module: &[u8]) -> Result<()> {
+        self.write(|state| {
+            state
+                .sessions
+                .insert(session.token.clone(), session.clone());
+            state.modules.insert(session.token.clone(), module.to_vec());
+            if session.idle_ttl > 0 {
+                state
+                    .deadlines
+                    .insert(session.token.clone(), unix_now() + session.idle_ttl);
+            }
+            Ok(())
+        })
+    }
+
+    async fn get_session(&self, token: &str) -> Result<Session> {
+        self.read(|state| {
+            state
+                .sessions
+                .get(token)
+                .cloned()
+                .ok_or_else(|| BackendError::not_found(format!("no session: {}", token)).into())
+        })
+    }
+
+    async fn get_module(&self, token: &str) -> Result<Vec<u8>> {
+        self.read(|state| {
+            state
+                .modules
+                .get(token)
+                .cloned()
+                .ok_or_else(|| BackendError::not_found(format!("no session: {}", token)).into())
+        })
+    }
+
+    async fn list_sessions(&self) -> Result<Vec<String>> {
+        self.read(|state| Ok(state.sessions.keys().cloned().collect()))
+    }
+
+    async fn session_stats(&self, token: &str) -> Result<SessionStats> {
+        self.read(|state| {
+            let module = state
+                .modules
+                .get(token)
+                .ok_or_else(|| BackendError::not_found(format!("no session: {}", token)))?;
+            let kv_fields = state.stores.get(token).map_or(0, |store| {
+                let written = store
+                    .epochs
+                    .values()
+                    .flat_map(|e| e.keys())
+                    .collect::<HashSet<_>>();
+                store.base.len() + written.len()
+            });
+            Ok(SessionStats {
+                module_size: module.len() as u64,
+                kv_fields: kv_fields as u64,
+            })
+        })
+    }
+
+    async fn increment_epoch(&self, token: &str) -> Result<u64> {
+        self.write(|state| {
+            let session = state
+                .sessions
+                .get_mut(token)
+                .ok_or_else(|| BackendError::not_found(format!("no session: {}", token)))?;
+            session.epoch += 1;
+            Ok(session.epoch)
+        })
+    }
+
+    async fn close_session(&self, token: &str) -> Result<()> {
+        self.write(|state| {
+            state.sessions.remove(token);
+            state.modules.remove(token);
+            state.stores.remove(token);
+            state.deadlines.remove(token);
+            Ok(())
+        })
+    }
+
+    async fn keep_alive(&self, token: &str) -> Result<u64> {
+        self.write(|state| {
+            let session = state
+                .sessions
+                .get_mut(token)
+                .ok_or_else(|| BackendError::not_found(format!("no session: {}", token)))?;
+            let now = unix_now();
+            session.last_used = now;
+            if session.idle_ttl == 0 {
+                return Ok(0);
+            }
+            let deadline = now + session.idle_ttl;
+            state.deadlines.insert(token.into(), deadline);
+            Ok(deadline)
+        })
+    }
+
+    async fn expired_sessions(&self, now: u64, limit: usize) -> Result<Vec<String>> {
+        self.read(|state| {
+            Ok(state
+                .deadlines
+                .iter()
+                .filter(|(_, deadline)| **deadline <= now)
+                .map(|(token, _)| token.clone())
+                .take(limit)
+                .collect())
+        })
+    }
+
+    async fn flush(&self) -> Result<()> {
+        let path = match &self.snapshot {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let data = self.read(|state| Ok(serde_json::to_vec(state)?))?;
+
+        // written aside first, so a crash never leaves a truncated snapshot
+        let temp = path.with_extension("tmp");
+        tokio::fs::write(&temp, data).await?;
+        tokio::fs::rename(&temp, path).await?;
+        Ok(())
+    }
+}
+
+#[mobc::async_trait]
+impl KvStore for EmbeddedKvStore {
+    async fn query_kv(
+        &self,
+        token: &str,
+        epoch: u64,
+        fields: &[&str],
+        defva: &str,
+    ) -> Result<Vec<String>> {
+        self.read(|state| {
+            state.check_epoch(token, epoch)?;
+            let store = state.stores.get(token);
+            Ok(fields
+                .iter()
+                .map(|field| {
+                    store
+                        .and_then(|s| s.lookup(epoch, field))
+                        .cloned()
+                        .unwrap_or_else(|| defva.into())
+                })
+                .collect())
+        })
+    }
+
+    async fn update_kv(
+        &self,
+        token: &str,
+        epoch: u64,
+        fields: &[&str],
+        argums: &[String],
+        kind: &str,
+        merge: MergeType,
+    ) -> Result<u64> {
+        self.write(|state| {
+            state.check_epoch(token, epoch)?;
+            if !matches!(kind, "int" | "flt" | "bol" | "txt") {
+                Err(BackendError::invalid_argument(format!(
+                    "unknown kind: {}",
+                    kind
+                )))?;
+            }
+
+            // worked out in full first, so a bad argument writes nothing
+            let store = state.stores.get(token);
+            let mut values = HashMap::new();
+            for (field, argum) in fields.iter().zip(argums.iter()) {
+                let value = match merge {
+                    MergeType::Mov => argum.clone(),
+                    MergeType::Add => {
+                        let value = match values.get(field) {
+                            Some(value) => Some(value),
+                            None => store.and_then(|s| s.lookup(epoch + 1, field)),
+                        };
+                        self::merge(value.map(|v| &v[..]), argum, kind).map_err(|err| {
+                            BackendError::invalid_argument(format!("{}: {}", field, err))
+                        })?
+                    }
+                };
+                values.insert(*field, value);
+            }
+
+            let store = state.stores.entry(token.into()).or_default();
+            let target = store.epochs.entry(epoch + 1).or_default();
+            for (field, value) in values.into_iter() {
+                target.insert(field.to_string(), value);
+            }
+            Ok(fields.len() as u64)
+        })
+    }
+
+    async fn update_store(
+        &self,
+        token: &str,
+        fields: &[String],
+        update: StoreUpdate<'_>,
+    ) -> Result<u64> {
+        self.write(|state| {
+            if !state.sessions.contains_key(token) {
+                Err(BackendError::not_found(format!("no session: {}", token)))?;
+            }
+
+            let base = &mut state.stores.entry(token.into()).or_default().base;
+            let mut ok_count = 0;
+            match update {
+                StoreUpdate::Del => {
+                    for field in fields.iter() {
+                        base.remove(field);
+                        ok_count += 1;
+                    }
+                }
+                StoreUpdate::Add(vals) => {
+                    for (field, value) in fields.iter().zip(vals.iter()) {
+                        let entry = base.entry(field.clone()).or_insert_with(|| "0".into());
+                        match entry.parse::<i64>() {
+                            Ok(x) => *entry = (x + value).to_string(),
+                            Err(_) => break,
+                        }
+                        ok_count += 1;
+                    }
+                }
+                StoreUpdate::Mov(vals) => {
+                    for (field, value) in fields.iter().zip(vals.iter()) {
+                        base.insert(field.clone(), value.clone());
+                        ok_count += 1;
+                    }
+                }
+            }
+            Ok(ok_count)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::error_kind;
+    use crate::bindgen::imports::StorageErrorKind;
+
+    async fn open(store: &EmbeddedKvStore, idle_ttl: u64) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        let now = unix_now();
+        let session = Session {
+            token: token.clone(),
+            space_name: "test".into(),
+            epoch: 0,
+            module_hash: 1,
+            ex_timeout: 1000,
+            parallel: 1,
+            max_fuel: 0,
+            limits: Default::default(),
+            idle_ttl,
+            created_at: now,
+            last_used: now,
+            owner: String::new(),
+        };
+        store.open_session(&session, b"module").await.unwrap();
+        token
+    }
+
+    #[tokio::test]
+    async fn epoch_versioned_kv() {
+        let store = EmbeddedKvStore::open(None).unwrap();
+        let token = open(&store, 0).await;
+
+        let base = ["a".to_string(), "s".to_string()];
+        let vals = ["10".to_string(), "x".to_string()];
+        store
+            .update_store(&token, &base, StoreUpdate::Mov(&vals))
+            .await
+            .unwrap();
+
+        // updates of epoch 0 are only visible from epoch 1 on
+        let argums = ["5".to_string(), "7".to_string()];
+        store
+            .update_kv(&token, 0, &["a", "b"], &argums, "int", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 0, &["a", "b"], "0").await.unwrap();
+        assert_eq!(got, ["10", "0"]);
+        assert_eq!(store.increment_epoch(&token).await.unwrap(), 1);
+        let got = store.query_kv(&token, 1, &["a", "b"], "0").await.unwrap();
+        assert_eq!(got, ["15", "7"]);
+
+        // later epochs build on the newest version before them
+        let one = ["1".to_string()];
+        store
+            .update_kv(&token, 1, &["a"], &one, "int", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 9, &["a"], "0").await.unwrap();
+        assert_eq!(got, ["16"]);
+        store
+            .update_kv(&token, 1, &["a"], &one, "int", MergeType::Mov)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 2, &["a"], "0").await.unwrap();
+        assert_eq!(got, ["1"]);
+
+        let halves = ["0.5".to_string(), "0.5".to_string()];
+        store
+            .update_kv(&token, 0, &["f", "f"], &halves, "flt", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 1, &["f"], "0").await.unwrap();
+        assert_eq!(got, ["1"]);
+
+        let y = ["y".to_string()];
+        store
+            .update_kv(&token, 0, &["s"], &y, "txt", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 1, &["s"], "").await.unwrap();
+        assert_eq!(got, ["xy"]);
+
+        let flags = ["1".to_string(), "0".to_string()];
+        store
+            .update_kv(&token, 0, &["t", "u"], &flags, "bol", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 1, &["t", "u"], "").await.unwrap();
+        assert_eq!(got, ["1", "0"]);
+
+        let stats = store.session_stats(&token).await.unwrap();
+        assert_eq!(stats.kv_fields, 2 + 6);
+    }
+
+    #[tokio::test]
+    async fn bad_updates_write_nothing() {
+        let store = EmbeddedKvStore::open(None).unwrap();
+        let token = open(&store, 0).await;
+
+        let argums = ["1".to_string(), "x".to_string()];
+        let err = store
+            .update_kv(&token, 0, &["a", "b"], &argums, "int", MergeType::Add)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+        let err = store
+            .update_kv(&token, 0, &["a", "b"], &argums, "big", MergeType::Mov)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+
+        let got = store.query_kv(&token, 1, &["a", "b"], "-").await.unwrap();
+        assert_eq!(got, ["-", "-"]);
+    }
+
+    #[tokio::test]
+    async fn runs_behind_the_kv_history_are_refused() {
+        let store = EmbeddedKvStore::open(None).unwrap();
+        let token = open(&store, 0).await;
+
+        let one = ["1".to_string()];
+        for epoch in 0..=KV_HISTORY + 1 {
+            store
+                .update_kv(&token, epoch, &["c"], &one, "int", MergeType::Add)
+                .await
+                .unwrap();
+            store.increment_epoch(&token).await.unwrap();
+        }
+
+        // the session is at KV_HISTORY + 2, the same as in the Redis store
+        let err = store
+            .update_kv(&token, 1, &["c"], &one, "int", MergeType::Add)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+        let err = store.query_kv(&token, 1, &["c"], "0").await.unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+
+        let got = store.query_kv(&token, 2, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["2"]);
+        store
+            .update_kv(&token, 2, &["c"], &one, "int", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 3, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["4"]);
+        let got = store.query_kv(&token, 6, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["6"]);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions() {
+        let store = EmbeddedKvStore::open(None).unwrap();
+        let expiring = open(&store, 60).await;
+        let lasting = open(&store, 0).await;
+
+        let before = unix_now();
+        let deadline = store.keep_alive(&expiring).await.unwrap();
+        assert!(deadline >= before + 60);
+        assert_eq!(store.keep_alive(&lasting).await.unwrap(), 0);
+
+        let expired = store.expired_sessions(deadline, 1 << 20).await.unwrap();
+        assert_eq!(expired, [expiring.clone()]);
+        assert!(store
+            .expired_sessions(deadline, 0)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store
+            .expired_sessions(before, 1 << 20)
+            .await
+            .unwrap()
+            .is_empty());
+
+        store.close_session(&expiring).await.unwrap();
+        assert!(store
+            .expired_sessions(deadline, 1 << 20)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn snapshot_round_trip() {
+        let path = std::env::temp_dir().join(format!("wart-{}.json", uuid::Uuid::new_v4()));
+        let store = EmbeddedKvStore::open(Some(path.clone())).unwrap();
+        let token = open(&store, 60).await;
+        let one = ["1".to_string()];
+        store
+            .update_kv(&token, 0, &["c"], &one, "int", MergeType::Add)
+            .await
+            .unwrap();
+        store.increment_epoch(&token).await.unwrap();
+        store.flush().await.unwrap();
+
+        let store = EmbeddedKvStore::open(Some(path.clone())).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let session = store.get_session(&token).await.unwrap();
+        assert_eq!(session.epoch, 1);
+        assert_eq!(store.get_module(&token).await.unwrap(), b"module");
+        let got = store.query_kv(&token, 1, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["1"]);
+        let expired = store.expired_sessions(u64::MAX, 1 << 20).await.unwrap();
+        assert_eq!(expired, [token]);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::bindgen::imports::StorageErrorKind;
+
+use tonic::Code;
+
+/// A failure of a backend that knows what went wrong. Other errors are
+/// classified by `error_kind` from their source.
+#[derive(Debug, thiserror::Error)]
+#[error("{message}")]
+pub struct BackendError {
+    pub kind: StorageErrorKind,
+    pub message: String,
+}
+
+impl BackendError {
+    pub fn new(kind: StorageErrorKind, message: impl ToString) -> Self {
+        Self {
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn not_found(message: impl ToString) -> Self {
+        Self::new(StorageErrorKind::NotFound, message)
+    }
+
+    pub fn unavailable(message: impl ToString) -> Self {
+        Self::new(StorageErrorKind::Unavailable, message)
+    }
+
+    pub fn invalid_argument(message: impl ToString) -> Self {
+        Self::new(StorageErrorKind::InvalidArgument, message)
+    }
+}
+
+/// `StorageResponse.error_code` shares the numbering of the gRPC status codes.
+pub fn code_kind(code: Code) -> StorageErrorKind {
+    match code {
+        Code::NotFound => StorageErrorKind::NotFound,
+        Code::InvalidArgument | Code::OutOfRange | Code::FailedPrecondition => {
+            StorageErrorKind::InvalidArgument
+        }
+        Code::Unavailable
+        | Code::DeadlineExceeded
+        | Code::ResourceExhausted
+        | Code::Aborted
+        | Code::Cancelled => StorageErrorKind::Unavailable,
+        _ => StorageErrorKind::Internal,
+    }
+}
+
+pub fn error_kind(err: &anyhow::Error) -> StorageErrorKind {
+    if let Some(err) = err.downcast_ref::<BackendError>() {
+        return err.kind;
+    }
+    if let Some(status) = err.downcast_ref::<tonic::Status>() {
+        return code_kind(status.code());
+    }
+    if err.downcast_ref::<tonic::transport::Error>().is_some() {
+        return StorageErrorKind::Unavailable;
+    }
+    if let Some(err) = err.downcast_ref::<redis::RedisError>() {
+        if err.is_io_error() || err.is_timeout() || err.is_connection_dropped() {
+            return StorageErrorKind::Unavailable;
+        }
+    }
+    StorageErrorKind::Internal
+}
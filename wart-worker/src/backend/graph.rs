@@ -0,0 +1,40 @@
+use crate::bindgen::*;
+
+use anyhow::Result;
+
+/// How many requests are in flight at once when a backend or a sampler fans a
+/// query out into one request per node.
+pub const FANOUT_CONCURRENCY: usize = 64;
+
+/// Graph queries issued by the sandboxes. Every call returns the `data` of the
+/// corresponding `StorageResponse` in `wart-storage.proto`.
+#[mobc::async_trait]
+pub trait GraphBackend: Send + Sync {
+    async fn choice_nodes(&self, request: ChoiceNodesRequest) -> Result<DataFrame>;
+
+    async fn fetch_node(&self, request: FetchNodeRequest) -> Result<DataFrame>;
+
+    async fn fetch_neighbors(&self, request: FetchNeighborsRequest) -> Result<DataFrame>;
+
+    async fn fetch_node_batch(&self, request: FetchNodeBatchRequest) -> Result<DataFrame>;
+
+    async fn fetch_neighbors_batch(&self, request: FetchNeighborsBatchRequest)
+        -> Result<DataFrame>;
+
+    /// Returns the iterator token.
+    async fn start_scan_edges(&self, request: StartScanEdgesRequest) -> Result<String>;
+
+    async fn get_next_edges(&self, request: GetNextEdgesRequest) -> Result<DataFrame>;
+
+    async fn finish_scan_edges(&self, request: FinishScanEdgesRequest) -> Result<()>;
+
+    /// Fails unless the graph is reachable, for health checking.
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// The state of the connection pool, if the backend has one.
+    async fn pool_state(&self) -> Option<mobc::State> {
+        None
+    }
+}
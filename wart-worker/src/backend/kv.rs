@@ -0,0 +1,158 @@
+use crate::bindgen::imports::MergeType;
+use crate::wasm::limits::Limits;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Epochs a run may be behind its session and still use the KV store. The
+/// versions of a field that no such run can read are dropped.
+pub const KV_HISTORY: u64 = 4;
+
+/// Seconds since the unix epoch, the unit of session deadlines.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Everything a worker needs to run the program of a session, but the
+/// serialized module itself which is fetched by `get_module` on a cache miss.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub token: String,
+    pub space_name: String,
+    pub epoch: u64,
+    pub module_hash: u64,
+    pub ex_timeout: u64,
+    pub parallel: usize,
+
+    /// Fuel of a single run, `0` for no limit.
+    #[serde(default)]
+    pub max_fuel: u64,
+
+    /// Already capped by the limits of the worker that opened the session.
+    #[serde(default)]
+    pub limits: Limits,
+
+    /// Seconds without runs or updates after which the session is closed by
+    /// the sweeper, `0` for never.
+    #[serde(default)]
+    pub idle_ttl: u64,
+
+    /// Unix seconds, `0` for sessions opened by older workers.
+    #[serde(default)]
+    pub created_at: u64,
+
+    /// Unix seconds of the last run or update, refreshed by `keep_alive`.
+    #[serde(default)]
+    pub last_used: u64,
+
+    /// The principal that opened the session, empty if authentication was
+    /// off. Only the owner may use it while authentication is on.
+    #[serde(default)]
+    pub owner: String,
+}
+
+/// Figures of a session too costly to gather on every run.
+#[derive(Debug, Clone, Default)]
+pub struct SessionStats {
+    pub module_size: u64,
+
+    /// Fields of the base store plus the fields written by `update_kv`.
+    pub kv_fields: u64,
+}
+
+/// A write issued by `UpdateStore` into the base store of a session.
+pub enum StoreUpdate<'a> {
+    Del,
+    Add(&'a [i64]),
+    Mov(&'a [String]),
+}
+
+#[mobc::async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn open_session(&self, session: &Session, module: &[u8]) -> Result<()>;
+
+    async fn get_session(&self, token: &str) -> Result<Session>;
+
+    async fn get_module(&self, token: &str) -> Result<Vec<u8>>;
+
+    /// Tokens of all the open sessions.
+    async fn list_sessions(&self) -> Result<Vec<String>>;
+
+    async fn session_stats(&self, token: &str) -> Result<SessionStats>;
+
+    /// Returns the new epoch.
+    async fn increment_epoch(&self, token: &str) -> Result<u64>;
+
+    /// Drops the session along with its KV stores. Closing a session that
+    /// doesn't exist is not an error, so expired sessions may be closed by
+    /// several workers at once.
+    async fn close_session(&self, token: &str) -> Result<()>;
+
+    /// Postpones the expiry of the session to `idle_ttl` seconds from now and
+    /// marks it as used. Returns the new deadline, `0` if the session never expires.
+    async fn keep_alive(&self, token: &str) -> Result<u64>;
+
+    /// At most `limit` sessions whose deadline is not after `now`. They stay
+    /// expired until closed.
+    async fn expired_sessions(&self, now: u64, limit: usize) -> Result<Vec<String>>;
+
+    /// Fails unless the store is reachable, for health checking.
+    async fn ping(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// The state of the connection pool, if the store has one.
+    async fn pool_state(&self) -> Option<mobc::State> {
+        None
+    }
+
+    /// Persists the state, if the store supports it.
+    async fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The per-session KV stores. Values are kept in their string form, `kind` is
+/// one of "int", "flt", "bol" or "txt" and decides how `MergeType::Add`
+/// combines them.
+///
+/// The value of a field at epoch `e` is the one written in the newest epoch
+/// `ep <= e`, or the one in the base store written by `UpdateStore` otherwise.
+/// Runs more than `KV_HISTORY` epochs behind their session fail with
+/// `InvalidArgument`, as would-be answers may be gone.
+#[mobc::async_trait]
+pub trait KvStore: SessionStore {
+    async fn query_kv(
+        &self,
+        token: &str,
+        epoch: u64,
+        fields: &[&str],
+        defva: &str,
+    ) -> Result<Vec<String>>;
+
+    /// Writes into epoch + 1, so the updates are invisible to the runs of the
+    /// current epoch. Returns the number of fields written.
+    async fn update_kv(
+        &self,
+        token: &str,
+        epoch: u64,
+        fields: &[&str],
+        argums: &[String],
+        kind: &str,
+        merge: MergeType,
+    ) -> Result<u64>;
+
+    /// Writes into the base store and returns the number of fields written
+    /// before the first failure.
+    async fn update_store(
+        &self,
+        token: &str,
+        fields: &[String],
+        update: StoreUpdate<'_>,
+    ) -> Result<u64>;
+}
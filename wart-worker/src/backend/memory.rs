@@ -0,0 +1,727 @@
+/*
+An in-memory graph loaded from a directory of plain edge-list files, meant for
+development and offline tests. The same graph is served for every space_name.
+
+{path}/{tag}.nodes:
+    id,key1,key2,...
+    1005,42,0.5,...
+
+{path}/{tag}.edges:
+    src,dst,key1,...
+    1005,1006,2001,...
+
+Fields are separated by commas, empty lines and lines starting with '#' are
+skipped. A column holds int64 if every field parses as such, float64 if every
+field parses as a number and strings otherwise.
+*/
+
+use crate::bindgen::*;
+use crate::wasm::sampler;
+
+use super::{BackendError, GraphBackend};
+
+use anyhow::{anyhow, Result};
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+enum Column {
+    Int(Vec<i64>),
+    Flt(Vec<f64>),
+    Txt(Vec<String>),
+}
+
+impl Column {
+    fn parse(raw: Vec<String>) -> Self {
+        if let Ok(x) = raw
+            .iter()
+            .map(|v| v.parse())
+            .collect::<Result<Vec<i64>, _>>()
+        {
+            return Self::Int(x);
+        }
+        if let Ok(x) = raw
+            .iter()
+            .map(|v| v.parse())
+            .collect::<Result<Vec<f64>, _>>()
+        {
+            return Self::Flt(x);
+        }
+        Self::Txt(raw)
+    }
+
+    fn as_f64(&self) -> Option<Vec<f64>> {
+        match self {
+            Self::Int(x) => Some(x.iter().map(|v| *v as f64).collect()),
+            Self::Flt(x) => Some(x.clone()),
+            Self::Txt(_) => None,
+        }
+    }
+
+    /// Picks the rows in `index`, `None` picks a default value.
+    fn take(&self, index: &[Option<usize>]) -> series::Values {
+        match self {
+            Self::Int(x) => series::Values::Int64Values(series::Int64Series {
+                data: index.iter().map(|i| i.map(|i| x[i]).unwrap_or(0)).collect(),
+            }),
+            Self::Flt(x) => series::Values::Float64Values(series::Float64Series {
+                data: index
+                    .iter()
+                    .map(|i| i.map(|i| x[i]).unwrap_or(0.0))
+                    .collect(),
+            }),
+            Self::Txt(x) => series::Values::StringValues(series::StringSeries {
+                data: index
+                    .iter()
+                    .map(|i| i.map(|i| x[i].clone()).unwrap_or_default())
+                    .collect(),
+            }),
+        }
+    }
+}
+
+struct Table {
+    keys: HashMap<String, Column>,
+    order: Vec<String>,
+}
+
+impl Table {
+    fn load(path: &Path, head: usize) -> Result<(Vec<Vec<String>>, Self)> {
+        let text = std::fs::read_to_string(path)?;
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+        let header = lines
+            .next()
+            .ok_or_else(|| anyhow!("{}: missing header", path.display()))?
+            .1
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect::<Vec<_>>();
+        if header.len() < head {
+            Err(anyhow!("{}: too few columns in header", path.display()))?;
+        }
+
+        let mut raw = vec![vec![]; header.len()];
+        for (no, line) in lines {
+            let fields = line.split(',').map(|s| s.trim()).collect::<Vec<_>>();
+            if fields.len() != header.len() {
+                Err(anyhow!(
+                    "{}:{}: expected {} fields",
+                    path.display(),
+                    no,
+                    header.len()
+                ))?;
+            }
+            for (column, field) in raw.iter_mut().zip(fields.into_iter()) {
+                column.push(field.to_string());
+            }
+        }
+
+        let attrs = raw.split_off(head);
+        let order = header[head..].to_vec();
+        let keys = order
+            .iter()
+            .cloned()
+            .zip(attrs.into_iter().map(Column::parse))
+            .collect();
+        Ok((raw, Self { keys, order }))
+    }
+
+    fn columns(
+        &self,
+        tag: &str,
+        keys: &[String],
+        index: &[Option<usize>],
+        df: &mut DataFrame,
+    ) -> Result<()> {
+        for key in keys.iter() {
+            let column = self
+                .keys
+                .get(key)
+                .ok_or_else(|| BackendError::not_found(format!("unknown key: {}.{}", tag, key)))?;
+            df.headers.push(format!("{}.{}", tag, key));
+            df.columns.push(Series {
+                values: Some(column.take(index)),
+            });
+        }
+        Ok(())
+    }
+}
+
+struct NodeTable {
+    ids: Vec<String>,
+    index: HashMap<String, usize>,
+    attrs: Table,
+}
+
+struct EdgeTable {
+    src: Vec<String>,
+    dst: Vec<String>,
+    out: HashMap<String, Vec<usize>>,
+    inc: HashMap<String, Vec<usize>>,
+    attrs: Table,
+}
+
+struct Scan {
+    tag: String,
+    index: Vec<usize>,
+    cursor: usize,
+}
+
+pub struct MemoryGraphBackend {
+    nodes: HashMap<String, NodeTable>,
+    edges: HashMap<String, EdgeTable>,
+    numeric_ids: bool,
+    scans: Mutex<HashMap<String, Scan>>,
+}
+
+impl MemoryGraphBackend {
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut nodes = HashMap::new();
+        let mut edges = HashMap::new();
+
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            let tag = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(tag) => tag.to_string(),
+                None => continue,
+            };
+
+            match path.extension().and_then(|s| s.to_str()) {
+                Some("nodes") => {
+                    let (mut raw, attrs) = Table::load(&path, 1)?;
+                    let ids = raw.remove(0);
+                    let index = ids
+                        .iter()
+                        .enumerate()
+                        .map(|(i, id)| (id.clone(), i))
+                        .collect();
+                    nodes.insert(tag, NodeTable { ids, index, attrs });
+                }
+                Some("edges") => {
+                    let (mut raw, attrs) = Table::load(&path, 2)?;
+                    let dst = raw.remove(1);
+                    let src = raw.remove(0);
+                    let mut out: HashMap<_, Vec<_>> = HashMap::new();
+                    let mut inc: HashMap<_, Vec<_>> = HashMap::new();
+                    for (i, (s, d)) in src.iter().zip(dst.iter()).enumerate() {
+                        out.entry(s.clone()).or_default().push(i);
+                        inc.entry(d.clone()).or_default().push(i);
+                    }
+                    edges.insert(
+                        tag,
+                        EdgeTable {
+                            src,
+                            dst,
+                            out,
+                            inc,
+                            attrs,
+                        },
+                    );
+                }
+                _ => continue,
+            }
+        }
+
+        let numeric_ids = nodes
+            .values()
+            .flat_map(|t| t.ids.iter())
+            .chain(
+                edges
+                    .values()
+                    .flat_map(|t| t.src.iter().chain(t.dst.iter())),
+            )
+            .all(|id| id.parse::<i64>().is_ok());
+
+        log::info!(
+            "memory graph loaded from {}: {} node tags, {} edge tags",
+            path.display(),
+            nodes.len(),
+            edges.len()
+        );
+
+        Ok(Self {
+            nodes,
+            edges,
+            numeric_ids,
+            scans: Mutex::new(HashMap::new()),
+        })
+    }
+
+    fn ids(&self, ids: Vec<String>) -> series::Values {
+        if self.numeric_ids {
+            series::Values::Int64Values(series::Int64Series {
+                data: ids.iter().map(|id| id.parse().unwrap_or(0)).collect(),
+            })
+        } else {
+            series::Values::StringValues(series::StringSeries { data: ids })
+        }
+    }
+
+    fn node_ids(ids: Option<Series>) -> Vec<String> {
+        match ids.and_then(|s| s.values) {
+            Some(series::Values::Int64Values(x)) => {
+                x.data.into_iter().map(|v| v.to_string()).collect()
+            }
+            Some(series::Values::StringValues(x)) => x.data,
+            _ => vec![],
+        }
+    }
+
+    fn node_table(&self, tag: &str) -> Result<&NodeTable> {
+        self.nodes
+            .get(tag)
+            .ok_or_else(|| BackendError::not_found(format!("unknown tag: {}", tag)).into())
+    }
+
+    fn edge_table(&self, tag: &str) -> Result<&EdgeTable> {
+        self.edges
+            .get(tag)
+            .ok_or_else(|| BackendError::not_found(format!("unknown edge type: {}", tag)).into())
+    }
+
+    fn nodes(&self, ids: &[String], tag: &str, keys: &[String]) -> Result<DataFrame> {
+        let table = self.node_table(tag)?;
+        let index = ids
+            .iter()
+            .map(|id| table.index.get(id).copied())
+            .collect::<Vec<_>>();
+
+        let mut df = DataFrame::default();
+        table.attrs.columns(tag, keys, &index, &mut df)?;
+        Ok(df)
+    }
+
+    /// The neighbors of all the `ids` one after another, along with the
+    /// offsets where those of every id start.
+    fn neighbors(
+        &self,
+        ids: &[String],
+        tag: &str,
+        keys: &[String],
+        reversely: bool,
+    ) -> Result<(Vec<i64>, DataFrame)> {
+        let table = self.edge_table(tag)?;
+        let (adj, other) = if reversely {
+            (&table.inc, &table.src)
+        } else {
+            (&table.out, &table.dst)
+        };
+        let mut offsets = vec![0];
+        let mut index = vec![];
+        for id in ids.iter() {
+            if let Some(edges) = adj.get(id) {
+                index.extend_from_slice(edges);
+            }
+            offsets.push(index.len() as i64);
+        }
+
+        let mut df = DataFrame::default();
+        df.headers.push("dst".into());
+        df.columns.push(Series {
+            values: Some(self.ids(index.iter().map(|i| other[*i].clone()).collect())),
+        });
+
+        let index = index.into_iter().map(Some).collect::<Vec<_>>();
+        table.attrs.columns(tag, keys, &index, &mut df)?;
+        Ok((offsets, df))
+    }
+}
+
+fn node_id_fetch(id: Option<fetch_node_request::NodeId>) -> Result<String> {
+    match id {
+        Some(fetch_node_request::NodeId::AsInt(x)) => Ok(x.to_string()),
+        Some(fetch_node_request::NodeId::AsStr(x)) => Ok(x),
+        None => Err(BackendError::invalid_argument("empty node_id").into()),
+    }
+}
+
+fn node_id_neighbors(id: Option<fetch_neighbors_request::NodeId>) -> Result<String> {
+    match id {
+        Some(fetch_neighbors_request::NodeId::AsInt(x)) => Ok(x.to_string()),
+        Some(fetch_neighbors_request::NodeId::AsStr(x)) => Ok(x),
+        None => Err(BackendError::invalid_argument("empty node_id").into()),
+    }
+}
+
+#[mobc::async_trait]
+impl GraphBackend for MemoryGraphBackend {
+    async fn choice_nodes(&self, request: ChoiceNodesRequest) -> Result<DataFrame> {
+        let table = self.node_table(&request.tag)?;
+        let number = request.number.max(0) as usize;
+        let ids = sampler::uniform(table.ids.len(), number)
+            .into_iter()
+            .map(|i| table.ids[i].clone())
+            .collect();
+
+        Ok(DataFrame {
+            headers: vec!["id".into()],
+            columns: vec![Series {
+                values: Some(self.ids(ids)),
+            }],
+            comment: "".into(),
+        })
+    }
+
+    async fn fetch_node(&self, request: FetchNodeRequest) -> Result<DataFrame> {
+        let id = node_id_fetch(request.node_id)?;
+        let table = self.node_table(&request.tag)?;
+
+        // an unknown node gives empty columns
+        let index = table
+            .index
+            .get(&id)
+            .map(|i| vec![Some(*i)])
+            .unwrap_or_default();
+
+        let mut df = DataFrame::default();
+        table
+            .attrs
+            .columns(&request.tag, &request.keys, &index, &mut df)?;
+        Ok(df)
+    }
+
+    async fn fetch_neighbors(&self, request: FetchNeighborsRequest) -> Result<DataFrame> {
+        let id = node_id_neighbors(request.node_id)?;
+        let (_, df) = self.neighbors(&[id], &request.tag, &request.keys, request.reversely)?;
+        Ok(df)
+    }
+
+    async fn fetch_node_batch(&self, request: FetchNodeBatchRequest) -> Result<DataFrame> {
+        let ids = Self::node_ids(request.node_ids);
+        self.nodes(&ids, &request.tag, &request.keys)
+    }
+
+    async fn fetch_neighbors_batch(
+        &self,
+        request: FetchNeighborsBatchRequest,
+    ) -> Result<DataFrame> {
+        let ids = Self::node_ids(request.node_ids);
+        let (offsets, mut df) =
+            self.neighbors(&ids, &request.tag, &request.keys, request.reversely)?;
+        df.headers.insert(0, "offsets".into());
+        df.columns.insert(
+            0,
+            Series {
+                values: Some(series::Values::Int64Values(series::Int64Series {
+                    data: offsets,
+                })),
+            },
+        );
+        Ok(df)
+    }
+
+    async fn start_scan_edges(&self, request: StartScanEdgesRequest) -> Result<String> {
+        let table = self.edge_table(&request.tag)?;
+        let times = table
+            .attrs
+            .keys
+            .get(&request.time_field)
+            .and_then(|c| c.as_f64())
+            .ok_or_else(|| {
+                BackendError::invalid_argument(format!(
+                    "invalid time field: {}",
+                    request.time_field
+                ))
+            })?;
+
+        let sta = request.start_time as f64;
+        let end = sta + request.duration as f64;
+        let mut index = (0..times.len())
+            .filter(|i| times[*i] >= sta && times[*i] < end)
+            .collect::<Vec<_>>();
+        index.sort_by(|a, b| {
+            times[*a]
+                .partial_cmp(&times[*b])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        let iterator_token = uuid::Uuid::new_v4().to_string();
+        let scan = Scan {
+            tag: request.tag,
+            index,
+            cursor: 0,
+        };
+        self.scans
+            .lock()
+            .map_err(|err| anyhow!("{}", err))?
+            .insert(iterator_token.clone(), scan);
+        Ok(iterator_token)
+    }
+
+    async fn get_next_edges(&self, request: GetNextEdgesRequest) -> Result<DataFrame> {
+        let mut scans = self.scans.lock().map_err(|err| anyhow!("{}", err))?;
+        let scan = scans.get_mut(&request.iterator_token).ok_or_else(|| {
+            BackendError::not_found(format!("unknown iterator: {}", request.iterator_token))
+        })?;
+
+        let table = self.edge_table(&scan.tag)?;
+        let number = request.number.max(0) as usize;
+        let end = scan.index.len().min(scan.cursor + number);
+        let index = scan.index[scan.cursor..end].to_vec();
+        scan.cursor = end;
+
+        let mut df = DataFrame::default();
+        df.headers.push("src".into());
+        df.columns.push(Series {
+            values: Some(self.ids(index.iter().map(|i| table.src[*i].clone()).collect())),
+        });
+        df.headers.push("dst".into());
+        df.columns.push(Series {
+            values: Some(self.ids(index.iter().map(|i| table.dst[*i].clone()).collect())),
+        });
+
+        let index = index.into_iter().map(Some).collect::<Vec<_>>();
+        table
+            .attrs
+            .columns(&scan.tag, &table.attrs.order, &index, &mut df)?;
+        Ok(df)
+    }
+
+    async fn finish_scan_edges(&self, request: FinishScanEdgesRequest) -> Result<()> {
+        self.scans
+            .lock()
+            .map_err(|err| anyhow!("{}", err))?
+            .remove(&request.iterator_token);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::error_kind;
+    use crate::bindgen::imports::StorageErrorKind;
+
+    const USERS: &str = "
+        # users
+        id,age,score,name
+        1,30,0.5,ann
+        2,40,1,bob
+        3,50,1.5,cid
+        4,60,2,dan
+    ";
+
+    const FOLLOWS: &str = "
+        src,dst,time
+        1,2,100
+        1,3,300
+        4,2,200
+        3,4,400
+    ";
+
+    fn load(files: &[(&str, &str)]) -> Result<MemoryGraphBackend> {
+        let dir = std::env::temp_dir().join(format!("wart-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir(&dir).unwrap();
+        for (name, text) in files.iter() {
+            std::fs::write(dir.join(name), text).unwrap();
+        }
+        let graph = MemoryGraphBackend::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        graph
+    }
+
+    fn graph() -> MemoryGraphBackend {
+        load(&[("user.nodes", USERS), ("follow.edges", FOLLOWS)]).unwrap()
+    }
+
+    fn ints(df: &DataFrame, key: &str) -> Vec<i64> {
+        let i = df.headers.iter().position(|h| h == key).unwrap();
+        match &df.columns[i].values {
+            Some(series::Values::Int64Values(x)) => x.data.clone(),
+            values => panic!("{} is not int64: {:?}", key, values),
+        }
+    }
+
+    fn keys(keys: &[&str]) -> Vec<String> {
+        keys.iter().map(|k| k.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_errors() {
+        let err = load(&[("user.nodes", "id,age\n1,30\n2,40,50\n")])
+            .err()
+            .unwrap();
+        assert!(err.to_string().ends_with("user.nodes:3: expected 2 fields"));
+
+        let err = load(&[("follow.edges", "src\n1\n")]).err().unwrap();
+        assert!(err.to_string().ends_with("too few columns in header"));
+
+        let err = load(&[("user.nodes", "# nothing\n")]).err().unwrap();
+        assert!(err.to_string().ends_with("missing header"));
+    }
+
+    #[tokio::test]
+    async fn column_types_and_unknown_names() {
+        let graph = graph();
+        let request = FetchNodeBatchRequest {
+            node_ids: Some(Series {
+                values: Some(series::Values::Int64Values(series::Int64Series {
+                    data: vec![1, 2],
+                })),
+            }),
+            tag: "user".into(),
+            keys: keys(&["age", "score", "name"]),
+            ..Default::default()
+        };
+        let df = graph.fetch_node_batch(request.clone()).await.unwrap();
+        assert_eq!(df.headers, ["user.age", "user.score", "user.name"]);
+        assert_eq!(ints(&df, "user.age"), [30, 40]);
+        assert!(matches!(
+            &df.columns[1].values,
+            Some(series::Values::Float64Values(x)) if x.data == [0.5, 1.0]
+        ));
+        assert!(matches!(
+            &df.columns[2].values,
+            Some(series::Values::StringValues(x)) if x.data == ["ann", "bob"]
+        ));
+
+        let unknown_tag = FetchNodeBatchRequest {
+            tag: "item".into(),
+            ..request.clone()
+        };
+        let err = graph.fetch_node_batch(unknown_tag).await.unwrap_err();
+        assert!(matches!(error_kind(&err), StorageErrorKind::NotFound));
+
+        let unknown_key = FetchNodeBatchRequest {
+            keys: keys(&["height"]),
+            ..request
+        };
+        let err = graph.fetch_node_batch(unknown_key).await.unwrap_err();
+        assert!(matches!(error_kind(&err), StorageErrorKind::NotFound));
+
+        let unknown_edge_type = FetchNeighborsRequest {
+            node_id: Some(fetch_neighbors_request::NodeId::AsInt(1)),
+            tag: "like".into(),
+            ..Default::default()
+        };
+        let err = graph.fetch_neighbors(unknown_edge_type).await.unwrap_err();
+        assert!(matches!(error_kind(&err), StorageErrorKind::NotFound));
+    }
+
+    #[tokio::test]
+    async fn unknown_node_ids() {
+        let graph = graph();
+
+        // FetchNode answers with empty columns, the batch with defaults
+        let request = FetchNodeRequest {
+            node_id: Some(fetch_node_request::NodeId::AsInt(9)),
+            tag: "user".into(),
+            keys: keys(&["age"]),
+            ..Default::default()
+        };
+        let df = graph.fetch_node(request).await.unwrap();
+        assert!(ints(&df, "user.age").is_empty());
+
+        let request = FetchNodeBatchRequest {
+            node_ids: Some(Series {
+                values: Some(series::Values::Int64Values(series::Int64Series {
+                    data: vec![9, 2],
+                })),
+            }),
+            tag: "user".into(),
+            keys: keys(&["age"]),
+            ..Default::default()
+        };
+        let df = graph.fetch_node_batch(request).await.unwrap();
+        assert_eq!(ints(&df, "user.age"), [0, 40]);
+
+        let request = FetchNeighborsRequest {
+            node_id: Some(fetch_neighbors_request::NodeId::AsInt(9)),
+            tag: "follow".into(),
+            keys: keys(&["time"]),
+            ..Default::default()
+        };
+        let df = graph.fetch_neighbors(request).await.unwrap();
+        assert!(ints(&df, "dst").is_empty());
+        assert!(ints(&df, "follow.time").is_empty());
+    }
+
+    #[tokio::test]
+    async fn neighbors_both_ways() {
+        let graph = graph();
+        let request = FetchNeighborsRequest {
+            node_id: Some(fetch_neighbors_request::NodeId::AsInt(2)),
+            tag: "follow".into(),
+            keys: keys(&["time"]),
+            reversely: true,
+            ..Default::default()
+        };
+        let df = graph.fetch_neighbors(request).await.unwrap();
+        assert_eq!(ints(&df, "dst"), [1, 4]);
+        assert_eq!(ints(&df, "follow.time"), [100, 200]);
+
+        let request = FetchNeighborsBatchRequest {
+            node_ids: Some(Series {
+                values: Some(series::Values::Int64Values(series::Int64Series {
+                    data: vec![1, 2, 3],
+                })),
+            }),
+            tag: "follow".into(),
+            keys: keys(&["time"]),
+            ..Default::default()
+        };
+        let df = graph.fetch_neighbors_batch(request.clone()).await.unwrap();
+        assert_eq!(ints(&df, "offsets"), [0, 2, 2, 3]);
+        assert_eq!(ints(&df, "dst"), [2, 3, 4]);
+
+        let empty = FetchNeighborsBatchRequest {
+            node_ids: Some(Series {
+                values: Some(series::Values::Int64Values(Default::default())),
+            }),
+            ..request
+        };
+        let df = graph.fetch_neighbors_batch(empty).await.unwrap();
+        assert_eq!(ints(&df, "offsets"), [0]);
+        assert!(ints(&df, "dst").is_empty());
+        assert!(ints(&df, "follow.time").is_empty());
+    }
+
+    #[tokio::test]
+    async fn scan_paging() {
+        let graph = graph();
+        let request = StartScanEdgesRequest {
+            tag: "follow".into(),
+            time_field: "time".into(),
+            start_time: 100,
+            duration: 300,
+            ..Default::default()
+        };
+        let iterator_token = graph.start_scan_edges(request).await.unwrap();
+
+        let next = GetNextEdgesRequest {
+            iterator_token: iterator_token.clone(),
+            number: 2,
+        };
+        let df = graph.get_next_edges(next.clone()).await.unwrap();
+        assert_eq!(ints(&df, "follow.time"), [100, 200]);
+        assert_eq!(ints(&df, "src"), [1, 4]);
+        let df = graph.get_next_edges(next.clone()).await.unwrap();
+        assert_eq!(ints(&df, "follow.time"), [300]);
+        let df = graph.get_next_edges(next.clone()).await.unwrap();
+        assert!(ints(&df, "follow.time").is_empty());
+
+        graph
+            .finish_scan_edges(FinishScanEdgesRequest { iterator_token })
+            .await
+            .unwrap();
+        let err = graph.get_next_edges(next).await.unwrap_err();
+        assert!(matches!(error_kind(&err), StorageErrorKind::NotFound));
+
+        let request = StartScanEdgesRequest {
+            tag: "follow".into(),
+            time_field: "dst".into(),
+            ..Default::default()
+        };
+        let err = graph.start_scan_edges(request).await.unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+    }
+}
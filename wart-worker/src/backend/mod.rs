@@ -1,5 +1,15 @@
+mod embedded;
+mod error;
+mod graph;
+mod kv;
+mod memory;
 mod redis;
 mod storage;
 
-pub use self::redis::RedisConnectionManager;
-pub use storage::StorageConnectionManager;
+pub use self::redis::RedisKvStore;
+pub use embedded::EmbeddedKvStore;
+pub use error::{error_kind, BackendError};
+pub use graph::{GraphBackend, FANOUT_CONCURRENCY};
+pub use kv::{unix_now, KvStore, Session, SessionStats, SessionStore, StoreUpdate, KV_HISTORY};
+pub use memory::MemoryGraphBackend;
+pub use storage::StorageGraphBackend;
@@ -1,20 +1,39 @@
-use redis::aio::Connection;
-use redis::{Client, RedisError};
+use crate::bindgen::imports::MergeType;
+use crate::config::{ClientTlsConfig, PoolConfig};
+use crate::tls::{self, RedisTls};
+
+use super::{
+    unix_now, BackendError, KvStore, Session, SessionStats, SessionStore, StoreUpdate, KV_HISTORY,
+};
+
+use anyhow::Result;
+use lazy_static::lazy_static;
+use redis::aio::{AsyncStream, Connection};
+use redis::{AsyncCommands, Client, RedisConnectionInfo, RedisError};
+use tokio::net::TcpStream;
 
 use std::net::SocketAddr;
+use std::pin::Pin;
+
+/// Deadlines of the sessions with an `idle_ttl`, a sorted set of tokens.
+const EXPIRY_KEY: &str = "wart:expiry";
 
 pub struct RedisConnectionManager {
     client: Client,
+    addr: SocketAddr,
+    tls: Option<RedisTls>,
 }
 
 impl RedisConnectionManager {
-    pub fn new(addr: SocketAddr) -> Self {
+    pub fn new(addr: SocketAddr, tls: Option<RedisTls>) -> Self {
         let url = match addr {
             SocketAddr::V4(x) => format!("redis://{}:{}/", x.ip(), x.port()),
             SocketAddr::V6(x) => format!("redis://[{}]:{}/", x.ip(), x.port()),
         };
         Self {
             client: Client::open(url).unwrap(),
+            addr,
+            tls,
         }
     }
 }
@@ -25,12 +44,880 @@ impl mobc::Manager for RedisConnectionManager {
     type Error = RedisError;
 
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
-        let con = self.client.get_async_connection().await?;
-        Ok(con)
+        let tls = match &self.tls {
+            Some(tls) => tls,
+            None => return self.client.get_async_connection().await,
+        };
+        let tcp = TcpStream::connect(self.addr).await?;
+        tcp.set_nodelay(true)?;
+        let stream = tls.connector.connect(tls.domain.clone(), tcp).await?;
+        let stream: Pin<Box<dyn AsyncStream + Send + Sync>> = Box::pin(stream);
+        Connection::new(&RedisConnectionInfo::default(), stream).await
     }
 
     async fn check(&self, mut con: Self::Connection) -> Result<Self::Connection, Self::Error> {
-        let _: () = redis::cmd("PING").query_async(&mut con).await?;
+        ping(&mut con).await?;
         Ok(con)
     }
 }
+
+async fn ping(con: &mut Connection) -> redis::RedisResult<()> {
+    redis::cmd("PING").query_async(con).await
+}
+
+/*
+KEYS[1]: "wart:store:{token}", the base hash written by UpdateStore
+KEYS[2]: "wart:store:{token}:epochs", the epochs a field was written in,
+         newest first and comma separated
+KEYS[3]: "wart:store:{token}:values", the value of a field at an epoch
+         under "{ep}:{field}"
+KEYS[4]: "wart:session:{token}", for the current epoch of the session
+
+The value of a field at epoch `e` is the one of the newest version with
+`ep <= e`, or the one in the base hash otherwise. Every lookup costs two
+HGETs whatever the epoch.
+
+Runs more than `KV_HISTORY` epochs behind the session are refused with a
+STALE error, so `update_kv` drops the versions only they could read.
+*/
+const LUA_KV_BASE: &str = "
+    local based = KEYS[1]
+    local index = KEYS[2]
+    local values = KEYS[3]
+    local session = KEYS[4]
+
+    -- the epoch of the session, the one of the run once it is closed
+    local function current(epoch)
+        return tonumber(redis.call('HGET', session, 'epoch') or epoch)
+    end
+
+    local function stale(epoch, now, history)
+        return redis.error_reply('STALE epoch ' .. epoch .. ' is more than ' ..
+            history .. ' epochs behind the session at ' .. now)
+    end
+
+    local function versions(field)
+        local eps = {}
+        local list = redis.call('HGET', index, field)
+        if list then
+            for ep in string.gmatch(list, '%d+') do
+                eps[#eps + 1] = ep
+            end
+        end
+        return eps
+    end
+
+    local function lookup(eps, epoch, field)
+        for _, ep in ipairs(eps) do
+            if tonumber(ep) <= epoch then
+                return redis.call('HGET', values, ep .. ':' .. field)
+            end
+        end
+        return redis.call('HGET', based, field)
+    end
+";
+
+lazy_static! {
+    /*
+    ARGV[1]: epoch
+    ARGV[2]: epochs a run may be behind the session
+    ARGV[3]: default value
+    ARGV[4..]: fields
+
+    Read-only.
+    */
+    static ref REDIS_QUERY_KV: redis::Script = {
+        let lua_script = format!(
+            "{} {}",
+            LUA_KV_BASE,
+            "
+            local epoch = tonumber(ARGV[1])
+            local history = tonumber(ARGV[2])
+            local defva = ARGV[3]
+            local now = current(epoch)
+            if epoch + history < now then
+                return stale(epoch, now, history)
+            end
+
+            local result = {}
+            for i = 4, #ARGV do
+                local value = lookup(versions(ARGV[i]), epoch, ARGV[i])
+                if not value then
+                    value = defva
+                end
+                result[#result + 1] = value
+            end
+            return result
+        "
+        );
+        redis::Script::new(&lua_script)
+    };
+
+    /*
+    ARGV[1]: epoch of the run
+    ARGV[2]: kind of values, 'int', 'flt', 'bol' or 'txt'
+    ARGV[3]: merge type, 'add' or 'mov'
+    ARGV[4]: epochs a run may be behind the session
+    ARGV[5..5+n]: fields
+    ARGV[5+n..5+2n]: values
+
+    Writing into epoch + 1 keeps the updates invisible to the runs of the
+    current epoch. Of the versions up to the oldest epoch a run may still be
+    at, only the newest is read, the older ones are dropped.
+    */
+    static ref REDIS_UPDATE_KV: redis::Script = {
+        let lua_script = format!(
+            "{} {}",
+            LUA_KV_BASE,
+            "
+            local epoch = tonumber(ARGV[1]) + 1
+            local target = string.format('%d', epoch)
+            local kind = ARGV[2]
+            local merge = ARGV[3]
+            local history = tonumber(ARGV[4])
+            local n = (#ARGV - 4) / 2
+
+            local now = current(epoch - 1)
+            if epoch - 1 + history < now then
+                return stale(epoch - 1, now, history)
+            end
+            local floor = now - history
+
+            if merge ~= 'add' and merge ~= 'mov' then
+                return redis.error_reply('unknown merge type: ' .. merge)
+            end
+            if kind ~= 'int' and kind ~= 'flt' and kind ~= 'bol' and kind ~= 'txt' then
+                return redis.error_reply('unknown kind: ' .. kind)
+            end
+
+            for i = 1, n do
+                local field = ARGV[4 + i]
+                local argum = ARGV[4 + n + i]
+                local key = target .. ':' .. field
+
+                local eps = versions(field)
+                local at = #eps + 1
+                for j, ep in ipairs(eps) do
+                    if tonumber(ep) <= epoch then
+                        at = j
+                        break
+                    end
+                end
+                local exists = eps[at] ~= nil and tonumber(eps[at]) == epoch
+
+                if merge == 'mov' then
+                    redis.call('HSET', values, key, argum)
+                else
+                    local value = lookup(eps, epoch, field)
+                    if kind == 'int' then
+                        if value then
+                            redis.call('HSET', values, key, value)
+                        end
+                        redis.call('HINCRBY', values, key, argum)
+                    elseif kind == 'flt' then
+                        if value then
+                            redis.call('HSET', values, key, value)
+                        end
+                        redis.call('HINCRBYFLOAT', values, key, argum)
+                    elseif kind == 'bol' then
+                        if value == '1' or argum == '1' then
+                            redis.call('HSET', values, key, '1')
+                        else
+                            redis.call('HSET', values, key, '0')
+                        end
+                    else
+                        redis.call('HSET', values, key, (value or '') .. argum)
+                    end
+                end
+
+                if not exists then
+                    table.insert(eps, at, target)
+                end
+                local kept = #eps
+                for j, ep in ipairs(eps) do
+                    if tonumber(ep) <= floor then
+                        kept = j
+                        break
+                    end
+                end
+                while #eps > kept do
+                    local ep = table.remove(eps)
+                    redis.call('HDEL', values, ep .. ':' .. field)
+                end
+                redis.call('HSET', index, field, table.concat(eps, ','))
+            end
+            return n
+        "
+        );
+        redis::Script::new(&lua_script)
+    };
+
+    /*
+    KEYS[1]: "wart:session:{token}"
+    KEYS[2]: "wart:expiry"
+    ARGV[1]: now
+    ARGV[2]: token
+
+    Returns the new deadline, 0 if the session never expires or -1 if there
+    is no such session.
+    */
+    static ref REDIS_KEEP_ALIVE: redis::Script = redis::Script::new(
+        "
+        if redis.call('EXISTS', KEYS[1]) == 0 then
+            return -1
+        end
+        redis.call('HSET', KEYS[1], 'last_used', ARGV[1])
+        local ttl = redis.call('HGET', KEYS[1], 'idle_ttl')
+        if not ttl then
+            return 0
+        end
+        ttl = tonumber(ttl)
+        if ttl == 0 then
+            return 0
+        end
+        local deadline = tonumber(ARGV[1]) + ttl
+        redis.call('ZADD', KEYS[2], deadline, ARGV[2])
+        return deadline
+    "
+    );
+
+    /*
+    KEYS[1]: "wart:session:{token}"
+    KEYS[2]: "wart:store:{token}"
+    KEYS[3]: "wart:store:{token}:epochs"
+    KEYS[4]: "wart:store:{token}:values"
+    KEYS[5]: "wart:expiry"
+    ARGV[1]: token
+
+    Takes a constant number of commands whatever the epoch, UNLINK frees the
+    hashes in the background.
+    */
+    static ref REDIS_CLOSE_SESSION: redis::Script = redis::Script::new(
+        "
+        redis.call('UNLINK', KEYS[1], KEYS[2], KEYS[3], KEYS[4])
+        redis.call('ZREM', KEYS[5], ARGV[1])
+        return 0
+    "
+    );
+
+    /*
+    KEYS[1]: "wart:session:{token}"
+    KEYS[2]: "wart:store:{token}"
+    KEYS[3]: "wart:store:{token}:epochs"
+
+    Returns the size of the module and the number of fields, nil if there is
+    no such session.
+    */
+    static ref REDIS_SESSION_STATS: redis::Script = redis::Script::new(
+        "
+        if redis.call('EXISTS', KEYS[1]) == 0 then
+            return false
+        end
+        local fields = redis.call('HLEN', KEYS[2]) + redis.call('HLEN', KEYS[3])
+        return {redis.call('HSTRLEN', KEYS[1], 'module'), fields}
+    "
+    );
+}
+
+/// The keys of the KV store of a session, see `LUA_KV_BASE`. Scripts get
+/// every key they touch in KEYS, as Redis Cluster requires.
+fn store_keys(token: &str) -> [String; 3] {
+    [
+        format!("wart:store:{}", token),
+        format!("wart:store:{}:epochs", token),
+        format!("wart:store:{}:values", token),
+    ]
+}
+
+/// Reports the STALE replies of the KV scripts as invalid arguments.
+fn stale(err: RedisError) -> anyhow::Error {
+    match err.code() {
+        Some("STALE") => {
+            BackendError::invalid_argument(err.detail().unwrap_or("stale epoch")).into()
+        }
+        _ => err.into(),
+    }
+}
+
+/// Keeps sessions in "wart:session:{token}" and the KV stores in the
+/// hashes of `store_keys`.
+pub struct RedisKvStore {
+    pool: mobc::Pool<RedisConnectionManager>,
+}
+
+impl RedisKvStore {
+    pub fn new(addr: SocketAddr, pool: &PoolConfig, tls: Option<&ClientTlsConfig>) -> Result<Self> {
+        let tls = tls.map(tls::redis).transpose()?;
+        let manager = RedisConnectionManager::new(addr, tls);
+        let pool = pool.builder().build(manager);
+        Ok(Self { pool })
+    }
+
+    async fn connection(&self) -> Result<mobc::Connection<RedisConnectionManager>> {
+        self.pool.get().await.map_err(|err| {
+            BackendError::unavailable(format!("redis connection pool: {}", err)).into()
+        })
+    }
+}
+
+#[mobc::async_trait]
+impl SessionStore for RedisKvStore {
+    #[tracing::instrument(name = "redis.open_session", skip_all)]
+    async fn open_session(&self, session: &Session, module: &[u8]) -> Result<()> {
+        let mut con = self.connection().await?;
+
+        let key = format!("wart:session:{}", session.token);
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .hset(&key, "token", &session.token)
+            .ignore()
+            .hset(&key, "space_name", &session.space_name)
+            .ignore()
+            .hset(&key, "epoch", session.epoch)
+            .ignore()
+            .hset(&key, "module", module)
+            .ignore()
+            .hset(&key, "module_hash", session.module_hash)
+            .ignore()
+            .hset(&key, "ex_timeout", session.ex_timeout)
+            .ignore()
+            .hset(&key, "parallel", session.parallel)
+            .ignore()
+            .hset(&key, "max_fuel", session.max_fuel)
+            .ignore()
+            .hset(&key, "limits", serde_json::to_string(&session.limits)?)
+            .ignore()
+            .hset(&key, "idle_ttl", session.idle_ttl)
+            .ignore()
+            .hset(&key, "created_at", session.created_at)
+            .ignore()
+            .hset(&key, "last_used", session.last_used)
+            .ignore()
+            .hset(&key, "owner", &session.owner)
+            .ignore();
+        if session.idle_ttl > 0 {
+            pipe.zadd(EXPIRY_KEY, &session.token, unix_now() + session.idle_ttl)
+                .ignore();
+        }
+        let _: () = pipe.query_async(&mut *con).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "redis.get_session", skip_all)]
+    async fn get_session(&self, token: &str) -> Result<Session> {
+        let mut con = self.connection().await?;
+
+        let key = format!("wart:session:{}", token);
+        if !con.exists::<_, bool>(&key).await? {
+            Err(BackendError::not_found(format!("no session: {}", token)))?;
+        }
+
+        // sessions opened by older workers have no module_hash, max_fuel,
+        // limits, idle_ttl, timestamps nor owner
+        let (
+            space_name,
+            epoch,
+            ex_timeout,
+            module_hash,
+            parallel,
+            max_fuel,
+            limits,
+            idle_ttl,
+            created_at,
+            last_used,
+            owner,
+        ): (
+            String,
+            u64,
+            u64,
+            Option<u64>,
+            usize,
+            Option<u64>,
+            Option<String>,
+            Option<u64>,
+            Option<u64>,
+            Option<u64>,
+            Option<String>,
+        ) = redis::pipe()
+            .atomic()
+            .hget(&key, "space_name")
+            .hget(&key, "epoch")
+            .hget(&key, "ex_timeout")
+            .hget(&key, "module_hash")
+            .hget(&key, "parallel")
+            .hget(&key, "max_fuel")
+            .hget(&key, "limits")
+            .hget(&key, "idle_ttl")
+            .hget(&key, "created_at")
+            .hget(&key, "last_used")
+            .hget(&key, "owner")
+            .query_async(&mut *con)
+            .await?;
+
+        Ok(Session {
+            token: token.into(),
+            space_name,
+            epoch,
+            module_hash: module_hash.unwrap_or(0),
+            ex_timeout,
+            parallel,
+            max_fuel: max_fuel.unwrap_or(0),
+            limits: match limits {
+                Some(limits) => serde_json::from_str(&limits)?,
+                None => Default::default(),
+            },
+            idle_ttl: idle_ttl.unwrap_or(0),
+            created_at: created_at.unwrap_or(0),
+            last_used: last_used.unwrap_or(0),
+            owner: owner.unwrap_or_default(),
+        })
+    }
+
+    #[tracing::instrument(name = "redis.get_module", skip_all)]
+    async fn get_module(&self, token: &str) -> Result<Vec<u8>> {
+        let mut con = self.connection().await?;
+
+        let key = format!("wart:session:{}", token);
+        let module: Option<Vec<u8>> = con.hget(&key, "module").await?;
+        module.ok_or_else(|| BackendError::not_found(format!("no session: {}", token)).into())
+    }
+
+    #[tracing::instrument(name = "redis.list_sessions", skip_all)]
+    async fn list_sessions(&self) -> Result<Vec<String>> {
+        let mut con = self.connection().await?;
+
+        let mut tokens = vec![];
+        let mut keys: redis::AsyncIter<String> = con.scan_match("wart:session:*").await?;
+        while let Some(key) = keys.next_item().await {
+            if let Some(token) = key.strip_prefix("wart:session:") {
+                tokens.push(token.to_string());
+            }
+        }
+        Ok(tokens)
+    }
+
+    #[tracing::instrument(name = "redis.session_stats", skip_all)]
+    async fn session_stats(&self, token: &str) -> Result<SessionStats> {
+        let mut con = self.connection().await?;
+
+        let [based, index, _] = store_keys(token);
+        let mut invocation = REDIS_SESSION_STATS.prepare_invoke();
+        invocation
+            .key(format!("wart:session:{}", token))
+            .key(based)
+            .key(index);
+
+        let stats: Option<(u64, u64)> = invocation.invoke_async(&mut *con).await?;
+        let (module_size, kv_fields) =
+            stats.ok_or_else(|| BackendError::not_found(format!("no session: {}", token)))?;
+        Ok(SessionStats {
+            module_size,
+            kv_fields,
+        })
+    }
+
+    #[tracing::instrument(name = "redis.increment_epoch", skip_all)]
+    async fn increment_epoch(&self, token: &str) -> Result<u64> {
+        let mut con = self.connection().await?;
+
+        let key = format!("wart:session:{}", token);
+        let (epoch,): (u64,) = redis::pipe()
+            .hincr(&key, "epoch", 1u64)
+            .query_async(&mut *con)
+            .await?;
+        Ok(epoch)
+    }
+
+    #[tracing::instrument(name = "redis.close_session", skip_all)]
+    async fn close_session(&self, token: &str) -> Result<()> {
+        let mut con = self.connection().await?;
+
+        let [based, index, values] = store_keys(token);
+        let mut invocation = REDIS_CLOSE_SESSION.prepare_invoke();
+        invocation
+            .key(format!("wart:session:{}", token))
+            .key(based)
+            .key(index)
+            .key(values)
+            .key(EXPIRY_KEY)
+            .arg(token);
+
+        let _: () = invocation.invoke_async(&mut *con).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "redis.keep_alive", skip_all)]
+    async fn keep_alive(&self, token: &str) -> Result<u64> {
+        let mut con = self.connection().await?;
+
+        let mut invocation = REDIS_KEEP_ALIVE.prepare_invoke();
+        invocation
+            .key(format!("wart:session:{}", token))
+            .key(EXPIRY_KEY)
+            .arg(unix_now())
+            .arg(token);
+
+        let deadline: i64 = invocation.invoke_async(&mut *con).await?;
+        if deadline < 0 {
+            Err(BackendError::not_found(format!("no session: {}", token)))?;
+        }
+        Ok(deadline as u64)
+    }
+
+    #[tracing::instrument(name = "redis.expired_sessions", skip_all)]
+    async fn expired_sessions(&self, now: u64, limit: usize) -> Result<Vec<String>> {
+        let mut con = self.connection().await?;
+
+        let tokens: Vec<String> = con
+            .zrangebyscore_limit(EXPIRY_KEY, "-inf", now, 0, limit as isize)
+            .await?;
+        Ok(tokens)
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let mut con = self.connection().await?;
+        ping(&mut con).await?;
+        Ok(())
+    }
+
+    async fn pool_state(&self) -> Option<mobc::State> {
+        Some(self.pool.state().await)
+    }
+}
+
+#[mobc::async_trait]
+impl KvStore for RedisKvStore {
+    #[tracing::instrument(name = "redis.query_kv", skip_all)]
+    async fn query_kv(
+        &self,
+        token: &str,
+        epoch: u64,
+        fields: &[&str],
+        defva: &str,
+    ) -> Result<Vec<String>> {
+        let mut con = self.connection().await?;
+
+        let [based, index, values] = store_keys(token);
+        let mut invocation = REDIS_QUERY_KV.prepare_invoke();
+        invocation
+            .key(based)
+            .key(index)
+            .key(values)
+            .key(format!("wart:session:{}", token))
+            .arg(epoch)
+            .arg(KV_HISTORY)
+            .arg(defva)
+            .arg(fields);
+
+        let val: Vec<String> = invocation.invoke_async(&mut *con).await.map_err(stale)?;
+        Ok(val)
+    }
+
+    #[tracing::instrument(name = "redis.update_kv", skip_all)]
+    async fn update_kv(
+        &self,
+        token: &str,
+        epoch: u64,
+        fields: &[&str],
+        argums: &[String],
+        kind: &str,
+        merge: MergeType,
+    ) -> Result<u64> {
+        let mut con = self.connection().await?;
+
+        let merge = match merge {
+            MergeType::Add => "add",
+            MergeType::Mov => "mov",
+        };
+
+        let [based, index, values] = store_keys(token);
+        let mut invocation = REDIS_UPDATE_KV.prepare_invoke();
+        invocation
+            .key(based)
+            .key(index)
+            .key(values)
+            .key(format!("wart:session:{}", token))
+            .arg(epoch)
+            .arg(kind)
+            .arg(merge)
+            .arg(KV_HISTORY)
+            .arg(fields)
+            .arg(argums);
+
+        let val: u64 = invocation.invoke_async(&mut *con).await.map_err(stale)?;
+        Ok(val)
+    }
+
+    #[tracing::instrument(name = "redis.update_store", skip_all)]
+    async fn update_store(
+        &self,
+        token: &str,
+        fields: &[String],
+        update: StoreUpdate<'_>,
+    ) -> Result<u64> {
+        let mut con = self.connection().await?;
+
+        if !con
+            .exists::<_, bool>(format!("wart:session:{}", token))
+            .await?
+        {
+            Err(BackendError::not_found(format!("no session: {}", token)))?;
+        }
+
+        let store_key = format!("wart:store:{}", token);
+        let mut ok_count = 0;
+        match update {
+            StoreUpdate::Del => {
+                for field in fields.iter() {
+                    match con.hdel::<_, _, ()>(&store_key, field).await {
+                        Ok(_) => ok_count += 1,
+                        Err(_) => break,
+                    }
+                }
+            }
+            StoreUpdate::Add(vals) => {
+                for (field, value) in fields.iter().zip(vals.iter()) {
+                    match con.hincr::<_, _, _, ()>(&store_key, field, *value).await {
+                        Ok(_) => ok_count += 1,
+                        Err(_) => break,
+                    }
+                }
+            }
+            StoreUpdate::Mov(vals) => {
+                for (field, value) in fields.iter().zip(vals.iter()) {
+                    match con.hset::<_, _, _, ()>(&store_key, field, value).await {
+                        Ok(_) => ok_count += 1,
+                        Err(_) => break,
+                    }
+                }
+            }
+        }
+        Ok(ok_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::error_kind;
+    use crate::bindgen::imports::StorageErrorKind;
+
+    /// The tests run against the Redis at `WART_TEST_REDIS`, e.g.
+    /// `redis://127.0.0.1:6379`, and are skipped unless it is set.
+    fn store() -> Option<RedisKvStore> {
+        let url = std::env::var("WART_TEST_REDIS").ok()?;
+        let addr = url
+            .trim_start_matches("redis://")
+            .trim_end_matches('/')
+            .parse()
+            .expect("WART_TEST_REDIS is not an ip:port");
+        let pool = PoolConfig {
+            max_open: 4,
+            max_idle: None,
+            get_timeout_ms: 0,
+        };
+        Some(RedisKvStore::new(addr, &pool, None).unwrap())
+    }
+
+    async fn open(store: &RedisKvStore, idle_ttl: u64) -> String {
+        let token = uuid::Uuid::new_v4().to_string();
+        let now = unix_now();
+        let session = Session {
+            token: token.clone(),
+            space_name: "test".into(),
+            epoch: 0,
+            module_hash: 1,
+            ex_timeout: 1000,
+            parallel: 1,
+            max_fuel: 0,
+            limits: Default::default(),
+            idle_ttl,
+            created_at: now,
+            last_used: now,
+            owner: String::new(),
+        };
+        store.open_session(&session, b"module").await.unwrap();
+        token
+    }
+
+    async fn exists(store: &RedisKvStore, key: &str) -> bool {
+        let mut con = store.connection().await.unwrap();
+        con.exists(key).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn epoch_versioned_kv() {
+        let store = match store() {
+            Some(store) => store,
+            None => return,
+        };
+        let token = open(&store, 0).await;
+
+        let base = ["a".to_string(), "s".to_string()];
+        let vals = ["10".to_string(), "x".to_string()];
+        let n = store
+            .update_store(&token, &base, StoreUpdate::Mov(&vals))
+            .await
+            .unwrap();
+        assert_eq!(n, 2);
+        let got = store.query_kv(&token, 0, &["a", "b"], "0").await.unwrap();
+        assert_eq!(got, ["10", "0"]);
+
+        // updates of epoch 0 are only visible from epoch 1 on
+        let argums = ["5".to_string(), "7".to_string()];
+        let n = store
+            .update_kv(&token, 0, &["a", "b"], &argums, "int", MergeType::Add)
+            .await
+            .unwrap();
+        assert_eq!(n, 2);
+        let got = store.query_kv(&token, 0, &["a", "b"], "0").await.unwrap();
+        assert_eq!(got, ["10", "0"]);
+        assert_eq!(store.increment_epoch(&token).await.unwrap(), 1);
+        let got = store.query_kv(&token, 1, &["a", "b"], "0").await.unwrap();
+        assert_eq!(got, ["15", "7"]);
+
+        // later epochs build on the newest version before them
+        let one = ["1".to_string()];
+        store
+            .update_kv(&token, 1, &["a"], &one, "int", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 1, &["a"], "0").await.unwrap();
+        assert_eq!(got, ["15"]);
+        let got = store.query_kv(&token, 9, &["a"], "0").await.unwrap();
+        assert_eq!(got, ["16"]);
+
+        store
+            .update_kv(&token, 1, &["a"], &one, "int", MergeType::Mov)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 2, &["a"], "0").await.unwrap();
+        assert_eq!(got, ["1"]);
+
+        let halves = ["0.5".to_string()];
+        for _ in 0..2 {
+            store
+                .update_kv(&token, 0, &["f"], &halves, "flt", MergeType::Add)
+                .await
+                .unwrap();
+        }
+        let got = store.query_kv(&token, 1, &["f"], "0").await.unwrap();
+        assert_eq!(got, ["1"]);
+
+        let y = ["y".to_string()];
+        store
+            .update_kv(&token, 0, &["s"], &y, "txt", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 1, &["s"], "").await.unwrap();
+        assert_eq!(got, ["xy"]);
+
+        let stats = store.session_stats(&token).await.unwrap();
+        assert_eq!(stats.module_size, 6);
+        assert_eq!(stats.kv_fields, 2 + 4);
+
+        store.close_session(&token).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn runs_behind_the_kv_history_are_refused() {
+        let store = match store() {
+            Some(store) => store,
+            None => return,
+        };
+        let token = open(&store, 0).await;
+
+        let one = ["1".to_string()];
+        for epoch in 0..=KV_HISTORY + 1 {
+            store
+                .update_kv(&token, epoch, &["c"], &one, "int", MergeType::Add)
+                .await
+                .unwrap();
+            store.increment_epoch(&token).await.unwrap();
+        }
+
+        // the session is at KV_HISTORY + 2, the run at 1 would lose its write
+        let err = store
+            .update_kv(&token, 1, &["c"], &one, "int", MergeType::Add)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+        let err = store.query_kv(&token, 1, &["c"], "0").await.unwrap_err();
+        assert!(matches!(
+            error_kind(&err),
+            StorageErrorKind::InvalidArgument
+        ));
+
+        // the oldest run still in reach reads and writes what it should
+        let got = store.query_kv(&token, 2, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["2"]);
+        store
+            .update_kv(&token, 2, &["c"], &one, "int", MergeType::Add)
+            .await
+            .unwrap();
+        let got = store.query_kv(&token, 3, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["4"]);
+        let got = store.query_kv(&token, 6, &["c"], "0").await.unwrap();
+        assert_eq!(got, ["6"]);
+
+        store.close_session(&token).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn keep_alive_and_expiry() {
+        let store = match store() {
+            Some(store) => store,
+            None => return,
+        };
+        let expiring = open(&store, 60).await;
+        let lasting = open(&store, 0).await;
+
+        let before = unix_now();
+        let deadline = store.keep_alive(&expiring).await.unwrap();
+        assert!(deadline >= before + 60);
+        assert_eq!(store.keep_alive(&lasting).await.unwrap(), 0);
+
+        let expired = store.expired_sessions(deadline, 1 << 20).await.unwrap();
+        assert!(expired.contains(&expiring));
+        assert!(!expired.contains(&lasting));
+        let expired = store.expired_sessions(before, 1 << 20).await.unwrap();
+        assert!(!expired.contains(&expiring));
+
+        let err = store.keep_alive("no-such-session").await.unwrap_err();
+        assert!(matches!(error_kind(&err), StorageErrorKind::NotFound));
+
+        store.close_session(&expiring).await.unwrap();
+        store.close_session(&lasting).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn close_unlinks_the_session() {
+        let store = match store() {
+            Some(store) => store,
+            None => return,
+        };
+        let token = open(&store, 60).await;
+        let argums = ["1".to_string()];
+        store
+            .update_kv(&token, 0, &["a"], &argums, "int", MergeType::Add)
+            .await
+            .unwrap();
+
+        store.close_session(&token).await.unwrap();
+        // closing twice is fine, the sweepers of several workers may race
+        store.close_session(&token).await.unwrap();
+
+        let err = store.get_session(&token).await.unwrap_err();
+        assert!(matches!(error_kind(&err), StorageErrorKind::NotFound));
+        for key in store_keys(&token) {
+            assert!(!exists(&store, &key).await, "{} left behind", key);
+        }
+        let expired = store.expired_sessions(u64::MAX, 1 << 20).await.unwrap();
+        assert!(!expired.contains(&token));
+        let got = store.query_kv(&token, 1, &["a"], "0").await.unwrap();
+        assert_eq!(got, ["0"]);
+    }
+}
@@ -1,21 +1,47 @@
 use crate::bindgen::*;
+use crate::config::{millis, ClientTlsConfig, PoolConfig, TimeoutsConfig};
+use crate::telemetry;
+use crate::tls;
+use crate::wasm::utils;
+
+use super::error::{self, BackendError};
+use super::{GraphBackend, FANOUT_CONCURRENCY};
 
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-use tonic::transport::{Channel, Error};
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use tonic::transport::{Channel, Endpoint, Error};
+use tonic::Request;
 use wart_storage_client::WartStorageClient;
 
 pub struct StorageConnectionManager {
-    pub url: String,
+    pub endpoint: Endpoint,
 }
 
 impl StorageConnectionManager {
-    pub fn new(addr: SocketAddr) -> Self {
+    pub fn new(
+        addr: SocketAddr,
+        timeouts: &TimeoutsConfig,
+        tls: Option<&ClientTlsConfig>,
+    ) -> Result<Self> {
+        let scheme = if tls.is_some() { "https" } else { "http" };
         let url = match addr {
-            SocketAddr::V4(x) => format!("http://{}:{}/", x.ip(), x.port()),
-            SocketAddr::V6(x) => format!("http://[{}]:{}/", x.ip(), x.port()),
+            SocketAddr::V4(x) => format!("{}://{}:{}/", scheme, x.ip(), x.port()),
+            SocketAddr::V6(x) => format!("{}://[{}]:{}/", scheme, x.ip(), x.port()),
         };
-        Self { url }
+        let mut endpoint = Endpoint::from_shared(url)?;
+        if let Some(tls) = tls {
+            endpoint = endpoint.tls_config(tls::storage(tls)?)?;
+        }
+        if let Some(timeout) = millis(timeouts.storage_connect_ms) {
+            endpoint = endpoint.connect_timeout(timeout);
+        }
+        if let Some(timeout) = millis(timeouts.storage_request_ms) {
+            endpoint = endpoint.timeout(timeout);
+        }
+        Ok(Self { endpoint })
     }
 }
 
@@ -25,7 +51,7 @@ impl mobc::Manager for StorageConnectionManager {
     type Error = Error;
 
     async fn connect(&self) -> Result<Self::Connection, Self::Error> {
-        let con = WartStorageClient::connect(self.url.clone()).await?;
+        let con = WartStorageClient::new(self.endpoint.connect().await?);
         Ok(con)
     }
 
@@ -33,3 +59,293 @@ impl mobc::Manager for StorageConnectionManager {
         Ok(con)
     }
 }
+
+/// Forwards every query to a `WartStorage` server.
+pub struct StorageGraphBackend {
+    pool: mobc::Pool<StorageConnectionManager>,
+
+    /// The endpoint of the pooled connections, for health checking.
+    endpoint: Endpoint,
+
+    /// Set once the server answers a batched query with `Unimplemented`, the
+    /// later ones go straight to the fan-out.
+    batch_unsupported: AtomicBool,
+}
+
+impl StorageGraphBackend {
+    pub fn new(
+        addr: SocketAddr,
+        pool: &PoolConfig,
+        timeouts: &TimeoutsConfig,
+        tls: Option<&ClientTlsConfig>,
+    ) -> Result<Self> {
+        let manager = StorageConnectionManager::new(addr, timeouts, tls)?;
+        let endpoint = manager.endpoint.clone();
+        let pool = pool.builder().build(manager);
+        Ok(Self {
+            pool,
+            endpoint,
+            batch_unsupported: AtomicBool::new(false),
+        })
+    }
+
+    /// Tonic clients multiplex requests over one channel, so a pooled client is
+    /// cloned and handed back to the pool right away.
+    async fn client(&self) -> Result<WartStorageClient<Channel>> {
+        let con =
+            self.pool.get().await.map_err(|err| {
+                BackendError::unavailable(format!("connection pool error: {}", err))
+            })?;
+        Ok((*con).clone())
+    }
+
+    /// Carries the trace of the current span, see `telemetry::inject`.
+    fn traced<T>(message: T) -> Request<T> {
+        let mut request = Request::new(message);
+        telemetry::inject(request.metadata_mut());
+        request
+    }
+
+    fn batch_supported(&self) -> bool {
+        !self.batch_unsupported.load(Ordering::Relaxed)
+    }
+
+    fn set_batch_unsupported(&self) {
+        if !self.batch_unsupported.swap(true, Ordering::Relaxed) {
+            log::warn!("storage server doesn't implement batched queries, falling back to fan-out");
+        }
+    }
+
+    /// Storage servers report failures in the response rather than the status.
+    fn check(error_code: i32, error_msg: String) -> Result<()> {
+        if error_code != 0 {
+            let kind = error::code_kind(tonic::Code::from_i32(error_code));
+            Err(BackendError::new(kind, error_msg))?;
+        }
+        Ok(())
+    }
+
+    fn into_data(response: tonic::Response<StorageResponse>) -> Result<DataFrame> {
+        let response = response.into_inner();
+        Self::check(response.error_code, response.error_msg)?;
+        response
+            .data
+            .ok_or_else(|| anyhow!("empty data in response"))
+    }
+
+    /// Emulates `FetchNodeBatch` with concurrent `FetchNode` calls for storage
+    /// servers that don't implement it.
+    async fn fetch_node_fanout(
+        client: WartStorageClient<Channel>,
+        request: FetchNodeBatchRequest,
+    ) -> Result<DataFrame> {
+        use fetch_node_request::NodeId;
+
+        let FetchNodeBatchRequest {
+            space_name,
+            node_ids,
+            tag,
+            keys,
+        } = request;
+
+        let node_ids = match node_ids.and_then(|s| s.values) {
+            Some(series::Values::Int64Values(x)) => {
+                x.data.into_iter().map(NodeId::AsInt).collect::<Vec<_>>()
+            }
+            Some(series::Values::StringValues(x)) => {
+                x.data.into_iter().map(NodeId::AsStr).collect::<Vec<_>>()
+            }
+            _ => vec![],
+        };
+
+        let frames = futures::stream::iter(node_ids.into_iter().map(|node_id| {
+            let mut client = client.clone();
+            let request = FetchNodeRequest {
+                space_name: space_name.clone(),
+                node_id: Some(node_id),
+                tag: tag.clone(),
+                keys: keys.clone(),
+            };
+            async move { client.fetch_node(Self::traced(request)).await }
+        }))
+        .buffered(FANOUT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        let mut data = vec![];
+        for frame in frames.into_iter() {
+            data.push(Self::into_data(frame?)?);
+        }
+
+        utils::concat_frames(data).ok_or_else(|| anyhow!("can't concat frames"))
+    }
+
+    /// Emulates `FetchNeighborsBatch` with concurrent `FetchNeighbors` calls for
+    /// storage servers that don't implement it.
+    async fn fetch_neighbors_fanout(
+        client: WartStorageClient<Channel>,
+        request: FetchNeighborsBatchRequest,
+    ) -> Result<DataFrame> {
+        use fetch_neighbors_request::NodeId;
+
+        let FetchNeighborsBatchRequest {
+            space_name,
+            node_ids,
+            tag,
+            keys,
+            reversely,
+        } = request;
+
+        let node_ids = node_ids.and_then(|s| s.values);
+        let dst = utils::empty_series(&node_ids);
+        let node_ids = match node_ids {
+            Some(series::Values::Int64Values(x)) => {
+                x.data.into_iter().map(NodeId::AsInt).collect::<Vec<_>>()
+            }
+            Some(series::Values::StringValues(x)) => {
+                x.data.into_iter().map(NodeId::AsStr).collect::<Vec<_>>()
+            }
+            _ => vec![],
+        };
+
+        let frames = futures::stream::iter(node_ids.into_iter().map(|node_id| {
+            let mut client = client.clone();
+            let request = FetchNeighborsRequest {
+                space_name: space_name.clone(),
+                node_id: Some(node_id),
+                tag: tag.clone(),
+                keys: keys.clone(),
+                reversely,
+            };
+            async move { client.fetch_neighbors(Self::traced(request)).await }
+        }))
+        .buffered(FANOUT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        let mut data = vec![];
+        for frame in frames.into_iter() {
+            data.push(Self::into_data(frame?)?);
+        }
+
+        utils::concat_neighbors(data, dst, &keys).ok_or_else(|| anyhow!("can't concat frames"))
+    }
+}
+
+#[mobc::async_trait]
+impl GraphBackend for StorageGraphBackend {
+    #[tracing::instrument(name = "storage.choice_nodes", skip_all)]
+    async fn choice_nodes(&self, request: ChoiceNodesRequest) -> Result<DataFrame> {
+        let response = self
+            .client()
+            .await?
+            .choice_nodes(Self::traced(request))
+            .await?;
+        Self::into_data(response)
+    }
+
+    #[tracing::instrument(name = "storage.fetch_node", skip_all)]
+    async fn fetch_node(&self, request: FetchNodeRequest) -> Result<DataFrame> {
+        let response = self
+            .client()
+            .await?
+            .fetch_node(Self::traced(request))
+            .await?;
+        Self::into_data(response)
+    }
+
+    #[tracing::instrument(name = "storage.fetch_neighbors", skip_all)]
+    async fn fetch_neighbors(&self, request: FetchNeighborsRequest) -> Result<DataFrame> {
+        let response = self
+            .client()
+            .await?
+            .fetch_neighbors(Self::traced(request))
+            .await?;
+        Self::into_data(response)
+    }
+
+    #[tracing::instrument(name = "storage.fetch_node_batch", skip_all)]
+    async fn fetch_node_batch(&self, request: FetchNodeBatchRequest) -> Result<DataFrame> {
+        let mut client = self.client().await?;
+        if !self.batch_supported() {
+            return Self::fetch_node_fanout(client, request).await;
+        }
+        match client.fetch_node_batch(Self::traced(request.clone())).await {
+            Ok(response) => Self::into_data(response),
+            Err(err) if err.code() == tonic::Code::Unimplemented => {
+                self.set_batch_unsupported();
+                Self::fetch_node_fanout(client, request).await
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[tracing::instrument(name = "storage.fetch_neighbors_batch", skip_all)]
+    async fn fetch_neighbors_batch(
+        &self,
+        request: FetchNeighborsBatchRequest,
+    ) -> Result<DataFrame> {
+        let mut client = self.client().await?;
+        if !self.batch_supported() {
+            return Self::fetch_neighbors_fanout(client, request).await;
+        }
+        match client
+            .fetch_neighbors_batch(Self::traced(request.clone()))
+            .await
+        {
+            Ok(response) => Self::into_data(response),
+            Err(err) if err.code() == tonic::Code::Unimplemented => {
+                self.set_batch_unsupported();
+                Self::fetch_neighbors_fanout(client, request).await
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    #[tracing::instrument(name = "storage.start_scan_edges", skip_all)]
+    async fn start_scan_edges(&self, request: StartScanEdgesRequest) -> Result<String> {
+        let response = self
+            .client()
+            .await?
+            .start_scan_edges(Self::traced(request))
+            .await?
+            .into_inner();
+        Self::check(response.error_code, response.error_msg)?;
+        Ok(response.iterator_token)
+    }
+
+    #[tracing::instrument(name = "storage.get_next_edges", skip_all)]
+    async fn get_next_edges(&self, request: GetNextEdgesRequest) -> Result<DataFrame> {
+        let response = self
+            .client()
+            .await?
+            .get_next_edges(Self::traced(request))
+            .await?
+            .into_inner();
+        Self::check(response.error_code, response.error_msg)?;
+        // an exhausted iterator may come back without any data
+        Ok(response.data.unwrap_or_default())
+    }
+
+    #[tracing::instrument(name = "storage.finish_scan_edges", skip_all)]
+    async fn finish_scan_edges(&self, request: FinishScanEdgesRequest) -> Result<()> {
+        let response = self
+            .client()
+            .await?
+            .finish_scan_edges(Self::traced(request))
+            .await?
+            .into_inner();
+        Self::check(response.error_code, response.error_msg)
+    }
+
+    /// Pooled channels reconnect lazily, so a fresh connection is made to
+    /// tell whether the server is up.
+    async fn ping(&self) -> Result<()> {
+        self.endpoint.connect().await?;
+        Ok(())
+    }
+
+    async fn pool_state(&self) -> Option<mobc::State> {
+        Some(self.pool.state().await)
+    }
+}
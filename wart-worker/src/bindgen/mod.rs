@@ -1,7 +1,9 @@
 mod proto;
+pub use proto::wart_admin::*;
 pub use proto::wart_storage::*;
 pub use proto::wart_types::*;
 pub use proto::wart_worker::*;
+pub use proto::FILE_DESCRIPTOR_SET;
 
 mod wasm;
 pub use wasm::imports;
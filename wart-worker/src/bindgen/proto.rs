@@ -9,3 +9,10 @@ pub mod wart_storage {
 pub mod wart_worker {
     tonic::include_proto!("wart_worker");
 }
+
+pub mod wart_admin {
+    tonic::include_proto!("wart_admin");
+}
+
+/// Descriptors of wart_types, wart_worker and wart_storage.
+pub const FILE_DESCRIPTOR_SET: &[u8] = tonic::include_file_descriptor_set!("wart_descriptor");
@@ -7,7 +7,24 @@ wit_bindgen_wasmtime::export!({
         "storage::choice-nodes",
         "storage::query-nodes",
         "storage::query-neighbors",
+        "storage::sample-neighbors",
+        "storage::sample-subgraph",
+        "storage::query-nodes-batch",
+        "storage::query-neighbors-batch",
         "storage::query-kv",
         "storage::update-kv",
+        "edge-scanner::new",
+        "edge-scanner::next",
+        "storage::try-choice-nodes",
+        "storage::try-query-nodes",
+        "storage::try-query-neighbors",
+        "storage::try-sample-neighbors",
+        "storage::try-sample-subgraph",
+        "storage::try-query-nodes-batch",
+        "storage::try-query-neighbors-batch",
+        "storage::try-query-kv",
+        "storage::try-update-kv",
+        "edge-scanner::try-new",
+        "edge-scanner::try-next",
     ],
 });
@@ -0,0 +1,864 @@
+use crate::wasm::limits::Limits;
+use crate::wasm::{SandboxManager, Storage};
+
+use anyhow::{anyhow, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: wart [config.yaml] [--config config.yaml] [--set key=value]... [--print-config]
+
+Settings are layered: the YAML file, then the WART_* environment variables,
+then the --set flags. A key is the path of a setting in the YAML file, joined
+by dots in flags and by double underscores in environment variables:
+
+    --set engine.epoch_tick_ms=50
+    WART_ENGINE__EPOCH_TICK_MS=50
+
+The file may also be given by WART_CONFIG. --print-config prints the
+effective configuration, with the JWT secret and API keys masked, and exits.";
+
+const ENV_PREFIX: &str = "WART_";
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum GraphBackendConfig {
+    /// Forwards the queries to `storage_server`.
+    Grpc,
+
+    /// Loads the graph from edge-list files, see `backend/memory.rs`.
+    Memory { path: PathBuf },
+}
+
+impl Default for GraphBackendConfig {
+    fn default() -> Self {
+        Self::Grpc
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum KvStoreConfig {
+    /// Keeps sessions and KV stores in `redis_server`.
+    Redis,
+
+    /// Keeps them in the memory of this worker, for development and CI. The
+    /// state is written to `snapshot` every `snapshot_interval` seconds.
+    Embedded {
+        snapshot: Option<PathBuf>,
+
+        #[serde(default = "default_snapshot_interval")]
+        snapshot_interval: u64,
+    },
+}
+
+fn default_snapshot_interval() -> u64 {
+    10
+}
+
+impl Default for KvStoreConfig {
+    fn default() -> Self {
+        Self::Redis
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum OptLevel {
+    None,
+    Speed,
+    SpeedAndSize,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EngineConfig {
+    /// Period of the epoch ticker in milliseconds, the granularity of
+    /// `ex_timeout` and of the yields of running sandboxes.
+    pub epoch_tick_ms: u64,
+
+    pub opt_level: OptLevel,
+
+    pub parallel_compilation: bool,
+
+    /// Instruments the code to count instructions, required by the `max_fuel`
+    /// of sessions and for reporting the fuel consumed by each run. Off by
+    /// default since the instrumented code runs noticeably slower.
+    pub consume_fuel: bool,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            epoch_tick_ms: 100,
+            opt_level: OptLevel::Speed,
+            parallel_compilation: true,
+            consume_fuel: false,
+        }
+    }
+}
+
+impl EngineConfig {
+    pub fn to_wasmtime(&self) -> wasmtime::Config {
+        let mut config = SandboxManager::<Storage>::default_config();
+        config.cranelift_opt_level(match self.opt_level {
+            OptLevel::None => wasmtime::OptLevel::None,
+            OptLevel::Speed => wasmtime::OptLevel::Speed,
+            OptLevel::SpeedAndSize => wasmtime::OptLevel::SpeedAndSize,
+        });
+        config.parallel_compilation(self.parallel_compilation);
+        config.consume_fuel(self.consume_fuel);
+        config
+    }
+
+    /// Tells apart the settings the compiled code depends on. Workers only
+    /// run the serialized modules of their own version, since the fuel of a
+    /// module compiled without it wouldn't be metered.
+    pub fn version(&self) -> u64 {
+        self.opt_level as u64 | (self.consume_fuel as u64) << 8
+    }
+
+    pub fn epoch_tick(&self) -> Duration {
+        Duration::from_millis(self.epoch_tick_ms)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(deny_unknown_fields)]
+pub struct PoolConfig {
+    pub max_open: u64,
+
+    /// Defaults to `max_open`.
+    #[serde(default)]
+    pub max_idle: Option<u64>,
+
+    /// Milliseconds to wait for a connection, `0` to wait as long as it takes.
+    #[serde(default)]
+    pub get_timeout_ms: u64,
+}
+
+impl PoolConfig {
+    fn with_size(size: u64) -> Self {
+        Self {
+            max_open: size,
+            max_idle: None,
+            get_timeout_ms: 0,
+        }
+    }
+
+    pub fn builder<M: mobc::Manager>(&self) -> mobc::Builder<M> {
+        mobc::Pool::builder()
+            .max_open(self.max_open)
+            .max_idle(self.max_idle.unwrap_or(self.max_open))
+            .get_timeout(millis(self.get_timeout_ms))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PoolsConfig {
+    /// Connections to `redis_server`.
+    pub redis: PoolConfig,
+
+    /// Clients of `storage_server`, each multiplexing many requests.
+    pub storage: PoolConfig,
+}
+
+impl Default for PoolsConfig {
+    fn default() -> Self {
+        Self {
+            redis: PoolConfig::with_size(128),
+            storage: PoolConfig::with_size(8),
+        }
+    }
+}
+
+/// Milliseconds, `0` for no timeout.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutsConfig {
+    pub storage_connect_ms: u64,
+
+    pub storage_request_ms: u64,
+
+    /// Upper bound of the `timeout_override` of a Run, `0` caps it at the
+    /// `ex_timeout` of the session instead.
+    pub max_run_ms: u64,
+}
+
+/// Responses buffered per stream before the sender waits for the client.
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelsConfig {
+    pub streaming_run: usize,
+
+    pub update_store: usize,
+}
+
+impl Default for ChannelsConfig {
+    fn default() -> Self {
+        Self {
+            streaming_run: 8,
+            update_store: 512,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// One of "error", "warn", "info", "debug" or "trace".
+    pub level: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".into(),
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn level(&self) -> tracing::Level {
+        self.level.parse().unwrap_or(tracing::Level::INFO)
+    }
+}
+
+/// Where the spans are sent, see `telemetry.rs`.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SpanExporterConfig {
+    /// Appends the spans to `path`, one per line, as they end.
+    File { path: PathBuf },
+
+    /// Sends them in batches to an OTLP/gRPC collector, e.g.
+    /// `http://127.0.0.1:4317`.
+    Otlp { endpoint: String },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TracingConfig {
+    pub exporter: SpanExporterConfig,
+
+    /// Fraction of the traces started by this worker that are recorded.
+    /// Traces continued from a client follow its sampling decision.
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+/// TLS of the listener of this worker, mutual when `client_ca` is given.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServerTlsConfig {
+    pub cert: PathBuf,
+
+    pub key: PathBuf,
+
+    /// Clients have to present a certificate signed by this CA.
+    #[serde(default)]
+    pub client_ca: Option<PathBuf>,
+}
+
+/// TLS of the connections to a backend. `cert` and `key` identify this
+/// worker to servers verifying their clients.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientTlsConfig {
+    pub ca: PathBuf,
+
+    /// The name in the certificate of the server, which is addressed by IP.
+    pub domain: String,
+
+    #[serde(default)]
+    pub cert: Option<PathBuf>,
+
+    #[serde(default)]
+    pub key: Option<PathBuf>,
+}
+
+/// Verification of the bearer tokens, signed with HS256 by whoever issues
+/// them. The `sub` claim names the principal and `exp` is required.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct JwtConfig {
+    pub secret: String,
+
+    #[serde(default)]
+    pub issuer: Option<String>,
+
+    #[serde(default)]
+    pub audience: Option<String>,
+}
+
+/// A client of this worker and what it may do.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PrincipalConfig {
+    pub name: String,
+
+    /// Static keys sent in the `x-api-key` header.
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+
+    /// The spaces it may open sessions on, `"*"` for any.
+    #[serde(default)]
+    pub spaces: Vec<String>,
+
+    /// Upper bound of the `parallel` of its sessions, `0` for no limit.
+    #[serde(default)]
+    pub max_parallel: u32,
+
+    /// Whether it may use the WartAdmin service.
+    #[serde(default)]
+    pub admin: bool,
+}
+
+/// Every RPC has to carry an API key or a bearer token of one of the
+/// `principals`, and sessions are only usable by the principal that opened
+/// them.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AuthConfig {
+    #[serde(default)]
+    pub jwt: Option<JwtConfig>,
+
+    pub principals: Vec<PrincipalConfig>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(rename = "rpc_server")]
+    pub rpc_server: SocketAddr,
+
+    #[serde(rename = "redis_server", default)]
+    pub redis_server: Option<SocketAddr>,
+
+    #[serde(rename = "storage_server")]
+    pub storage_server: SocketAddr,
+
+    /// Where `/metrics` is served, not at all unless given.
+    #[serde(rename = "metrics_server", default)]
+    pub metrics_server: Option<SocketAddr>,
+
+    #[serde(rename = "graph_backend", default)]
+    pub graph_backend: GraphBackendConfig,
+
+    #[serde(rename = "kv_store", default)]
+    pub kv_store: KvStoreConfig,
+
+    #[serde(rename = "cores")]
+    pub num_workers: usize,
+
+    #[serde(rename = "engine", default)]
+    pub engine: EngineConfig,
+
+    /// Number of compiled sessions kept by this worker.
+    #[serde(rename = "sandbox_cache", default = "default_sandbox_cache")]
+    pub sandbox_cache: usize,
+
+    /// Upper bounds of the limits a session may ask for, and the limits of
+    /// the sessions asking for none.
+    #[serde(rename = "limits", default = "default_limits")]
+    pub limits: Limits,
+
+    /// Seconds between two rounds of closing expired sessions.
+    #[serde(
+        rename = "session_sweep_interval",
+        default = "default_session_sweep_interval"
+    )]
+    pub session_sweep_interval: u64,
+
+    /// Seconds between two checks of the backends behind the health status.
+    #[serde(
+        rename = "health_check_interval",
+        default = "default_health_check_interval"
+    )]
+    pub health_check_interval: u64,
+
+    /// Seconds the health status stays NOT_SERVING on shutdown before the
+    /// listener closes, so load balancers stop sending requests first.
+    #[serde(rename = "shutdown_grace", default = "default_shutdown_grace")]
+    pub shutdown_grace: u64,
+
+    #[serde(rename = "pools", default)]
+    pub pools: PoolsConfig,
+
+    #[serde(rename = "timeouts", default)]
+    pub timeouts: TimeoutsConfig,
+
+    #[serde(rename = "channels", default)]
+    pub channels: ChannelsConfig,
+
+    #[serde(rename = "log", default)]
+    pub log: LogConfig,
+
+    /// Spans are only logged unless given.
+    #[serde(rename = "tracing", default)]
+    pub tracing: Option<TracingConfig>,
+
+    /// Plaintext unless given.
+    #[serde(rename = "tls", default)]
+    pub tls: Option<ServerTlsConfig>,
+
+    #[serde(rename = "storage_tls", default)]
+    pub storage_tls: Option<ClientTlsConfig>,
+
+    #[serde(rename = "redis_tls", default)]
+    pub redis_tls: Option<ClientTlsConfig>,
+
+    /// Open to anyone reaching `rpc_server` unless given.
+    #[serde(rename = "auth", default)]
+    pub auth: Option<AuthConfig>,
+}
+
+fn default_sandbox_cache() -> usize {
+    128
+}
+
+fn default_session_sweep_interval() -> u64 {
+    60
+}
+
+fn default_health_check_interval() -> u64 {
+    5
+}
+
+fn default_shutdown_grace() -> u64 {
+    5
+}
+
+fn default_limits() -> Limits {
+    Limits {
+        max_memory: 1 << 30,
+        max_data_frames: 4096,
+        max_bytes: 1 << 30,
+        ..Default::default()
+    }
+}
+
+/// `None` for `0`.
+pub fn millis(ms: u64) -> Option<Duration> {
+    if ms > 0 {
+        Some(Duration::from_millis(ms))
+    } else {
+        None
+    }
+}
+
+impl Config {
+    /// Reports every problem at once rather than the first one.
+    fn validate(&self) -> Result<()> {
+        let mut problems = vec![];
+
+        if self.num_workers == 0 {
+            problems.push("cores must be at least 1".to_string());
+        }
+        if self.engine.epoch_tick_ms == 0 {
+            problems.push("engine.epoch_tick_ms must be at least 1".into());
+        }
+        if self.session_sweep_interval == 0 {
+            problems.push("session_sweep_interval must be at least 1".into());
+        }
+        if self.health_check_interval == 0 {
+            problems.push("health_check_interval must be at least 1".into());
+        }
+        if let KvStoreConfig::Redis = self.kv_store {
+            if self.redis_server.is_none() {
+                problems.push("redis_server is required by the redis kv_store".into());
+            }
+        }
+        if let KvStoreConfig::Embedded {
+            snapshot_interval: 0,
+            ..
+        } = self.kv_store
+        {
+            problems.push("kv_store.snapshot_interval must be at least 1".into());
+        }
+        if let GraphBackendConfig::Memory { path } = &self.graph_backend {
+            if !path.exists() {
+                problems.push(format!(
+                    "graph_backend.path {} doesn't exist",
+                    path.display()
+                ));
+            }
+        }
+        for (name, pool) in [
+            ("redis", &self.pools.redis),
+            ("storage", &self.pools.storage),
+        ] {
+            if pool.max_open == 0 {
+                problems.push(format!("pools.{}.max_open must be at least 1", name));
+            }
+            if pool.max_idle.unwrap_or(0) > pool.max_open {
+                problems.push(format!("pools.{}.max_idle must not exceed max_open", name));
+            }
+        }
+        if self.channels.streaming_run == 0 || self.channels.update_store == 0 {
+            problems.push("channels must hold at least 1 response".into());
+        }
+        if self.log.level.parse::<tracing::Level>().is_err() {
+            problems.push(format!("unknown log.level: {}", self.log.level));
+        }
+        if let Some(tracing) = &self.tracing {
+            if !(0.0..=1.0).contains(&tracing.sample_ratio) {
+                problems.push("tracing.sample_ratio must be between 0 and 1".into());
+            }
+        }
+
+        let mut files: Vec<(String, &PathBuf)> = vec![];
+        if let Some(tls) = &self.tls {
+            files.push(("tls.cert".into(), &tls.cert));
+            files.push(("tls.key".into(), &tls.key));
+            files.extend(tls.client_ca.iter().map(|ca| ("tls.client_ca".into(), ca)));
+        }
+        for (name, tls) in [
+            ("storage_tls", &self.storage_tls),
+            ("redis_tls", &self.redis_tls),
+        ] {
+            let tls = match tls {
+                Some(tls) => tls,
+                None => continue,
+            };
+            if tls.cert.is_some() != tls.key.is_some() {
+                problems.push(format!("{}.cert and {}.key go together", name, name));
+            }
+            files.push((format!("{}.ca", name), &tls.ca));
+            files.extend(tls.cert.iter().map(|cert| (format!("{}.cert", name), cert)));
+            files.extend(tls.key.iter().map(|key| (format!("{}.key", name), key)));
+        }
+        for (name, path) in files {
+            if !path.is_file() {
+                problems.push(format!("{} {} doesn't exist", name, path.display()));
+            }
+        }
+
+        if let Some(auth) = &self.auth {
+            if auth.principals.is_empty() {
+                problems.push("auth.principals must not be empty".into());
+            }
+            if let Some(jwt) = &auth.jwt {
+                if jwt.secret.is_empty() {
+                    problems.push("auth.jwt.secret must not be empty".into());
+                }
+            }
+            let mut names = HashSet::new();
+            let mut keys = HashSet::new();
+            for principal in auth.principals.iter() {
+                if principal.name.is_empty() {
+                    problems.push("auth.principals need a name".into());
+                } else if !names.insert(&principal.name) {
+                    problems.push(format!("principal {} is listed twice", principal.name));
+                }
+                for key in principal.api_keys.iter() {
+                    if key.is_empty() {
+                        problems.push(format!("principal {} has an empty api key", principal.name));
+                    } else if !keys.insert(key) {
+                        problems.push(format!(
+                            "an api key of principal {} is already taken",
+                            principal.name
+                        ));
+                    }
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(anyhow!("invalid config:\n  {}", problems.join("\n  ")))
+        }
+    }
+}
+
+#[derive(Default)]
+struct Options {
+    path: Option<PathBuf>,
+    overrides: Vec<(String, String)>,
+    print_config: bool,
+    help: bool,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut options = Self::default();
+        while let Some(arg) = args.next() {
+            match &arg[..] {
+                "-h" | "--help" => options.help = true,
+                "--print-config" => options.print_config = true,
+                "--config" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--config needs a path"))?;
+                    options.path = Some(path.into());
+                }
+                "--set" => {
+                    let kv = args
+                        .next()
+                        .ok_or_else(|| anyhow!("--set needs key=value"))?;
+                    let (key, value) = kv
+                        .split_once('=')
+                        .ok_or_else(|| anyhow!("--set {}: expected key=value", kv))?;
+                    options.overrides.push((key.into(), value.into()));
+                }
+                flag if flag.starts_with('-') => Err(anyhow!("unknown flag: {}", flag))?,
+                path if options.path.is_none() => options.path = Some(path.into()),
+                path => Err(anyhow!("unexpected argument: {}", path))?,
+            }
+        }
+        if options.path.is_none() {
+            options.path = std::env::var_os("WART_CONFIG").map(PathBuf::from);
+        }
+        Ok(options)
+    }
+}
+
+/// Flags and environment variables carry strings. The ones spelling a number
+/// or a boolean are put in as such, which `read` turns back into strings for
+/// the string settings. Any other spelling, e.g. `007`, stays a string.
+fn scalar(raw: &str) -> Value {
+    let value = match serde_yaml::from_str::<Value>(raw) {
+        Ok(value @ (Value::Bool(_) | Value::Number(_))) => value,
+        _ => return Value::String(raw.into()),
+    };
+    match serde_yaml::to_string(&value) {
+        Ok(text) if text.trim_start_matches("---").trim() == raw => value,
+        _ => Value::String(raw.into()),
+    }
+}
+
+/// Reads `T` from the text of `root`, where unlike in `Value` an unquoted
+/// scalar such as `123456` takes the type of the setting it is given to.
+fn read<T: DeserializeOwned>(root: &Value) -> Result<T> {
+    let text = serde_yaml::to_string(root)?;
+    Ok(serde_yaml::from_str(&text)?)
+}
+
+/// The top-level keys of `Config`, as named by its `Deserialize`.
+fn config_keys() -> &'static [&'static str] {
+    use serde::de::{self, Visitor};
+
+    struct Keys<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de, 'a> de::Deserializer<'de> for Keys<'a> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("not a struct"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("only the keys are asked for"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map enum identifier ignored_any
+        }
+    }
+
+    let mut keys: &'static [&'static str] = &[];
+    let _ = Config::deserialize(Keys(&mut keys));
+    keys
+}
+
+/// Sets the WART_* variables among `vars`. Other tools may have variables of
+/// their own with the prefix, so a variable only has to name a setting when
+/// it is nested by `__`.
+fn set_env(root: &mut Value, vars: impl Iterator<Item = (String, String)>) -> Result<()> {
+    let keys = config_keys();
+    for (name, value) in vars {
+        let key = match name.strip_prefix(ENV_PREFIX) {
+            Some(key) if key != "CONFIG" => key.to_lowercase(),
+            _ => continue,
+        };
+        if !key.contains("__") && !keys.contains(&&key[..]) {
+            continue;
+        }
+        let path: Vec<_> = key.split("__").collect();
+        set(root, &path, scalar(&value)).with_context(|| format!("in {}", name))?;
+    }
+    Ok(())
+}
+
+fn set(root: &mut Value, path: &[&str], value: Value) -> Result<()> {
+    let (last, parents) = path.split_last().ok_or_else(|| anyhow!("empty key"))?;
+    let mut node = root;
+    for key in parents.iter().chain(Some(last)) {
+        if key.is_empty() {
+            Err(anyhow!("empty segment in key"))?;
+        }
+        if !node.is_mapping() {
+            *node = Value::Mapping(Default::default());
+        }
+        let map = node.as_mapping_mut().unwrap();
+        let key = Value::String(key.to_string());
+        if !map.contains_key(&key) {
+            map.insert(key.clone(), Value::Null);
+        }
+        node = map.get_mut(&key).unwrap();
+    }
+    *node = value;
+    Ok(())
+}
+
+fn load(options: &Options) -> Result<Config> {
+    let mut root = match &options.path {
+        Some(path) => {
+            let s =
+                std::fs::read(path).with_context(|| format!("can't read {}", path.display()))?;
+            serde_yaml::from_slice(&s).with_context(|| format!("can't parse {}", path.display()))?
+        }
+        None => Value::Null,
+    };
+    if root.is_null() {
+        root = Value::Mapping(Default::default());
+    }
+
+    set_env(&mut root, std::env::vars())?;
+
+    for (key, value) in options.overrides.iter() {
+        let path: Vec<_> = key.split('.').collect();
+        set(&mut root, &path, scalar(value)).with_context(|| format!("in --set {}", key))?;
+    }
+
+    let config: Config = read(&root).context("invalid config")?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// The YAML of `config` with its secrets masked, as printed by
+/// `--print-config`.
+fn redacted(config: &Config) -> Result<String> {
+    const MASK: &str = "***";
+
+    let mut root = serde_yaml::to_value(config)?;
+    if let Some(auth) = root.get_mut("auth") {
+        if let Some(secret) = auth.get_mut("jwt").and_then(|jwt| jwt.get_mut("secret")) {
+            *secret = Value::String(MASK.into());
+        }
+        let principals = auth.get_mut("principals").and_then(Value::as_sequence_mut);
+        for principal in principals.into_iter().flatten() {
+            let keys = principal
+                .get_mut("api_keys")
+                .and_then(Value::as_sequence_mut);
+            for key in keys.into_iter().flatten() {
+                *key = Value::String(MASK.into());
+            }
+        }
+    }
+    Ok(serde_yaml::to_string(&root)?)
+}
+
+/// Loads the layered configuration and exits with the reasons when it is
+/// invalid. `--help` and `--print-config` exit as well, after printing.
+pub fn load_or_exit() -> Config {
+    use std::process::exit;
+
+    // the arguments of the test harness aren't meant for the worker
+    let skip = if cfg!(test) { usize::MAX } else { 1 };
+    let options = match Options::parse(std::env::args().skip(skip)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            exit(2)
+        }
+    };
+    if options.help {
+        println!("{}", USAGE);
+        exit(0)
+    }
+    let config = match load(&options) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{:#}", err);
+            exit(1)
+        }
+    };
+    if options.print_config {
+        match redacted(&config) {
+            Ok(yaml) => print!("{}", yaml),
+            Err(err) => {
+                eprintln!("can't print config: {}", err);
+                exit(1)
+            }
+        }
+        exit(0)
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: &str = "
+rpc_server: \"[::1]:6066\"
+redis_server: \"127.0.0.1:20202\"
+storage_server: \"[::1]:9806\"
+cores: 32
+auth:
+  principals: []
+";
+
+    fn with_env(vars: &[(&str, &str)]) -> Result<Config> {
+        let mut root: Value = serde_yaml::from_str(BASE).unwrap();
+        let vars = vars.iter().map(|(k, v)| (k.to_string(), v.to_string()));
+        set_env(&mut root, vars)?;
+        read(&root)
+    }
+
+    #[test]
+    fn env_values_take_the_type_of_the_setting() {
+        let config = with_env(&[
+            ("WART_AUTH__JWT__SECRET", "123456"),
+            ("WART_CORES", "4"),
+            ("WART_ENGINE__CONSUME_FUEL", "true"),
+            ("WART_AUTH__JWT__ISSUER", "007"),
+        ])
+        .unwrap();
+        let jwt = config.auth.unwrap().jwt.unwrap();
+        assert_eq!(jwt.secret, "123456");
+        assert_eq!(jwt.issuer.as_deref(), Some("007"));
+        assert_eq!(config.num_workers, 4);
+        assert!(config.engine.consume_fuel);
+    }
+
+    #[test]
+    fn print_config_masks_secrets() {
+        let yaml = BASE.replace(
+            "principals: []",
+            "jwt: {secret: s3cret}\n  principals: [{name: app, api_keys: [k1, k2]}]",
+        );
+        let config: Config = read(&serde_yaml::from_str(&yaml).unwrap()).unwrap();
+        let yaml = redacted(&config).unwrap();
+        assert!(!yaml.contains("s3cret") && !yaml.contains("k1") && !yaml.contains("k2"));
+        assert_eq!(yaml.matches("***").count(), 3);
+        assert!(yaml.contains("app"));
+    }
+
+    #[test]
+    fn only_nested_env_names_have_to_be_settings() {
+        assert!(with_env(&[("WART_HOME", "/opt/wart"), ("WART_VERSION", "3")]).is_ok());
+        assert!(with_env(&[("WART_NO__SUCH", "1")]).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::bindgen::wart_admin_server::WartAdminServer;
+use crate::bindgen::wart_worker_server::WartWorkerServer;
+use crate::rpc_server::{Admin, Router};
+use crate::GLOBALS;
+
+use anyhow::{anyhow, Result};
+use tonic::transport::NamedService;
+use tonic_health::server::HealthReporter;
+use tonic_health::ServingStatus;
+
+use std::time::Duration;
+
+/// The services reported by `grpc.health.v1.Health`, the empty name standing
+/// for the whole server.
+const SERVICES: [&str; 3] = [
+    "",
+    <WartWorkerServer<Router> as NamedService>::NAME,
+    <WartAdminServer<Admin> as NamedService>::NAME,
+];
+
+pub async fn set_all(reporter: &mut HealthReporter, status: ServingStatus) {
+    for service in SERVICES {
+        reporter.set_service_status(service, status).await;
+    }
+}
+
+async fn check() -> Result<()> {
+    GLOBALS
+        .kv
+        .ping()
+        .await
+        .map_err(|err| anyhow!("kv_store: {:#}", err))?;
+    GLOBALS
+        .graph
+        .ping()
+        .await
+        .map_err(|err| anyhow!("graph_backend: {:#}", err))?;
+    Ok(())
+}
+
+/// Reports SERVING while the backends are reachable, checking them every
+/// `health_check_interval` seconds. A check taking longer than that fails.
+pub async fn watch(mut reporter: HealthReporter) {
+    let period = Duration::from_secs(GLOBALS.config.health_check_interval);
+    let mut interval = tokio::time::interval(period);
+    let mut serving = None;
+    loop {
+        interval.tick().await;
+        let result = match tokio::time::timeout(period, check()).await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow!("health check timed out")),
+        };
+        let status = match result {
+            Ok(()) => ServingStatus::Serving,
+            Err(err) => {
+                log::warn!("unhealthy: {}", err);
+                ServingStatus::NotServing
+            }
+        };
+        if serving != Some(status) {
+            log::info!("health: {:?}", status);
+            set_all(&mut reporter, status).await;
+            serving = Some(status);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::bindgen::{run_error, RunError, UpdateStoreResponse};
+use crate::GLOBALS;
+
+use anyhow::Result;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, StatusCode};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge_vec, Encoder,
+    HistogramTimer, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+// Series are labeled by space_name rather than by session token, which
+// would grow them without bound.
+lazy_static! {
+    static ref SESSIONS_OPENED: IntCounterVec = register_int_counter_vec!(
+        "wart_sessions_opened_total",
+        "Sessions opened by this worker.",
+        &["space_name"]
+    )
+    .unwrap();
+    static ref RUNS: IntCounterVec = register_int_counter_vec!(
+        "wart_runs_total",
+        "Finished runs, by the kind of their error or \"ok\".",
+        &["space_name", "outcome"]
+    )
+    .unwrap();
+    static ref RUN_DURATION: HistogramVec = register_histogram_vec!(
+        "wart_run_duration_seconds",
+        "Wall time of the runs, from instantiating the sandbox to the response.",
+        &["space_name", "outcome"],
+        vec![0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0]
+    )
+    .unwrap();
+    static ref RUNS_IN_FLIGHT: IntGaugeVec = register_int_gauge_vec!(
+        "wart_runs_in_flight",
+        "Runs in progress on this worker.",
+        &["space_name"]
+    )
+    .unwrap();
+    static ref INSTANTIATE_DURATION: HistogramVec = register_histogram_vec!(
+        "wart_sandbox_instantiate_seconds",
+        "Time to instantiate the sandbox of a run.",
+        &["space_name"],
+        vec![0.0001, 0.00025, 0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1]
+    )
+    .unwrap();
+    static ref COMPILE_DURATION: HistogramVec = register_histogram_vec!(
+        "wart_module_compile_seconds",
+        "Time to compile a program, or to deserialize the module of a session \
+         missing from the sandbox cache.",
+        &["kind"],
+        vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]
+    )
+    .unwrap();
+    static ref IMPORT_CALLS: IntCounterVec = register_int_counter_vec!(
+        "wart_import_calls_total",
+        "Calls of the storage imports by the programs.",
+        &["space_name", "import"]
+    )
+    .unwrap();
+    static ref IMPORT_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "wart_import_errors_total",
+        "Failed calls of the storage imports.",
+        &["space_name", "import"]
+    )
+    .unwrap();
+    static ref IMPORT_DURATION: HistogramVec = register_histogram_vec!(
+        "wart_import_duration_seconds",
+        "Latency of the storage imports.",
+        &["space_name", "import"],
+        vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5]
+    )
+    .unwrap();
+    static ref POOL_CONNECTIONS: IntGaugeVec = register_int_gauge_vec!(
+        "wart_pool_connections",
+        "Connections of the backend pools, by state: \"in_use\", \"idle\" or \"max_open\".",
+        &["pool", "state"]
+    )
+    .unwrap();
+    static ref UPDATE_STORE_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "wart_update_store_requests_total",
+        "UpdateStore requests, by outcome: \"ok\" or \"error\".",
+        &["space_name", "outcome"]
+    )
+    .unwrap();
+    static ref UPDATE_STORE_FIELDS: IntCounterVec = register_int_counter_vec!(
+        "wart_update_store_fields_total",
+        "Fields written by UpdateStore.",
+        &["space_name"]
+    )
+    .unwrap();
+}
+
+pub fn session_opened(space_name: &str) {
+    SESSIONS_OPENED.with_label_values(&[space_name]).inc();
+}
+
+fn outcome(error: Option<&RunError>) -> &'static str {
+    use run_error::Kind;
+
+    let kind = match error {
+        Some(error) => Kind::from_i32(error.kind).unwrap_or(Kind::Unknown),
+        None => return "ok",
+    };
+    match kind {
+        Kind::Unknown => "unknown",
+        Kind::Timeout => "timeout",
+        Kind::Trap => "trap",
+        Kind::OutOfMemory => "out_of_memory",
+        Kind::StorageUnavailable => "storage_unavailable",
+        Kind::InvalidArgs => "invalid_args",
+        Kind::Cancelled => "cancelled",
+        Kind::OutOfFuel => "out_of_fuel",
+    }
+}
+
+pub fn run_finished(space_name: &str, error: Option<&RunError>, elapsed: Duration) {
+    let labels = [space_name, outcome(error)];
+    RUNS.with_label_values(&labels).inc();
+    RUN_DURATION
+        .with_label_values(&labels)
+        .observe(elapsed.as_secs_f64());
+}
+
+/// The gauge of the runs in progress on the space, incremented by the caller.
+pub fn runs_in_flight(space_name: &str) -> IntGauge {
+    RUNS_IN_FLIGHT.with_label_values(&[space_name])
+}
+
+pub fn instantiate_timer(space_name: &str) -> HistogramTimer {
+    INSTANTIATE_DURATION
+        .with_label_values(&[space_name])
+        .start_timer()
+}
+
+/// `kind` is "compile" or "deserialize".
+pub fn compile_timer(kind: &str) -> HistogramTimer {
+    COMPILE_DURATION.with_label_values(&[kind]).start_timer()
+}
+
+/// Counts a call of `import` and observes its latency when dropped, so that
+/// every return of the import is accounted for.
+pub fn import_timer(import: &str, space_name: &str) -> HistogramTimer {
+    IMPORT_CALLS.with_label_values(&[space_name, import]).inc();
+    IMPORT_DURATION
+        .with_label_values(&[space_name, import])
+        .start_timer()
+}
+
+pub fn import_failed(import: &str, space_name: &str) {
+    IMPORT_ERRORS.with_label_values(&[space_name, import]).inc();
+}
+
+pub fn update_store(space_name: &str, result: &Result<UpdateStoreResponse>) {
+    let outcome = match result {
+        Ok(response) => {
+            UPDATE_STORE_FIELDS
+                .with_label_values(&[space_name])
+                .inc_by(response.ok_count);
+            "ok"
+        }
+        Err(_) => "error",
+    };
+    UPDATE_STORE_REQUESTS
+        .with_label_values(&[space_name, outcome])
+        .inc();
+}
+
+/// The pools are sampled when scraped rather than on every checkout.
+async fn sample_pools() {
+    let pools = [
+        ("redis", GLOBALS.kv.pool_state().await),
+        ("storage", GLOBALS.graph.pool_state().await),
+    ];
+    for (pool, state) in pools {
+        let state = match state {
+            Some(state) => state,
+            None => continue,
+        };
+        for (name, value) in [
+            ("in_use", state.in_use),
+            ("idle", state.idle),
+            ("max_open", state.max_open),
+        ] {
+            POOL_CONNECTIONS
+                .with_label_values(&[pool, name])
+                .set(value as i64);
+        }
+    }
+}
+
+async fn handle(request: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if request.uri().path() != "/metrics" {
+        let mut response = Response::new(Body::empty());
+        *response.status_mut() = StatusCode::NOT_FOUND;
+        return Ok(response);
+    }
+
+    sample_pools().await;
+
+    let encoder = TextEncoder::new();
+    let mut buffer = vec![];
+    let mut response = match encoder.encode(&prometheus::gather(), &mut buffer) {
+        Ok(()) => Response::new(Body::from(buffer)),
+        Err(err) => {
+            log::error!("can't encode metrics: {}", err);
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            return Ok(response);
+        }
+    };
+    if let Ok(value) = encoder.format_type().parse() {
+        response.headers_mut().insert(CONTENT_TYPE, value);
+    }
+    Ok(response)
+}
+
+/// Serves `/metrics` in the Prometheus text format, in plaintext and
+/// without authentication.
+pub async fn serve(addr: SocketAddr) -> Result<(), hyper::Error> {
+    let make_service = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(handle)) });
+    hyper::Server::bind(&addr).serve(make_service).await
+}
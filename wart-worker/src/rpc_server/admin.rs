@@ -0,0 +1,43 @@
+use crate::bindgen::wart_admin_server::WartAdmin;
+use crate::bindgen::*;
+
+use tonic::{Request, Response, Status};
+
+pub struct Admin {}
+
+impl Admin {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[tonic::async_trait]
+impl WartAdmin for Admin {
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsResponse>, Status> {
+        super::services::admin::list_sessions(request).await
+    }
+
+    async fn get_session(
+        &self,
+        request: Request<GetSessionRequest>,
+    ) -> Result<Response<SessionInfo>, Status> {
+        super::services::admin::get_session(request).await
+    }
+
+    async fn list_active_runs(
+        &self,
+        request: Request<ListActiveRunsRequest>,
+    ) -> Result<Response<ListActiveRunsResponse>, Status> {
+        super::services::admin::list_active_runs(request).await
+    }
+
+    async fn kill_run(
+        &self,
+        request: Request<KillRunRequest>,
+    ) -> Result<Response<KillRunResponse>, Status> {
+        super::services::admin::kill_run(request).await
+    }
+}
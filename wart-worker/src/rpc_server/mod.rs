@@ -1,4 +1,6 @@
+mod admin;
 mod router;
 pub mod services;
 
+pub use admin::Admin;
 pub use router::Router;
@@ -40,6 +40,13 @@ impl WartWorker for Router {
         super::services::streaming_run::streaming_run(request).await
     }
 
+    async fn run(
+        &self,
+        request: Request<RunRequest>,
+    ) -> Result<Response<StreamingRunResponse>, Status> {
+        super::services::run::run(request).await
+    }
+
     async fn increment_epoch(
         &self,
         request: Request<IncrementEpochRequest>,
@@ -53,4 +60,18 @@ impl WartWorker for Router {
     ) -> Result<Response<Self::UpdateStoreStream>, Status> {
         super::services::update_store::update_store(request).await
     }
+
+    async fn keep_alive(
+        &self,
+        request: Request<KeepAliveRequest>,
+    ) -> Result<Response<KeepAliveResponse>, Status> {
+        super::services::keep_alive::keep_alive(request).await
+    }
+
+    async fn validate_program(
+        &self,
+        request: Request<ValidateProgramRequest>,
+    ) -> Result<Response<ValidateProgramResponse>, Status> {
+        super::services::validate_program::validate_program(request).await
+    }
 }
@@ -0,0 +1,128 @@
+use crate::backend::Session;
+use crate::bindgen::*;
+use crate::GLOBALS;
+use anyhow::Result;
+use tonic::{Request, Response, Status};
+
+pub async fn list_sessions(
+    request: Request<ListSessionsRequest>,
+) -> Result<Response<ListSessionsResponse>, Status> {
+    match list_sessions_impl(request.into_inner()).await {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(Status::aborted(err.to_string())),
+    }
+}
+
+pub async fn get_session(
+    request: Request<GetSessionRequest>,
+) -> Result<Response<SessionInfo>, Status> {
+    match get_session_impl(request.into_inner()).await {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(Status::aborted(err.to_string())),
+    }
+}
+
+pub async fn list_active_runs(
+    request: Request<ListActiveRunsRequest>,
+) -> Result<Response<ListActiveRunsResponse>, Status> {
+    match list_active_runs_impl(request.into_inner()).await {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(Status::aborted(err.to_string())),
+    }
+}
+
+pub async fn kill_run(
+    request: Request<KillRunRequest>,
+) -> Result<Response<KillRunResponse>, Status> {
+    match kill_run_impl(request.into_inner()).await {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(Status::aborted(err.to_string())),
+    }
+}
+
+async fn list_sessions_impl(request: ListSessionsRequest) -> Result<ListSessionsResponse> {
+    let ListSessionsRequest {} = request;
+
+    let mut sessions = vec![];
+    for token in GLOBALS.kv.list_sessions().await? {
+        // closed between the listing and the lookup
+        let session = match GLOBALS.kv.get_session(&token).await {
+            Ok(session) => session,
+            Err(err) => {
+                log::debug!("skipping session {}: {}", token, err);
+                continue;
+            }
+        };
+        sessions.push(session_info(session));
+    }
+
+    Ok(ListSessionsResponse { sessions })
+}
+
+async fn get_session_impl(request: GetSessionRequest) -> Result<SessionInfo> {
+    let GetSessionRequest { token } = request;
+
+    let session = GLOBALS.kv.get_session(&token).await?;
+    let stats = GLOBALS.kv.session_stats(&token).await?;
+
+    Ok(SessionInfo {
+        module_size: stats.module_size,
+        kv_fields: stats.kv_fields,
+        ..session_info(session)
+    })
+}
+
+async fn list_active_runs_impl(request: ListActiveRunsRequest) -> Result<ListActiveRunsResponse> {
+    let ListActiveRunsRequest { token } = request;
+
+    let token = if token.is_empty() {
+        None
+    } else {
+        Some(&token[..])
+    };
+    let runs = GLOBALS
+        .runs
+        .list(token)
+        .into_iter()
+        .map(|run| RunInfo {
+            run_id: run.run_id,
+            token: run.token,
+            request_id: run.request_id,
+            args: run.args,
+            sta_time: run.sta_time,
+        })
+        .collect();
+
+    Ok(ListActiveRunsResponse { runs })
+}
+
+async fn kill_run_impl(request: KillRunRequest) -> Result<KillRunResponse> {
+    let KillRunRequest { run_id } = request;
+
+    let killed = GLOBALS.runs.kill(run_id);
+    if killed {
+        log::info!("run {} killed", run_id);
+    }
+
+    Ok(KillRunResponse { killed })
+}
+
+/// Everything but the figures of `session_stats`.
+fn session_info(session: Session) -> SessionInfo {
+    let active_runs = GLOBALS.runs.count(&session.token) as u32;
+    SessionInfo {
+        token: session.token,
+        space_name: session.space_name,
+        epoch: session.epoch,
+        ex_timeout: session.ex_timeout,
+        parallel: session.parallel as u32,
+        module_hash: session.module_hash,
+        module_size: 0,
+        created_at: session.created_at,
+        last_used: session.last_used,
+        active_runs,
+        kv_fields: 0,
+        idle_ttl: session.idle_ttl,
+        owner: session.owner,
+    }
+}
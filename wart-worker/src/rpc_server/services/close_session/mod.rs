@@ -1,3 +1,5 @@
+use crate::auth::{self, Caller};
+use crate::backend::unix_now;
 use crate::bindgen::*;
 use crate::GLOBALS;
 use anyhow::Result;
@@ -6,49 +8,61 @@ use tonic::{Request, Response, Status};
 pub async fn close_session(
     request: Request<CloseSessionRequest>,
 ) -> Result<Response<CloseSessionResponse>, Status> {
-    match close_session_impl(request.into_inner()).await {
+    let caller = auth::caller(&request);
+    match close_session_impl(caller, request.into_inner()).await {
         Ok(msg) => Ok(Response::new(msg)),
-        Err(err) => Err(Status::aborted(err.to_string())),
+        Err(err) => Err(super::status(err)),
     }
 }
 
-pub async fn close_session_impl(request: CloseSessionRequest) -> Result<CloseSessionResponse> {
+pub async fn close_session_impl(
+    caller: Caller,
+    request: CloseSessionRequest,
+) -> Result<CloseSessionResponse> {
     let CloseSessionRequest { token } = request;
 
-    let mut con = GLOBALS.redis.get().await?;
-    log::info!("closing session: {}", token);
-
-    let key = format!("wart:session:{}", token);
-    let (epoch,): (u64,) = redis::pipe()
-        .hget(&key, "epoch")
-        .query_async(&mut *con)
-        .await?;
-
-    for ep in 0..=(epoch + 1) {
-        let key = format!("wart:store:{}:{}", token, ep);
-        redis::pipe()
-            .unlink(&key)
-            .ignore()
-            .query_async(&mut *con)
-            .await?;
-    }
+    auth::check_session(&caller, &token).await?;
 
-    {
-        let key = format!("wart:store:{}", token);
-        redis::pipe()
-            .unlink(&key)
-            .ignore()
-            .query_async(&mut *con)
-            .await?;
-    }
+    log::info!("closing session: {}", token);
 
-    redis::pipe()
-        .unlink(&key)
-        .ignore()
-        .query_async(&mut *con)
-        .await?;
+    GLOBALS.sandboxes.remove(&token);
+    GLOBALS.kv.close_session(&token).await?;
 
     log::info!("session {} closed", token);
 
     Ok(CloseSessionResponse {})
 }
+
+/// Sessions closed by a single round of the sweeper.
+const SWEEP_BATCH: usize = 256;
+
+/// Closes the sessions idle for longer than their `idle_ttl`. A session
+/// failing to close is left to the next round rather than holding up the
+/// others.
+pub async fn sweep_expired_sessions() {
+    loop {
+        let tokens = match GLOBALS.kv.expired_sessions(unix_now(), SWEEP_BATCH).await {
+            Ok(tokens) => tokens,
+            Err(err) => {
+                log::error!("can't list expired sessions: {}", err);
+                break;
+            }
+        };
+        let mut closed = 0;
+        for token in tokens.iter() {
+            log::info!("session {} expired", token);
+            let request = CloseSessionRequest {
+                token: token.clone(),
+            };
+            // the sweeper acts on behalf of no principal
+            match close_session_impl(None, request).await {
+                Ok(_) => closed += 1,
+                Err(err) => log::error!("can't close expired session {}: {}", token, err),
+            }
+        }
+        // the ones failing to close are listed again, don't spin on them
+        if tokens.len() < SWEEP_BATCH || closed == 0 {
+            break;
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::auth::{self, Caller};
 use crate::bindgen::*;
 use crate::GLOBALS;
 use anyhow::Result;
@@ -6,24 +7,23 @@ use tonic::{Request, Response, Status};
 pub async fn increment_epoch(
     request: Request<IncrementEpochRequest>,
 ) -> Result<Response<IncrementEpochResponse>, Status> {
-    match increment_epoch_impl(request.into_inner()).await {
+    let caller = auth::caller(&request);
+    match increment_epoch_impl(caller, request.into_inner()).await {
         Ok(msg) => Ok(Response::new(msg)),
-        Err(err) => Err(Status::aborted(err.to_string())),
+        Err(err) => Err(super::status(err)),
     }
 }
 
 pub async fn increment_epoch_impl(
+    caller: Caller,
     request: IncrementEpochRequest,
 ) -> Result<IncrementEpochResponse> {
     let IncrementEpochRequest { token } = request;
 
-    let mut con = GLOBALS.redis.get().await?;
+    auth::check_session(&caller, &token).await?;
 
-    let key = format!("wart:session:{}", token);
-    let (epoch,): (u64,) = redis::pipe()
-        .hincr(&key, "epoch", 1u64)
-        .query_async(&mut *con)
-        .await?;
+    let epoch = GLOBALS.kv.increment_epoch(&token).await?;
+    GLOBALS.kv.keep_alive(&token).await?;
 
     Ok(IncrementEpochResponse { epoch })
 }
@@ -0,0 +1,28 @@
+use crate::auth::{self, Caller};
+use crate::bindgen::*;
+use crate::GLOBALS;
+use anyhow::Result;
+use tonic::{Request, Response, Status};
+
+pub async fn keep_alive(
+    request: Request<KeepAliveRequest>,
+) -> Result<Response<KeepAliveResponse>, Status> {
+    let caller = auth::caller(&request);
+    match keep_alive_impl(caller, request.into_inner()).await {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(super::status(err)),
+    }
+}
+
+pub async fn keep_alive_impl(
+    caller: Caller,
+    request: KeepAliveRequest,
+) -> Result<KeepAliveResponse> {
+    let KeepAliveRequest { token } = request;
+
+    auth::check_session(&caller, &token).await?;
+
+    let deadline = GLOBALS.kv.keep_alive(&token).await?;
+
+    Ok(KeepAliveResponse { deadline })
+}
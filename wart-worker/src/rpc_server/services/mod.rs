@@ -1,5 +1,21 @@
+pub mod admin;
 pub mod close_session;
 pub mod increment_epoch;
+pub mod keep_alive;
 pub mod open_session;
+pub mod run;
 pub mod streaming_run;
 pub mod update_store;
+pub mod validate_program;
+
+use crate::auth::PermissionDenied;
+
+use tonic::Status;
+
+/// The status of a failed request, ABORTED unless the caller lacks permission.
+pub fn status(err: anyhow::Error) -> Status {
+    match err.downcast_ref::<PermissionDenied>() {
+        Some(denied) => Status::permission_denied(denied.to_string()),
+        None => Status::aborted(err.to_string()),
+    }
+}
@@ -1,64 +1,112 @@
+use crate::auth::{self, Caller};
+use crate::backend::{unix_now, Session};
 use crate::bindgen::*;
-use crate::wasm::{SandboxManager, Storage};
+use crate::metrics;
+use crate::telemetry;
+use crate::wasm::limits::Limits;
+use crate::wasm::validate::validate;
 use crate::GLOBALS;
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use tonic::{Request, Response, Status};
+use tracing::Instrument;
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 pub async fn open_session(
     request: Request<OpenSessionRequest>,
 ) -> Result<Response<OpenSessionResponse>, Status> {
-    match open_session_impl(request.into_inner()).await {
+    let caller = auth::caller(&request);
+    let span = tracing::info_span!("OpenSession", space_name = %request.get_ref().space_name);
+    telemetry::follow(&span, request.metadata());
+    match open_session_impl(caller, request.into_inner())
+        .instrument(span)
+        .await
+    {
         Ok(msg) => Ok(Response::new(msg)),
-        Err(err) => Err(Status::aborted(err.to_string())),
+        Err(err) => Err(super::status(err)),
     }
 }
 
-async fn open_session_impl(request: OpenSessionRequest) -> Result<OpenSessionResponse> {
+async fn open_session_impl(
+    caller: Caller,
+    request: OpenSessionRequest,
+) -> Result<OpenSessionResponse> {
     let OpenSessionRequest {
         space_name,
         program,
         // io_timeout: _,
         ex_timeout,
         parallel,
+        max_fuel,
+        limits,
+        idle_ttl,
     } = request;
 
-    let module = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
-        let config = SandboxManager::<Storage>::default_config();
-        let manager = SandboxManager::<Storage>::from_bytes(&program, &config)?;
-        manager.module.serialize()
-    })
-    .await??;
+    auth::check_open(&caller, &space_name, parallel)?;
+
+    if max_fuel > 0 && !GLOBALS.config.engine.consume_fuel {
+        Err(anyhow!("fuel metering is disabled on this worker"))?;
+    }
 
-    let mut con = GLOBALS.redis.get().await?;
+    let limits = limits
+        .map(Limits::from)
+        .unwrap_or_default()
+        .capped_by(&GLOBALS.config.limits);
+
+    let validated =
+        tokio::task::spawn_blocking(move || validate(&GLOBALS.engine, &program)).await?;
+    let manager = match validated {
+        Ok(manager) => manager,
+        Err(diagnostics) => {
+            let message = diagnostics
+                .iter()
+                .map(|d| d.message.clone())
+                .collect::<Vec<_>>()
+                .join("; ");
+            log::info!("program rejected: {}", message);
+            return Ok(OpenSessionResponse {
+                result: Some(open_session_response::Result::Err(
+                    open_session_response::Err {
+                        message,
+                        diagnostics,
+                    },
+                )),
+            });
+        }
+    };
+    // the hash covers the engine settings as well, see `SandboxCache`
+    let module = manager.serialize(GLOBALS.config.engine.version())?;
+
+    let module_hash = {
+        let mut hasher = DefaultHasher::new();
+        module.hash(&mut hasher);
+        hasher.finish()
+    };
 
     let token = uuid::Uuid::new_v4().to_string();
     log::info!("opening session: {}", token);
 
-    let key = format!("wart:session:{}", token);
-    let _: () = redis::pipe()
-        .atomic()
-        .hset(&key, "token", &token)
-        .ignore()
-        .hset(&key, "space_name", &space_name)
-        .ignore()
-        .hset(&key, "epoch", 0)
-        .ignore()
-        .hset(&key, "module", &*module)
-        .ignore()
-        .hset(&key, "ex_timeout", ex_timeout)
-        .ignore()
-        .hset(&key, "parallel", parallel)
-        .ignore()
-        .query_async(&mut *con)
-        .await?;
-
-    let (token,): (String,) = redis::pipe()
-        .atomic()
-        .hget(&key, "token")
-        .query_async(&mut *con)
-        .await?;
+    let now = unix_now();
+    let session = Session {
+        token: token.clone(),
+        space_name,
+        epoch: 0,
+        module_hash,
+        ex_timeout: ex_timeout as u64,
+        parallel: parallel as usize,
+        max_fuel,
+        limits,
+        idle_ttl: idle_ttl as u64,
+        created_at: now,
+        last_used: now,
+        owner: auth::owner(&caller),
+    };
+    GLOBALS.kv.open_session(&session, &module).await?;
+    GLOBALS.sandboxes.insert(&token, module_hash, manager);
+    metrics::session_opened(&session.space_name);
 
-    log::info!("session {} closed", token);
+    log::info!("session {} opened", token);
 
     Ok(OpenSessionResponse {
         result: Some(open_session_response::Result::Ok(
@@ -0,0 +1,313 @@
+use crate::auth::{self, Caller};
+use crate::bindgen::*;
+use crate::telemetry;
+use crate::wasm::StorageManager;
+use crate::GLOBALS;
+
+use anyhow::{anyhow, Result};
+
+use tokio::sync::mpsc;
+use tonic::{Request, Response, Status};
+use tracing::Instrument;
+
+use super::streaming_run::streaming_run_launch;
+
+pub async fn run(request: Request<RunRequest>) -> Result<Response<StreamingRunResponse>, Status> {
+    let caller = auth::caller(&request);
+    let span = tracing::info_span!("Run");
+    telemetry::follow(&span, request.metadata());
+    match run_impl(caller, request.into_inner())
+        .instrument(span)
+        .await
+    {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(super::status(err)),
+    }
+}
+
+async fn run_impl(caller: Caller, request: RunRequest) -> Result<StreamingRunResponse> {
+    use streaming_run_request::{Args, Data};
+
+    let RunRequest {
+        token,
+        args,
+        timeout_override,
+        request_id,
+        trace,
+    } = request;
+
+    if timeout_override < 0 {
+        Err(anyhow!("negative timeout_override: {}", timeout_override))?;
+    }
+
+    auth::check_session(&caller, &token).await?;
+    let mut storage_manager = StorageManager::new(&token).await?;
+    if timeout_override > 0 {
+        storage_manager.ttl = max_timeout(storage_manager.ttl).min(timeout_override as u64);
+    }
+
+    // the receiver lives as long as this call, dropping it on cancellation
+    // stops the sandbox just like a closed stream does
+    let (bypass_tx, _bypass_rx) = mpsc::channel::<Result<StreamingRunResponse, Status>>(1);
+
+    let request = StreamingRunRequest {
+        data: Some(Data::Args(Args {
+            args,
+            request_id,
+            trace,
+        })),
+    };
+    let task = GLOBALS
+        .runtime
+        .spawn(streaming_run_launch(request, bypass_tx, storage_manager).in_current_span());
+
+    Ok(task.await?)
+}
+
+/// How long a run of a session with `ex_timeout` may be given at most.
+fn max_timeout(ex_timeout: u64) -> u64 {
+    match GLOBALS.config.timeouts.max_run_ms {
+        0 => ex_timeout,
+        max => max,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc_server::services::increment_epoch::increment_epoch;
+    use crate::rpc_server::services::open_session::open_session;
+
+    use std::sync::Once;
+
+    /// Reads the `age` of user 1005 into the table `out`, adds 7 to the KV
+    /// field `hits` and reports the value it had in the table `kv`, since the
+    /// update only shows in the next epoch. Traps when an import fails.
+    const PROGRAM: &str = r#"
+    (module
+      (import "imports" "storage::new" (func $storage_new (param i32)))
+      (import "imports" "storage::query-nodes"
+        (func $query_nodes (param i32 i32 i64 i32 i32 i32 i32 i32 i32)))
+      (import "imports" "storage::update-kv"
+        (func $update_kv (param i32 i32 i32 i32 i32 i32 i32 i32)))
+      (import "imports" "storage::query-kv"
+        (func $query_kv (param i32 i32 i32 i32 i64 i32 i32)))
+      (import "imports" "data-frame::new" (func $df_new (param i32 i32 i32 i32 i32)))
+      (import "imports" "data-frame::push" (func $df_push (param i32 i32 i32 i32)))
+
+      (memory (export "memory") 2)
+      (global $heap (mut i32) (i32.const 1024))
+
+      ;; the return area is at 16
+      (data (i32.const 256) "user")
+      (data (i32.const 264) "age")
+      (data (i32.const 272) "out")
+      (data (i32.const 280) "hits")
+      (data (i32.const 288) "kv")
+      ;; list<string> ["age"] and ["hits"]
+      (data (i32.const 320) "\08\01\00\00\03\00\00\00")
+      (data (i32.const 328) "\18\01\00\00\04\00\00\00")
+      ;; list<s64> [7]
+      (data (i32.const 336) "\07\00\00\00\00\00\00\00")
+      ;; rows of one item, age: i64 0, hits: i64 0 and hits: i64 filled in
+      (data (i32.const 352) "\08\01\00\00\03\00\00\00\03")
+      (data (i32.const 384) "\18\01\00\00\04\00\00\00\03")
+      (data (i32.const 416) "\18\01\00\00\04\00\00\00\03")
+
+      (func (export "canonical_abi_realloc")
+        (param $old i32) (param $old_size i32) (param $align i32) (param $size i32)
+        (result i32)
+        (local $ptr i32)
+        (local.set $ptr
+          (i32.and
+            (i32.add (global.get $heap) (i32.sub (local.get $align) (i32.const 1)))
+            (i32.sub (i32.const 0) (local.get $align))))
+        (global.set $heap (i32.add (local.get $ptr) (local.get $size)))
+        (if (i32.gt_u (global.get $heap) (i32.const 131072)) (then unreachable))
+        (local.get $ptr))
+
+      (func $expect
+        (if (i32.eqz (i32.load8_u (i32.const 16))) (then unreachable)))
+
+      (func (export "__wasm_call_ctors"))
+      (func (export "__wasm_call_dtors"))
+
+      (func (export "_start")
+        (local $storage i32) (local $row i32) (local $len i32) (local $df i32)
+        (call $storage_new (i32.const 16))
+        (call $expect)
+        (local.set $storage (i32.load (i32.const 20)))
+
+        (call $query_nodes (local.get $storage)
+          (i32.const 3) (i64.const 1005) (i32.const 0)
+          (i32.const 256) (i32.const 4) (i32.const 320) (i32.const 1) (i32.const 16))
+        (call $expect)
+        (local.set $row (i32.load (i32.const 20)))
+        (local.set $len (i32.load (i32.const 24)))
+        (call $df_new (i32.const 272) (i32.const 3) (i32.const 352) (i32.const 1) (i32.const 16))
+        (call $expect)
+        (local.set $df (i32.load (i32.const 20)))
+        (call $df_push (local.get $df) (local.get $row) (local.get $len) (i32.const 16))
+        (call $expect)
+
+        (call $update_kv (local.get $storage)
+          (i32.const 328) (i32.const 1) (i32.const 3) (i32.const 336) (i32.const 1)
+          (i32.const 0) (i32.const 16))
+        (call $expect)
+        (call $query_kv (local.get $storage)
+          (i32.const 328) (i32.const 1) (i32.const 3) (i64.const 0) (i32.const 0)
+          (i32.const 16))
+        (call $expect)
+        (i64.store (i32.const 432) (i64.load (i32.load (i32.const 24))))
+        (call $df_new (i32.const 288) (i32.const 2) (i32.const 384) (i32.const 1) (i32.const 16))
+        (call $expect)
+        (local.set $df (i32.load (i32.const 20)))
+        (call $df_push (local.get $df) (i32.const 416) (i32.const 1) (i32.const 16))
+        (call $expect)))
+    "#;
+
+    /// Points the worker at a memory graph and the embedded KV store, before
+    /// anything touches `GLOBALS`.
+    fn setup() {
+        static ONCE: Once = Once::new();
+        ONCE.call_once(|| {
+            let dir = std::env::temp_dir().join(format!("wart-run-{}", std::process::id()));
+            let graph = dir.join("graph");
+            std::fs::create_dir_all(&graph).unwrap();
+            std::fs::write(graph.join("user.nodes"), "id,age\n1005,42\n1006,17\n").unwrap();
+            std::fs::write(graph.join("follow.edges"), "src,dst\n1005,1006\n").unwrap();
+
+            let config = dir.join("config.yaml");
+            let yaml = format!(
+                "rpc_server: \"[::1]:0\"\n\
+                 storage_server: \"[::1]:0\"\n\
+                 cores: 2\n\
+                 graph_backend:\n  type: memory\n  path: {:?}\n\
+                 kv_store:\n  type: embedded\n",
+                graph
+            );
+            std::fs::write(&config, yaml).unwrap();
+            std::env::set_var("WART_CONFIG", &config);
+        });
+    }
+
+    async fn open(program: &str) -> String {
+        let request = OpenSessionRequest {
+            space_name: "test".into(),
+            program: program.as_bytes().to_vec(),
+            ex_timeout: 10_000,
+            parallel: 1,
+            ..Default::default()
+        };
+        let response = open_session(Request::new(request)).await.unwrap();
+        match response.into_inner().result {
+            Some(open_session_response::Result::Ok(ok)) => ok.token,
+            other => panic!("session not opened: {:?}", other),
+        }
+    }
+
+    fn column(response: &StreamingRunResponse, table: &str, key: &str) -> Vec<i64> {
+        let df = response
+            .tables
+            .iter()
+            .find(|df| df.comment == table)
+            .unwrap_or_else(|| panic!("missing table {}", table));
+        let i = df.headers.iter().position(|h| h == key).unwrap();
+        match &df.columns[i].values {
+            Some(series::Values::Int64Values(x)) => x.data.clone(),
+            other => panic!("{}.{} isn't int64: {:?}", table, key, other),
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_against_memory_graph_and_embedded_kv() {
+        setup();
+        let token = open(PROGRAM).await;
+
+        for hits in [0, 7, 14] {
+            let request = RunRequest {
+                token: token.clone(),
+                request_id: format!("hits-{}", hits),
+                ..Default::default()
+            };
+            let response = run(Request::new(request)).await.unwrap().into_inner();
+            assert!(response.error.is_none(), "{}", response.last_err);
+            assert_eq!(column(&response, "out", "age"), vec![42]);
+            assert_eq!(column(&response, "kv", "hits"), vec![hits]);
+            assert_eq!(response.counter, 3);
+
+            let request = IncrementEpochRequest {
+                token: token.clone(),
+            };
+            increment_epoch(Request::new(request)).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn traces_every_import_of_the_run() {
+        setup();
+        let token = open(PROGRAM).await;
+
+        let request = RunRequest {
+            token,
+            trace: true,
+            ..Default::default()
+        };
+        let response = run(Request::new(request)).await.unwrap().into_inner();
+        assert!(response.error.is_none(), "{}", response.last_err);
+        let trace = response.tables.last().unwrap();
+        assert_eq!(trace.comment, "__trace__");
+        let imports = match &trace.columns[1].values {
+            Some(series::Values::StringValues(x)) => x.data.clone(),
+            other => panic!("__trace__.import isn't string: {:?}", other),
+        };
+        assert_eq!(
+            imports,
+            [
+                "storage::new",
+                "storage::query-nodes",
+                "data-frame::new",
+                "data-frame::push",
+                "storage::update-kv",
+                "storage::query-kv",
+                "data-frame::new",
+                "data-frame::push",
+            ]
+        );
+        assert_eq!(column(&response, "__trace__", "result_size")[3], 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_negative_timeout_override() {
+        setup();
+        let token = open(PROGRAM).await;
+
+        let request = RunRequest {
+            token,
+            timeout_override: -1,
+            ..Default::default()
+        };
+        assert!(run(Request::new(request)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn blames_the_trap_on_the_failed_import() {
+        setup();
+        // asks for the tag "use", which the graph doesn't have
+        let program = PROGRAM.replace(
+            "(i32.const 256) (i32.const 4)",
+            "(i32.const 256) (i32.const 3)",
+        );
+        let token = open(&program).await;
+
+        let request = RunRequest {
+            token,
+            ..Default::default()
+        };
+        let response = run(Request::new(request)).await.unwrap().into_inner();
+        let error = response.error.expect("the run should fail");
+        assert_eq!(error.kind, run_error::Kind::InvalidArgs as i32);
+        assert_eq!(error.failed_import, "storage::query-nodes");
+        assert!(response.tables.is_empty());
+    }
+}
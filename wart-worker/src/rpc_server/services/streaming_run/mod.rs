@@ -1,80 +1,110 @@
+use crate::auth::{self, Caller};
 use crate::bindgen::*;
+use crate::wasm::error;
+use crate::wasm::runs::RunGuard;
 use crate::wasm::StorageManager;
 
 use anyhow::{anyhow, Result};
 use futures::StreamExt;
 use log;
 
-use tokio::sync::mpsc;
-use tokio::time;
+use tokio::sync::{mpsc, Semaphore};
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
 
+use tracing::{Dispatch, Instrument};
 use tracing_futures::WithSubscriber;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
 use tracing_subscriber::fmt::Subscriber;
 use tracing_subscriber::prelude::*;
 
 use crate::log_tracer::WasmTracer;
+use crate::metrics;
+use crate::telemetry;
 
 use crate::GLOBALS;
 
+use run_error::Kind;
+use std::sync::Arc;
+use std::time::Instant;
+
 type StreamingRunStream = ReceiverStream<Result<StreamingRunResponse, Status>>;
 
 pub async fn streaming_run(
     request: Request<Streaming<StreamingRunRequest>>,
 ) -> Result<Response<StreamingRunStream>, Status> {
-    match streaming_run_impl(request.into_inner()).await {
+    let caller = auth::caller(&request);
+    // lasts as long as the stream, the runs of its args are its children
+    let span = tracing::info_span!("StreamingRun");
+    telemetry::follow(&span, request.metadata());
+    match streaming_run_impl(caller, request.into_inner())
+        .instrument(span)
+        .await
+    {
         Ok(msg) => Ok(Response::new(msg)),
-        Err(err) => Err(Status::aborted(err.to_string())),
+        Err(err) => Err(super::status(err)),
     }
 }
 
 async fn streaming_run_impl(
+    caller: Caller,
     mut istream: Streaming<StreamingRunRequest>,
 ) -> Result<StreamingRunStream> {
-    let (mpsc_tx, mpsc_rx) = mpsc::channel(8);
+    let (mpsc_tx, mpsc_rx) = mpsc::channel(GLOBALS.config.channels.streaming_run);
 
     if let Some(config) = istream.next().await {
         let config = config?;
-        let storage_manager = streaming_run_config(config).await?;
-
-        let sig_cls = mpsc_tx.clone();
-        storage_manager.spawn_clk(async move {
-            sig_cls.closed().await;
-        });
+        let (storage_manager, unordered) = streaming_run_config(&caller, config).await?;
 
-        GLOBALS
-            .runtime
-            .spawn(streaming_run_args(istream, mpsc_tx, storage_manager));
+        if unordered {
+            GLOBALS.runtime.spawn(
+                streaming_run_args_unordered(istream, mpsc_tx, storage_manager).in_current_span(),
+            );
+        } else {
+            GLOBALS
+                .runtime
+                .spawn(streaming_run_args(istream, mpsc_tx, storage_manager).in_current_span());
+        }
     }
 
     Ok(ReceiverStream::new(mpsc_rx))
 }
 
-async fn streaming_run_config(request: StreamingRunRequest) -> Result<StorageManager> {
+/// Returns the storage manager of the session and whether the responses are
+/// delivered in completion order.
+async fn streaming_run_config(
+    caller: &Caller,
+    request: StreamingRunRequest,
+) -> Result<(StorageManager, bool)> {
     use streaming_run_request::{Config, Data};
     match request.data.ok_or(anyhow!("empty config"))? {
         Data::Config(config) => {
-            let Config { token } = config;
+            let Config { token, unordered } = config;
+            auth::check_session(caller, &token).await?;
             let storage_manager = StorageManager::new(&token).await?;
-            Ok(storage_manager)
+            Ok((storage_manager, unordered))
         }
         Data::Args(_) => Err(anyhow!("invalid config"))?,
     }
 }
 
+fn parallelism(storage_manager: &StorageManager) -> usize {
+    if storage_manager.par > 0 {
+        storage_manager.par
+    } else {
+        1
+    }
+}
+
 async fn streaming_run_args(
     mut istream: Streaming<StreamingRunRequest>,
     mpsc_tx: mpsc::Sender<Result<StreamingRunResponse, Status>>,
     storage_manager: StorageManager,
 ) {
-    let par_n = if storage_manager.par > 0 {
-        storage_manager.par
-    } else {
-        1
-    };
+    let par_n = parallelism(&storage_manager);
     let (par_tx, mut par_rx) = mpsc::channel(par_n);
     let bypass_tx = mpsc_tx.clone();
+    let span = tracing::Span::current();
     tokio::spawn(async move {
         while let Some(request) = istream.next().await {
             let request = match request {
@@ -92,7 +122,7 @@ async fn streaming_run_args(
                             request,
                             bypass_tx.clone(),
                             storage_manager.clone(),
-                        ));
+                        ).instrument(span.clone()));
                         permit.send(task);
                     },
                     Err(err) => {
@@ -109,94 +139,200 @@ async fn streaming_run_args(
 
     while let Some(task) = par_rx.recv().await {
         match task.await {
-            Ok(result) => match result {
-                Ok(resp) => {
-                    if let Err(_) = mpsc_tx.send(Ok(resp)).await {
-                        break;
-                    }
+            Ok(resp) => {
+                if let Err(_) = mpsc_tx.send(Ok(resp)).await {
+                    break;
                 }
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                break;
+            }
+        }
+    }
+}
+
+/// Sends every response as soon as its run finishes, so a slow run doesn't
+/// hold back the ones after it. At most `par` runs are in flight.
+async fn streaming_run_args_unordered(
+    mut istream: Streaming<StreamingRunRequest>,
+    mpsc_tx: mpsc::Sender<Result<StreamingRunResponse, Status>>,
+    storage_manager: StorageManager,
+) {
+    let semaphore = Arc::new(Semaphore::new(parallelism(&storage_manager)));
+    while let Some(request) = istream.next().await {
+        let request = match request {
+            Ok(request) => request,
+            Err(err) => {
+                let _ = mpsc_tx.send(Err(err)).await;
+                break;
+            }
+        };
+
+        let permit = tokio::select! {
+            p = semaphore.clone().acquire_owned() => match p {
+                Ok(permit) => permit,
                 Err(err) => {
                     log::error!("{}", err);
-                    let resp = StreamingRunResponse {
-                        last_err: err.to_string(),
-                        ..Default::default()
-                    };
-                    if let Err(_) = mpsc_tx.send(Ok(resp)).await {
-                        break;
-                    }
+                    break;
                 }
             },
-            Err(err) => {
-                log::error!("{}", err);
+            _ = mpsc_tx.closed() => {
                 break;
+            },
+        };
+
+        let mpsc_tx = mpsc_tx.clone();
+        let storage_manager = storage_manager.clone();
+        let span = tracing::Span::current();
+        tokio::spawn(async move {
+            let resp = streaming_run_launch(request, mpsc_tx.clone(), storage_manager)
+                .instrument(span)
+                .await;
+            let _ = mpsc_tx.send(Ok(resp)).await;
+            drop(permit);
+        });
+    }
+}
+
+/// Never fails, what went wrong is reported in `error` along with the output
+/// produced so far.
+pub async fn streaming_run_launch(
+    request: StreamingRunRequest,
+    bypass_tx: mpsc::Sender<Result<StreamingRunResponse, Status>>,
+    storage_manager: StorageManager,
+) -> StreamingRunResponse {
+    use streaming_run_request::{Args, Data};
+
+    match request.data {
+        Some(Data::Args(Args {
+            args,
+            request_id,
+            trace,
+        })) => {
+            let space_name = storage_manager.space_name.clone();
+            let run =
+                GLOBALS
+                    .runs
+                    .register(&storage_manager.token, &space_name, &request_id, &args);
+            let sta_time = Instant::now();
+            let span = tracing::info_span!(
+                "run",
+                space_name = %space_name,
+                request_id = %request_id,
+            );
+            let response = streaming_run_sandbox(&args, trace, &run, bypass_tx, storage_manager)
+                .instrument(span)
+                .await;
+            metrics::run_finished(&space_name, response.error.as_ref(), sta_time.elapsed());
+            StreamingRunResponse {
+                request_id,
+                ..response
             }
         }
+        _ => failed(error::of_kind(Kind::InvalidArgs, "invalid args")),
     }
 }
 
-async fn streaming_run_launch(
-    request: StreamingRunRequest,
+async fn streaming_run_sandbox(
+    args: &[String],
+    trace: bool,
+    run: &RunGuard<'_>,
     bypass_tx: mpsc::Sender<Result<StreamingRunResponse, Status>>,
     storage_manager: StorageManager,
-) -> Result<StreamingRunResponse> {
-    use streaming_run_request::Data::{Args, Config};
-
-    let request = request.data.ok_or(anyhow!("empty args"))?;
-    match request {
-        Config(_) => Err(anyhow!("invalid args"))?,
-        Args(args) => {
-            let args = &args.args;
-            let mut sandbox = storage_manager.get_sandbox(args).await?;
-            sandbox.store.epoch_deadline_async_yield_and_update(1);
-
-            let tracer = WasmTracer::default();
-            let logs = tracer.get_logs();
-            let subscriber = Subscriber::builder()
-                .with_max_level(Subscriber::DEFAULT_MAX_LEVEL)
-                .finish()
-                .with(tracer);
-
-            {
-                let ttl = time::Duration::from_millis(storage_manager.ttl);
-                let task = time::timeout(ttl, sandbox.call_async()).with_subscriber(subscriber);
-                tokio::pin!(task);
-
-                tokio::select! {
-                    result = &mut task => {
-                        result??;
-                    },
-                    _ = bypass_tx.closed() => {
-                        Err(anyhow!("reset by peer"))?;
-                    }
-                };
-                task.dispatch();
+) -> StreamingRunResponse {
+    storage_manager.keep_alive().await;
+
+    let mut sandbox = match storage_manager.get_sandbox(args, trace).await {
+        Ok(sandbox) => sandbox,
+        Err(err) => return failed((&err).into()),
+    };
+    sandbox.store.epoch_deadline_async_yield_and_update(1);
+
+    let tracer = WasmTracer::default();
+    let logs = tracer.get_logs();
+    let subscriber = Subscriber::builder()
+        .with_max_level(Subscriber::DEFAULT_MAX_LEVEL)
+        .finish()
+        .with(tracer)
+        .with(telemetry::layer(GLOBALS.tracer.as_ref()));
+    let dispatch = Dispatch::new(subscriber);
+
+    // the subscriber of the run doesn't know the spans of the global one, so
+    // the parent of the guest span is passed on explicitly
+    let guest = tracing::dispatcher::with_default(&dispatch, || tracing::info_span!("guest"));
+    guest.set_parent(tracing::Span::current().context());
+
+    let error = {
+        let ticks = GLOBALS.clock.ticks(storage_manager.ttl);
+        let task = sandbox
+            .call_async()
+            .instrument(guest)
+            .with_subscriber(dispatch);
+        tokio::pin!(task);
+
+        let error = tokio::select! {
+            result = &mut task => {
+                result.err().map(|err| RunError::from(&err))
+            },
+            _ = GLOBALS.clock.deadline(ticks) => {
+                Some(error::of_kind(Kind::Timeout, "deadline has elapsed"))
+            },
+            _ = bypass_tx.closed() => {
+                Some(error::of_kind(Kind::Cancelled, "reset by peer"))
+            },
+            _ = run.killed() => {
+                Some(error::of_kind(Kind::Cancelled, "killed by admin"))
             }
+        };
+        task.dispatch();
+        error
+    };
 
-            let storage = sandbox.store.into_data().imports;
-            let sta_time = storage.start_time.timestamp_millis();
-            let end_time = chrono::Local::now().timestamp_millis();
-            let counter = storage.counter as i64;
+    let fuel_consumed = sandbox.fuel_consumed().unwrap_or(0);
+    let mut storage = sandbox.store.into_data().imports;
+    let error = error.map(|err| error::blame(err, storage.failure.take()));
+    if let Some(err) = &error {
+        log::error!("{}", err.message);
+    }
 
+    let sta_time = storage.start_time.timestamp_millis();
+    let end_time = chrono::Local::now().timestamp_millis();
+    let counter = storage.counter as i64;
 
-            let tables = storage.into_tables().await;
-            let logs = if let Ok(it) = logs.lock() {
-                it.iter()
-                    .filter_map(|x| serde_json::to_string(x).ok())
-                    .collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
+    let tables = storage.into_tables().await;
+    let logs = if let Ok(it) = logs.lock() {
+        it.iter()
+            .filter_map(|x| serde_json::to_string(x).ok())
+            .collect::<Vec<_>>()
+    } else {
+        vec![]
+    };
 
-            let last_err = "".into();
+    let last_err = error
+        .as_ref()
+        .map(|err| err.message.clone())
+        .unwrap_or_default();
 
-            Ok(StreamingRunResponse {
-                tables,
-                logs,
-                last_err,
-                sta_time,
-                end_time,
-                counter,
-            })
-        }
+    StreamingRunResponse {
+        tables,
+        logs,
+        last_err,
+        sta_time,
+        end_time,
+        counter,
+        fuel_consumed,
+        error,
+        ..Default::default()
+    }
+}
+
+/// A run that failed before the program started.
+fn failed(error: RunError) -> StreamingRunResponse {
+    log::error!("{}", error.message);
+    StreamingRunResponse {
+        last_err: error.message.clone(),
+        error: Some(error),
+        ..Default::default()
     }
 }
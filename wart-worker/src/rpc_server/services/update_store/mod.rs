@@ -1,34 +1,43 @@
+use crate::auth::{self, Caller};
+use crate::backend::StoreUpdate;
 use crate::bindgen::*;
+use crate::metrics;
 use crate::GLOBALS;
 use anyhow::{anyhow, Result};
 use futures::StreamExt;
-use redis::AsyncCommands;
 
 use tokio::sync::mpsc;
 use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status, Streaming};
 
+use std::collections::HashMap;
+
 type UpdateStoreStream = ReceiverStream<Result<UpdateStoreResponse, Status>>;
 
 pub async fn update_store(
     request: Request<Streaming<UpdateStoreRequest>>,
 ) -> Result<Response<UpdateStoreStream>, Status> {
-    match update_store_impl(request.into_inner()).await {
+    let caller = auth::caller(&request);
+    match update_store_impl(caller, request.into_inner()).await {
         Ok(msg) => Ok(Response::new(msg)),
-        Err(err) => Err(Status::aborted(err.to_string())),
+        Err(err) => Err(super::status(err)),
     }
 }
 
 async fn update_store_impl(
+    caller: Caller,
     mut istream: Streaming<UpdateStoreRequest>,
 ) -> Result<UpdateStoreStream> {
-    let (mpsc_tx, mpsc_rx) = mpsc::channel(512);
+    let (mpsc_tx, mpsc_rx) = mpsc::channel(GLOBALS.config.channels.update_store);
     tokio::spawn(async move {
+        // the spaces of the sessions written so far, each looked up and
+        // checked for ownership once per stream
+        let mut spaces = HashMap::new();
         while let Some(message) = istream.next().await {
             let response = match message {
-                Ok(request) => match write_to_redis(request).await {
+                Ok(request) => match write_checked(&caller, &mut spaces, request).await {
                     Ok(msg) => Ok(msg),
-                    Err(err) => Err(Status::aborted(err.to_string())),
+                    Err(err) => Err(super::status(err)),
                 },
                 Err(err) => Err(err),
             };
@@ -42,7 +51,26 @@ async fn update_store_impl(
     Ok(ostream)
 }
 
-async fn write_to_redis(request: UpdateStoreRequest) -> Result<UpdateStoreResponse> {
+async fn write_checked(
+    caller: &Caller,
+    spaces: &mut HashMap<String, String>,
+    request: UpdateStoreRequest,
+) -> Result<UpdateStoreResponse> {
+    let space_name = match spaces.get(&request.token) {
+        Some(space_name) => space_name.clone(),
+        None => {
+            let session = GLOBALS.kv.get_session(&request.token).await?;
+            auth::check_owner(caller, &session)?;
+            spaces.insert(request.token.clone(), session.space_name.clone());
+            session.space_name
+        }
+    };
+    let result = write_to_store(request).await;
+    metrics::update_store(&space_name, &result);
+    result
+}
+
+async fn write_to_store(request: UpdateStoreRequest) -> Result<UpdateStoreResponse> {
     use update_store_request::MergeType;
 
     let UpdateStoreRequest {
@@ -55,77 +83,45 @@ async fn write_to_redis(request: UpdateStoreRequest) -> Result<UpdateStoreRespon
     let keys = keys.map(|s| s.data).ok_or(anyhow!("empty keys"))?;
     let vals = vals.and_then(|s| s.values);
 
-    let store_key = format!("wart:store:{}", token);
-    let mut con = GLOBALS.redis.get().await?;
-
-    if !con.exists::<_, bool>(&store_key).await? {
-        Err(anyhow!("no session: {}", token))?;
-    }
-
-    let mut ok_count = 0;
-    match merge_type {
+    let ok_count = match merge_type {
         x if x == MergeType::Del as i32 => {
-            for field in keys.iter() {
-                match con.hdel::<_, _, ()>(&store_key, field).await {
-                    Ok(_) => ok_count += 1,
-                    Err(_) => break,
-                }
-            }
+            GLOBALS
+                .kv
+                .update_store(&token, &keys, StoreUpdate::Del)
+                .await?
         }
         x if x == MergeType::Add as i32 => {
-            let vals = vals.ok_or(anyhow!("empty vals"))?;
-            match vals {
+            let vals = match vals.ok_or(anyhow!("empty vals"))? {
                 series::Values::Int32Values(vals) => {
-                    for (field, value) in keys.iter().zip(vals.data.iter()) {
-                        match con.hincr::<_, _, _, ()>(&store_key, field, *value).await {
-                            Ok(_) => ok_count += 1,
-                            Err(_) => break,
-                        }
-                    }
+                    vals.data.into_iter().map(|v| v as i64).collect()
                 }
-                series::Values::Int64Values(vals) => {
-                    for (field, value) in keys.iter().zip(vals.data.iter()) {
-                        match con.hincr::<_, _, _, ()>(&store_key, field, *value).await {
-                            Ok(_) => ok_count += 1,
-                            Err(_) => break,
-                        }
-                    }
-                }
-                _ => (),
-            }
+                series::Values::Int64Values(vals) => vals.data,
+                _ => vec![],
+            };
+            GLOBALS
+                .kv
+                .update_store(&token, &keys, StoreUpdate::Add(&vals))
+                .await?
         }
         x if x == MergeType::Mov as i32 => {
-            let vals = vals.ok_or(anyhow!("empty vals"))?;
-            match vals {
+            let vals = match vals.ok_or(anyhow!("empty vals"))? {
                 series::Values::Int32Values(vals) => {
-                    for (field, value) in keys.iter().zip(vals.data.iter()) {
-                        match con.hset::<_, _, _, ()>(&store_key, field, *value).await {
-                            Ok(_) => ok_count += 1,
-                            Err(_) => break,
-                        }
-                    }
+                    vals.data.into_iter().map(|v| v.to_string()).collect()
                 }
                 series::Values::Int64Values(vals) => {
-                    for (field, value) in keys.iter().zip(vals.data.iter()) {
-                        match con.hset::<_, _, _, ()>(&store_key, field, *value).await {
-                            Ok(_) => ok_count += 1,
-                            Err(_) => break,
-                        }
-                    }
+                    vals.data.into_iter().map(|v| v.to_string()).collect()
                 }
-                series::Values::StringValues(vals) => {
-                    for (field, value) in keys.iter().zip(vals.data.iter()) {
-                        match con.hset::<_, _, _, ()>(&store_key, field, value).await {
-                            Ok(_) => ok_count += 1,
-                            Err(_) => break,
-                        }
-                    }
-                }
-                _ => (),
-            }
+                series::Values::StringValues(vals) => vals.data,
+                _ => vec![],
+            };
+            GLOBALS
+                .kv
+                .update_store(&token, &keys, StoreUpdate::Mov(&vals))
+                .await?
         }
-        _ => (),
-    }
+        _ => 0,
+    };
+    GLOBALS.kv.keep_alive(&token).await?;
 
     Ok(UpdateStoreResponse { ok_count })
 }
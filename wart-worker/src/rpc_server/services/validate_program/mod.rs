@@ -0,0 +1,23 @@
+use crate::bindgen::*;
+use crate::wasm::validate::validate;
+use crate::GLOBALS;
+use anyhow::Result;
+use tonic::{Request, Response, Status};
+
+pub async fn validate_program(
+    request: Request<ValidateProgramRequest>,
+) -> Result<Response<ValidateProgramResponse>, Status> {
+    match validate_program_impl(request.into_inner()).await {
+        Ok(msg) => Ok(Response::new(msg)),
+        Err(err) => Err(Status::aborted(err.to_string())),
+    }
+}
+
+async fn validate_program_impl(request: ValidateProgramRequest) -> Result<ValidateProgramResponse> {
+    let ValidateProgramRequest { program } = request;
+
+    let result = tokio::task::spawn_blocking(move || validate(&GLOBALS.engine, &program)).await?;
+    let diagnostics = result.err().unwrap_or_default();
+
+    Ok(ValidateProgramResponse { diagnostics })
+}
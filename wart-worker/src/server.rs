@@ -1,41 +1,50 @@
+mod auth;
 mod backend;
 mod bindgen;
+mod config;
+mod health;
 mod log_tracer;
+mod metrics;
 mod rpc_server;
+mod telemetry;
+mod tls;
 mod wasm;
 
+use auth::Authenticator;
+use bindgen::wart_admin_server::WartAdminServer;
 use bindgen::wart_worker_server::WartWorkerServer;
-use rpc_server::Router;
+use config::{Config, GraphBackendConfig, KvStoreConfig};
+use rpc_server::{Admin, Router};
+use wasm::runs::RunRegistry;
+use wasm::{EpochClock, SandboxCache};
 
 use lazy_static::lazy_static;
-use serde::Deserialize;
+use tonic_health::ServingStatus;
 
-use backend::RedisConnectionManager;
-use backend::StorageConnectionManager;
+use backend::{EmbeddedKvStore, KvStore, RedisKvStore};
+use backend::{GraphBackend, MemoryGraphBackend, StorageGraphBackend};
 
-#[derive(Deserialize)]
-pub struct Config {
-    #[serde(rename = "rpc_server")]
-    rpc_server: std::net::SocketAddr,
+pub struct Globals {
+    config: Config,
 
-    #[serde(rename = "redis_server")]
-    redis_server: std::net::SocketAddr,
+    kv: Box<dyn KvStore>,
 
-    #[serde(rename = "storage_server")]
-    storage_server: std::net::SocketAddr,
+    graph: Box<dyn GraphBackend>,
 
-    #[serde(rename = "cores")]
-    num_workers: usize,
-}
+    engine: wasmtime::Engine,
 
-pub struct Globals {
-    config: Config,
+    clock: EpochClock,
 
-    #[allow(dead_code)]
-    redis: mobc::Pool<RedisConnectionManager>,
+    sandboxes: SandboxCache,
 
-    #[allow(dead_code)]
-    storage: mobc::Pool<StorageConnectionManager>,
+    runs: RunRegistry,
+
+    server_tls: Option<tonic::transport::ServerTlsConfig>,
+
+    auth: Option<Authenticator>,
+
+    /// Exports the spans of the runs, see `telemetry::layer`.
+    tracer: Option<opentelemetry::sdk::trace::Tracer>,
 
     #[allow(dead_code)]
     runtime: tokio::runtime::Runtime,
@@ -44,68 +53,229 @@ pub struct Globals {
 lazy_static! {
     pub static ref GLOBALS: Globals = {
         use std::process::exit;
-        use std::{env, fs};
 
-        let config: Config = match env::args().nth(1) {
-            Some(path) => {
-                let s = fs::read(path).unwrap();
-                serde_yaml::from_slice(&s).unwrap()
+        let config = config::load_or_exit();
+
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(config.num_workers)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        // the OTLP exporter sends its batches from this runtime
+        let tracer = {
+            let _entered = runtime.enter();
+            match telemetry::init(&config) {
+                Ok(tracer) => tracer,
+                Err(err) => {
+                    eprintln!("can't set up tracing: {:#}", err);
+                    exit(1)
+                }
             }
-            None => {
-                eprintln!("Usage: wart config.yaml");
+        };
+
+        let kv: Box<dyn KvStore> = match (&config.kv_store, config.redis_server) {
+            (KvStoreConfig::Redis, Some(addr)) => {
+                match RedisKvStore::new(addr, &config.pools.redis, config.redis_tls.as_ref()) {
+                    Ok(store) => Box::new(store),
+                    Err(err) => {
+                        eprintln!("can't set up redis kv_store: {:#}", err);
+                        exit(1)
+                    }
+                }
+            }
+            (KvStoreConfig::Redis, None) => unreachable!("checked by Config::validate"),
+            (KvStoreConfig::Embedded { snapshot, .. }, _) => {
+                match EmbeddedKvStore::open(snapshot.clone()) {
+                    Ok(store) => Box::new(store),
+                    Err(err) => {
+                        eprintln!("can't open embedded kv_store: {}", err);
+                        exit(1)
+                    }
+                }
+            }
+        };
+
+        let graph: Box<dyn GraphBackend> = match &config.graph_backend {
+            GraphBackendConfig::Grpc => match StorageGraphBackend::new(
+                config.storage_server,
+                &config.pools.storage,
+                &config.timeouts,
+                config.storage_tls.as_ref(),
+            ) {
+                Ok(backend) => Box::new(backend),
+                Err(err) => {
+                    eprintln!("can't set up storage client: {:#}", err);
+                    exit(1)
+                }
+            },
+            GraphBackendConfig::Memory { path } => match MemoryGraphBackend::load(path) {
+                Ok(backend) => Box::new(backend),
+                Err(err) => {
+                    eprintln!("can't load graph from {}: {}", path.display(), err);
+                    exit(1)
+                }
+            },
+        };
+
+        let engine = match wasmtime::Engine::new(&config.engine.to_wasmtime()) {
+            Ok(engine) => engine,
+            Err(err) => {
+                eprintln!("can't create engine: {}", err);
                 exit(1)
             }
         };
 
-        let redis = {
-            let manager = RedisConnectionManager::new(config.redis_server);
-            mobc::Pool::builder()
-                .get_timeout(None)
-                .max_open(128)
-                .max_idle(128)
-                .build(manager)
+        let clock = match EpochClock::start(engine.clone(), config.engine.epoch_tick()) {
+            Ok(clock) => clock,
+            Err(err) => {
+                eprintln!("can't start epoch clock: {}", err);
+                exit(1)
+            }
         };
 
-        let storage = {
-            let manager = StorageConnectionManager::new(config.storage_server);
-            mobc::Pool::builder()
-                .get_timeout(None)
-                .max_open(8)
-                .max_idle(8)
-                .build(manager)
+        let server_tls = match config.tls.as_ref().map(tls::server).transpose() {
+            Ok(tls) => tls,
+            Err(err) => {
+                eprintln!("can't set up tls: {:#}", err);
+                exit(1)
+            }
         };
 
-        let runtime = tokio::runtime::Builder::new_multi_thread()
-            .worker_threads(config.num_workers)
-            .enable_all()
-            .build()
-            .unwrap();
+        let auth = config.auth.as_ref().map(Authenticator::new);
+
+        let sandboxes = SandboxCache::new(config.sandbox_cache);
 
         Globals {
             config,
-            redis,
-            storage,
+            kv,
+            graph,
+            engine,
+            clock,
+            sandboxes,
+            runs: RunRegistry::default(),
+            server_tls,
+            auth,
+            tracer,
             runtime,
         }
     };
 }
 
 fn main() -> Result<(), tonic::transport::Error> {
-    tracing_subscriber::fmt::init();
+    // loads the config and sets up logging before anything is served
+    lazy_static::initialize(&GLOBALS);
     let runtime = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .unwrap();
 
-    runtime.block_on(async {
+    let result = runtime.block_on(async {
+        if let KvStoreConfig::Embedded {
+            snapshot_interval, ..
+        } = GLOBALS.config.kv_store
+        {
+            tokio::spawn(async move {
+                let period = std::time::Duration::from_secs(snapshot_interval);
+                let mut interval = tokio::time::interval(period);
+                loop {
+                    interval.tick().await;
+                    if let Err(err) = GLOBALS.kv.flush().await {
+                        log::error!("can't write snapshot: {}", err);
+                    }
+                }
+            });
+        }
+
+        tokio::spawn(async move {
+            let period = std::time::Duration::from_secs(GLOBALS.config.session_sweep_interval);
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                rpc_server::services::close_session::sweep_expired_sessions().await;
+            }
+        });
+
+        if let Some(addr) = GLOBALS.config.metrics_server {
+            log::info!("metrics_server: {}", addr);
+            tokio::spawn(async move {
+                if let Err(err) = metrics::serve(addr).await {
+                    log::error!("metrics_server stopped: {}", err);
+                }
+            });
+        }
+
+        // NOT_SERVING until the first check of the backends passes
+        let (mut reporter, health_server) = tonic_health::server::health_reporter();
+        health::set_all(&mut reporter, ServingStatus::NotServing).await;
+        let watcher = tokio::spawn(health::watch(reporter.clone()));
+
+        let reflection = match tonic_reflection::server::Builder::configure()
+            .register_encoded_file_descriptor_set(bindgen::FILE_DESCRIPTOR_SET)
+            .build()
+        {
+            Ok(reflection) => reflection,
+            Err(err) => {
+                log::error!("can't set up reflection: {}", err);
+                std::process::exit(1)
+            }
+        };
+
+        let shutdown = async move {
+            shutdown_signal().await;
+            log::info!("shutting down");
+            watcher.abort();
+            health::set_all(&mut reporter, ServingStatus::NotServing).await;
+            let grace = std::time::Duration::from_secs(GLOBALS.config.shutdown_grace);
+            tokio::time::sleep(grace).await;
+        };
+
         log::info!("rpc_server: {}", GLOBALS.config.rpc_server);
-        let router = WartWorkerServer::new(Router::new());
-        tonic::transport::Server::builder()
+        let router = WartWorkerServer::with_interceptor(Router::new(), auth::intercept);
+        let admin = WartAdminServer::with_interceptor(Admin::new(), auth::intercept_admin);
+        let mut server = tonic::transport::Server::builder();
+        if let Some(tls) = &GLOBALS.server_tls {
+            log::info!("tls enabled");
+            server = server.tls_config(tls.clone())?;
+        }
+        if GLOBALS.auth.is_some() {
+            log::info!("auth enabled");
+        }
+        let served = server
             .add_service(router)
-            .serve(GLOBALS.config.rpc_server)
-            .await?;
+            .add_service(admin)
+            .add_service(health_server)
+            .add_service(reflection)
+            .serve_with_shutdown(GLOBALS.config.rpc_server, shutdown)
+            .await;
+
+        // what changed since the last snapshot would be lost otherwise
+        if let Err(err) = GLOBALS.kv.flush().await {
+            log::error!("can't write snapshot: {}", err);
+        }
+        served?;
         Ok(())
-    })
+    });
+    telemetry::shutdown();
+    result
+}
+
+/// Completes on SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut terminate = match signal(SignalKind::terminate()) {
+        Ok(terminate) => terminate,
+        Err(err) => {
+            log::error!("can't listen for SIGTERM: {}", err);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => {},
+        _ = terminate.recv() => {},
+    }
 }
 
 // #[tokio::main]
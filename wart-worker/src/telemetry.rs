@@ -0,0 +1,126 @@
+use crate::config::{Config, SpanExporterConfig, TracingConfig};
+
+use anyhow::{Context, Result};
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::sdk::export::trace::stdout;
+use opentelemetry::sdk::propagation::TraceContextPropagator;
+use opentelemetry::sdk::trace::{self as sdktrace, Sampler, Tracer};
+use opentelemetry::sdk::Resource;
+use opentelemetry::KeyValue;
+use tonic::metadata::{KeyRef, MetadataKey, MetadataMap};
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+
+use std::fs::OpenOptions;
+
+const SERVICE_NAME: &str = "wart-worker";
+
+/// Sets up logging, and the export of the spans when `tracing` is given.
+/// Must be called within a tokio runtime, which runs the OTLP exporter.
+pub fn init(config: &Config) -> Result<Option<Tracer>> {
+    let tracer = config.tracing.as_ref().map(tracer).transpose()?;
+    tracing_subscriber::registry()
+        .with(LevelFilter::from_level(config.log.level()))
+        .with(tracing_subscriber::fmt::layer())
+        .with(layer(tracer.as_ref()))
+        .init();
+    Ok(tracer)
+}
+
+fn tracer(config: &TracingConfig) -> Result<Tracer> {
+    let trace_config = sdktrace::config()
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(Resource::new(vec![KeyValue::new(
+            "service.name",
+            SERVICE_NAME,
+        )]));
+
+    let tracer = match &config.exporter {
+        SpanExporterConfig::File { path } => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("can't open {}", path.display()))?;
+            stdout::new_pipeline()
+                .with_writer(file)
+                .with_trace_config(trace_config)
+                .install_simple()
+        }
+        SpanExporterConfig::Otlp { endpoint } => opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint.clone()),
+            )
+            .with_trace_config(trace_config)
+            .install_batch(opentelemetry::runtime::Tokio)?,
+    };
+
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+    Ok(tracer)
+}
+
+/// Exports the spans of a subscriber, `None` when tracing is off. Runs have
+/// subscribers of their own, see `streaming_run_sandbox`.
+pub fn layer<S>(tracer: Option<&Tracer>) -> Option<OpenTelemetryLayer<S, Tracer>>
+where
+    S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracer.map(|tracer| tracing_opentelemetry::layer().with_tracer(tracer.clone()))
+}
+
+/// Sends the spans still buffered, before the process exits.
+pub fn shutdown() {
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+struct MetadataExtractor<'a>(&'a MetadataMap);
+
+impl<'a> Extractor for MetadataExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .map(|key| match key {
+                KeyRef::Ascii(key) => key.as_str(),
+                KeyRef::Binary(key) => key.as_str(),
+            })
+            .collect()
+    }
+}
+
+struct MetadataInjector<'a>(&'a mut MetadataMap);
+
+impl<'a> Injector for MetadataInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(key), Ok(value)) = (MetadataKey::from_bytes(key.as_bytes()), value.parse()) {
+            self.0.insert(key, value);
+        }
+    }
+}
+
+/// Continues the trace of the client when the request carries a W3C
+/// `traceparent`, the span starts a trace of its own otherwise.
+pub fn follow(span: &tracing::Span, metadata: &MetadataMap) {
+    let parent = opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.extract(&MetadataExtractor(metadata))
+    });
+    span.set_parent(parent);
+}
+
+/// Passes the trace of the current span on to the storage server.
+pub fn inject(metadata: &mut MetadataMap) {
+    let context = tracing::Span::current().context();
+    opentelemetry::global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&context, &mut MetadataInjector(metadata))
+    });
+}
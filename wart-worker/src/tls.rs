@@ -0,0 +1,190 @@
+use crate::config::{ClientTlsConfig, ServerTlsConfig};
+
+use anyhow::{anyhow, Context, Result};
+use tokio_rustls::rustls;
+use tonic::transport::{Certificate, Identity};
+
+use std::convert::TryFrom;
+use std::path::Path;
+use std::sync::Arc;
+
+fn read(path: &Path) -> Result<Vec<u8>> {
+    std::fs::read(path).with_context(|| format!("can't read {}", path.display()))
+}
+
+fn identity(cert: &Path, key: &Path) -> Result<Identity> {
+    Ok(Identity::from_pem(read(cert)?, read(key)?))
+}
+
+/// TLS of the listener of this worker, verifying the certificates of the
+/// clients as well when `client_ca` is given.
+pub fn server(config: &ServerTlsConfig) -> Result<tonic::transport::ServerTlsConfig> {
+    let mut tls =
+        tonic::transport::ServerTlsConfig::new().identity(identity(&config.cert, &config.key)?);
+    if let Some(ca) = &config.client_ca {
+        tls = tls.client_ca_root(Certificate::from_pem(read(ca)?));
+    }
+    Ok(tls)
+}
+
+/// TLS of the connections to `storage_server`.
+pub fn storage(config: &ClientTlsConfig) -> Result<tonic::transport::ClientTlsConfig> {
+    let mut tls = tonic::transport::ClientTlsConfig::new()
+        .ca_certificate(Certificate::from_pem(read(&config.ca)?))
+        .domain_name(config.domain.clone());
+    if let (Some(cert), Some(key)) = (&config.cert, &config.key) {
+        tls = tls.identity(identity(cert, key)?);
+    }
+    Ok(tls)
+}
+
+/// TLS of the connections to `redis_server`. The redis crate only speaks
+/// TLS through native-tls with the system roots, so the streams are set up
+/// here and handed over to it.
+#[derive(Clone)]
+pub struct RedisTls {
+    pub connector: tokio_rustls::TlsConnector,
+    pub domain: rustls::ServerName,
+}
+
+pub fn redis(config: &ClientTlsConfig) -> Result<RedisTls> {
+    let mut roots = rustls::RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut &read(&config.ca)?[..])? {
+        roots
+            .add(&rustls::Certificate(cert))
+            .with_context(|| format!("bad certificate in {}", config.ca.display()))?;
+    }
+
+    let builder = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots);
+    let client = match (&config.cert, &config.key) {
+        (Some(cert), Some(key)) => {
+            let certs = rustls_pemfile::certs(&mut &read(cert)?[..])?
+                .into_iter()
+                .map(rustls::Certificate)
+                .collect();
+            builder.with_single_cert(certs, private_key(key)?)?
+        }
+        _ => builder.with_no_client_auth(),
+    };
+
+    let domain = rustls::ServerName::try_from(&config.domain[..])
+        .map_err(|_| anyhow!("invalid domain: {}", config.domain))?;
+    Ok(RedisTls {
+        connector: tokio_rustls::TlsConnector::from(Arc::new(client)),
+        domain,
+    })
+}
+
+fn private_key(path: &Path) -> Result<rustls::PrivateKey> {
+    use rustls_pemfile::Item;
+
+    for item in rustls_pemfile::read_all(&mut &read(path)?[..])? {
+        match item {
+            Item::PKCS8Key(key) | Item::RSAKey(key) | Item::ECKey(key) => {
+                return Ok(rustls::PrivateKey(key))
+            }
+            _ => continue,
+        }
+    }
+    Err(anyhow!("no private key in {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bindgen::wart_worker_server::WartWorkerServer;
+    use crate::rpc_server::Router;
+
+    use std::path::PathBuf;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::{TcpListener, TcpStream};
+
+    /// Runs gen-certs.sh in a directory of its own, so that every call comes
+    /// with another CA.
+    fn gen_certs(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("wart-tls-{}-{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("gen-certs.sh");
+        std::fs::copy(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/gen-certs.sh"),
+            &script,
+        )
+        .unwrap();
+        let status = std::process::Command::new("bash")
+            .arg(&script)
+            .output()
+            .expect("gen-certs.sh needs bash and openssl")
+            .status;
+        assert!(status.success(), "gen-certs.sh failed");
+        dir.join("certs")
+    }
+
+    fn client(ca: &Path, certs: &Path) -> ClientTlsConfig {
+        ClientTlsConfig {
+            ca: ca.join("ca.pem"),
+            domain: "wart.local".to_string(),
+            cert: Some(certs.join("client.pem")),
+            key: Some(certs.join("client.key")),
+        }
+    }
+
+    /// Whether the server speaks to the client after the handshake, as a
+    /// rejected certificate only shows in the alert following it.
+    async fn accepted(tls: &RedisTls, addr: std::net::SocketAddr) -> bool {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let mut stream = match tls.connector.connect(tls.domain.clone(), stream).await {
+            Ok(stream) => stream,
+            Err(_) => return false,
+        };
+        let mut buf = [0; 64];
+        matches!(stream.read(&mut buf).await, Ok(n) if n > 0)
+    }
+
+    #[test]
+    fn loads_the_generated_certificates() {
+        let certs = gen_certs("load");
+        let config = ServerTlsConfig {
+            cert: certs.join("server.pem"),
+            key: certs.join("server.key"),
+            client_ca: Some(certs.join("ca.pem")),
+        };
+        server(&config).unwrap();
+        storage(&client(&certs, &certs)).unwrap();
+        redis(&client(&certs, &certs)).unwrap();
+
+        let err = redis(&client(&certs, &certs.join("missing")))
+            .err()
+            .unwrap();
+        assert!(format!("{:#}", err).contains("can't read"));
+    }
+
+    #[tokio::test]
+    async fn rejects_clients_not_signed_by_the_ca() {
+        let certs = gen_certs("trusted");
+        let other = gen_certs("other");
+        let config = ServerTlsConfig {
+            cert: certs.join("server.pem"),
+            key: certs.join("server.key"),
+            client_ca: Some(certs.join("ca.pem")),
+        };
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let incoming = async_stream::stream! {
+            loop {
+                yield listener.accept().await.map(|(stream, _)| stream);
+            }
+        };
+        let server = tonic::transport::Server::builder()
+            .tls_config(server(&config).unwrap())
+            .unwrap()
+            .add_service(WartWorkerServer::new(Router::new()))
+            .serve_with_incoming(incoming);
+        tokio::spawn(server);
+
+        assert!(accepted(&redis(&client(&certs, &certs)).unwrap(), addr).await);
+        assert!(!accepted(&redis(&client(&certs, &other)).unwrap(), addr).await);
+    }
+}
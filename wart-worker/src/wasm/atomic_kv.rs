@@ -1,120 +1,112 @@
-use crate::bindgen::imports::MergeType;
-
-use log;
-
-use lazy_static::lazy_static;
-
-const LUA_QUERY_BASE: &str = "
-    local prefk = KEYS[1]
-    local epoch = tonumber(ARGV[1])
-    local field = ARGV[2]
-    local defva = ARGV[3]
-    for ep = epoch,0,-1 do
-        local key = prefk .. ep
-        local value = redis.hget(key, field)
-        if (value) then
-            redis.hdel(key, field)
-            break
-        end
-    end
-    if (not value) then
-        value = defva
-    end
-    local key = prefk .. epoch
-    redis.hset(key, field, value)
-";
-
-lazy_static! {
-    static ref REDIS_QUERY_KV: redis::Script = {
-        let lua_script = format!(
-            "{} {}",
-            LUA_QUERY_BASE,
-            "
-            return value
-        "
-        );
-        redis::Script::new(&lua_script)
-    };
-    static ref REDIS_UPDATE_KV: redis::Script = {
-        let lua_script = format!(
-            "{} {}",
-            LUA_QUERY_BASE,
-            "
-            local argum = ARGV[4]
-            local merge = ARGV[5]
-            if (merge == 'add') then
-                local key = prefk .. (epoch + 1)
-                local value = tonumber(value)
-                local argum = tonumber(argum)
-                return redis.hset(key, field, value + argum)
-            elseif (merge == 'mov') then
-                local key = prefk .. (epoch + 1)
-                return redis.hset(key, field, argum)
-            else
-                return 0
-            end
-        "
-        );
-        redis::Script::new(&lua_script)
+use super::error::ImportError;
+use crate::bindgen::imports::{MergeType, StorageErrorKind};
+use crate::GLOBALS;
+
+/// Values are kept in their string form by the KV stores, `KIND` tells the
+/// store how to merge them.
+pub trait AtomicKind: Sized {
+    const KIND: &'static str;
+
+    fn encode(&self) -> String;
+
+    fn decode(s: &str) -> Option<Self>;
+}
+
+impl AtomicKind for bool {
+    const KIND: &'static str = "bol";
+
+    fn encode(&self) -> String {
+        if *self { "1" } else { "0" }.into()
+    }
+
+    fn decode(s: &str) -> Option<Self> {
+        match s {
+            "1" => Some(true),
+            "0" => Some(false),
+            _ => s.parse().ok(),
+        }
+    }
+}
+
+macro_rules! atomic_kind {
+    ($t:ty, $kind:expr) => {
+        impl AtomicKind for $t {
+            const KIND: &'static str = $kind;
+
+            fn encode(&self) -> String {
+                self.to_string()
+            }
+
+            fn decode(s: &str) -> Option<Self> {
+                s.parse().ok()
+            }
+        }
     };
 }
 
-pub async fn atomic_query_kv<T, C>(
-    prefk: &str,
+atomic_kind!(i32, "int");
+atomic_kind!(i64, "int");
+atomic_kind!(f32, "flt");
+atomic_kind!(f64, "flt");
+atomic_kind!(String, "txt");
+
+pub async fn atomic_query_kv<T>(
+    token: &str,
     epoch: u64,
-    field: &str,
+    fields: &[&str],
     defva: &T,
-    con: &mut C,
-) -> Option<T>
+) -> Result<Vec<T>, ImportError>
 where
-    T: redis::ToRedisArgs + redis::FromRedisValue,
-    C: redis::aio::ConnectionLike,
+    T: AtomicKind,
 {
-    let val: T = REDIS_QUERY_KV
-        .key(prefk)
-        .arg(epoch)
-        .arg(field)
-        .arg(defva)
-        .invoke_async(con)
+    const IMPORT: &str = "storage::query-kv";
+
+    let val = GLOBALS
+        .kv
+        .query_kv(token, epoch, fields, &defva.encode())
         .await
-        .map_err(|err| {
-            log::error!("redis error: {}", err);
-            err
+        .map_err(|err| ImportError::backend(IMPORT, &err))?;
+
+    val.iter()
+        .map(|v| {
+            T::decode(v).ok_or_else(|| {
+                let message = format!("can't decode {} as {}", v, T::KIND);
+                ImportError::new(IMPORT, StorageErrorKind::TypeMismatch, message)
+            })
         })
-        .ok()?;
-    Some(val)
+        .collect()
 }
 
-pub async fn atomic_update_kv<T, C>(
-    prefk: &str,
+pub async fn atomic_update_kv<T>(
+    token: &str,
     epoch: u64,
-    field: &str,
-    defva: &T,
-    argum: &T,
+    fields: &[&str],
+    argums: &[T],
     merge: MergeType,
-    con: &mut C,
-) -> Option<i32>
+) -> Result<u64, ImportError>
 where
-    T: redis::ToRedisArgs,
-    C: redis::aio::ConnectionLike,
+    T: AtomicKind,
 {
-    let merge = match merge {
-        MergeType::Add => "add",
-        MergeType::Mov => "mov",
-    };
-    let val: i32 = REDIS_QUERY_KV
-        .key(prefk)
-        .arg(epoch)
-        .arg(field)
-        .arg(defva)
-        .arg(argum)
-        .arg(merge)
-        .invoke_async(con)
+    const IMPORT: &str = "storage::update-kv";
+
+    if fields.len() != argums.len() {
+        let message = format!(
+            "mismatched length of keys and vals: {} != {}",
+            fields.len(),
+            argums.len()
+        );
+        return Err(ImportError::new(
+            IMPORT,
+            StorageErrorKind::InvalidArgument,
+            message,
+        ));
+    }
+
+    let argums = argums.iter().map(|v| v.encode()).collect::<Vec<_>>();
+
+    GLOBALS
+        .kv
+        .update_kv(token, epoch, fields, &argums, T::KIND, merge)
         .await
-        .map_err(|err| {
-            log::error!("redis error: {}", err);
-            err
-        })
-        .ok()?;
-    Some(val)
+        .map_err(|err| ImportError::backend(IMPORT, &err))
 }
@@ -0,0 +1,90 @@
+use super::{SandboxManager, Storage};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+struct Entry {
+    module_hash: u64,
+    manager: SandboxManager<Storage>,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Entries {
+    clock: u64,
+    map: HashMap<String, Entry>,
+}
+
+/// Compiled sessions of this worker, keyed by token and evicted in LRU order.
+/// An entry is only reused while the module hash of the session matches, the
+/// hash is taken over the serialized module including the version of the
+/// engine settings it was compiled with.
+pub struct SandboxCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl SandboxCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn get(&self, token: &str, module_hash: u64) -> Option<SandboxManager<Storage>> {
+        let mut entries = self.entries.lock().ok()?;
+        entries.clock += 1;
+        let clock = entries.clock;
+
+        let entry = entries.map.get_mut(token)?;
+        if entry.module_hash != module_hash {
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.manager.clone())
+    }
+
+    pub fn insert(&self, token: &str, module_hash: u64, manager: SandboxManager<Storage>) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut entries = match self.entries.lock() {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        entries.clock += 1;
+        let last_used = entries.clock;
+
+        entries.map.insert(
+            token.into(),
+            Entry {
+                module_hash,
+                manager,
+                last_used,
+            },
+        );
+
+        while entries.map.len() > self.capacity {
+            let lru = entries
+                .map
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(token, _)| token.clone());
+            match lru {
+                Some(token) => {
+                    log::debug!("evicting sandbox manager: {}", token);
+                    entries.map.remove(&token);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn remove(&self, token: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.map.remove(token);
+        }
+    }
+}
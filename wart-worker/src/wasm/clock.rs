@@ -0,0 +1,50 @@
+use tokio::sync::watch;
+use wasmtime::Engine;
+
+use std::time::Duration;
+
+/// Advances the epoch of the shared engine from a single thread. Sandboxes
+/// yield on every tick and their deadlines are counted in ticks as well, so
+/// concurrent runs don't need a timer of their own.
+pub struct EpochClock {
+    tick: Duration,
+    ticks: watch::Receiver<u64>,
+}
+
+impl EpochClock {
+    pub fn start(engine: Engine, tick: Duration) -> std::io::Result<Self> {
+        let (tx, rx) = watch::channel(0u64);
+        std::thread::Builder::new()
+            .name("wart-epoch".into())
+            .spawn(move || {
+                let mut n = 0u64;
+                loop {
+                    std::thread::sleep(tick);
+                    engine.increment_epoch();
+                    n += 1;
+                    if tx.send(n).is_err() {
+                        break;
+                    }
+                }
+            })?;
+        Ok(Self { tick, ticks: rx })
+    }
+
+    /// Number of ticks covering `ms` milliseconds.
+    pub fn ticks(&self, ms: u64) -> u64 {
+        let tick = self.tick.as_millis().max(1) as u64;
+        (ms + tick - 1) / tick
+    }
+
+    /// Resolves once `ticks` ticks have passed.
+    pub async fn deadline(&self, ticks: u64) {
+        let mut rx = self.ticks.clone();
+        let end = *rx.borrow() + ticks;
+        while *rx.borrow() < end {
+            if rx.changed().await.is_err() {
+                // the clock never stops while the worker is alive
+                futures::future::pending::<()>().await;
+            }
+        }
+    }
+}
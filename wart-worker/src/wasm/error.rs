@@ -0,0 +1,126 @@
+use super::sandbox::{CallError, InstantiateError};
+use crate::backend;
+use crate::bindgen::imports::{StorageError, StorageErrorKind};
+use crate::bindgen::{run_error::Kind, RunError};
+
+use wasmtime::Trap;
+
+/// Why an import failed. Guests of the option-based imports only see a `None`,
+/// so the last one is kept in `Storage` and reported along with the run.
+#[derive(Debug, Clone)]
+pub struct ImportError {
+    pub import: &'static str,
+    pub kind: StorageErrorKind,
+    pub message: String,
+}
+
+impl ImportError {
+    pub fn new(import: &'static str, kind: StorageErrorKind, message: impl ToString) -> Self {
+        Self {
+            import,
+            kind,
+            message: message.to_string(),
+        }
+    }
+
+    /// Classifies an error of the graph backend or the KV store, along with
+    /// the contexts it was given.
+    pub fn backend(import: &'static str, err: &anyhow::Error) -> Self {
+        Self::new(import, backend::error_kind(err), format!("{:#}", err))
+    }
+}
+
+impl From<ImportError> for StorageError {
+    fn from(err: ImportError) -> Self {
+        StorageError {
+            kind: err.kind,
+            message: err.message,
+        }
+    }
+}
+
+fn import_kind(kind: StorageErrorKind) -> Kind {
+    match kind {
+        StorageErrorKind::NotFound
+        | StorageErrorKind::InvalidArgument
+        | StorageErrorKind::TypeMismatch => Kind::InvalidArgs,
+        StorageErrorKind::Unavailable | StorageErrorKind::Internal => Kind::StorageUnavailable,
+        StorageErrorKind::LimitExceeded => Kind::OutOfMemory,
+    }
+}
+
+pub fn of_kind(kind: Kind, message: impl ToString) -> RunError {
+    RunError {
+        kind: kind as i32,
+        message: message.to_string(),
+        ..Default::default()
+    }
+}
+
+fn backtrace(trap: &Trap) -> Vec<String> {
+    trap.trace()
+        .iter()
+        .map(|frame| {
+            let func = match frame.func_name() {
+                Some(name) => name.to_string(),
+                None => format!("<wasm function {}>", frame.func_index()),
+            };
+            match frame.module_name() {
+                Some(module) => format!("{}!{}", module, func),
+                None => func,
+            }
+        })
+        .collect()
+}
+
+impl From<&Trap> for RunError {
+    fn from(trap: &Trap) -> Self {
+        RunError {
+            kind: Kind::Trap as i32,
+            message: trap.display_reason().to_string(),
+            backtrace: backtrace(trap),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&CallError> for RunError {
+    fn from(err: &CallError) -> Self {
+        match err {
+            CallError::OutOfFuel(_) => of_kind(Kind::OutOfFuel, err),
+            CallError::OutOfMemory(trap) => RunError {
+                kind: Kind::OutOfMemory as i32,
+                ..trap.into()
+            },
+            CallError::Trap(trap) => trap.into(),
+        }
+    }
+}
+
+impl From<&anyhow::Error> for RunError {
+    fn from(err: &anyhow::Error) -> Self {
+        if let Some(denied) = err.downcast_ref::<InstantiateError>() {
+            return match denied {
+                InstantiateError::OutOfMemory(_) => of_kind(Kind::OutOfMemory, denied),
+            };
+        }
+        match err.downcast_ref::<Trap>() {
+            Some(trap) => trap.into(),
+            None => of_kind(Kind::Unknown, err),
+        }
+    }
+}
+
+/// Blames the failure of a run on the import that failed last, the guest
+/// usually traps right after an import returns `None`. See `Storage::failure`
+/// for when an import is still to blame.
+pub fn blame(mut err: RunError, import: Option<ImportError>) -> RunError {
+    if let Some(import) = import {
+        if err.kind == Kind::Trap as i32 {
+            err.kind = import_kind(import.kind) as i32;
+            err.message = format!("{}: {}", import.import, import.message);
+        }
+        err.failed_import = import.import.into();
+    }
+    err
+}
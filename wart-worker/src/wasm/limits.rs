@@ -0,0 +1,126 @@
+use crate::bindgen::*;
+
+use serde::{Deserialize, Serialize};
+use wasmtime::{StoreLimits, StoreLimitsBuilder};
+
+/// Resource limits of a single run, `0` stands for no limit.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Bytes of linear memory of each instance.
+    pub max_memory: u64,
+    pub max_instances: u64,
+    pub max_data_frames: u64,
+    /// Rows over all the returned data-frames.
+    pub max_rows: u64,
+    /// Bytes over all the returned data-frames.
+    pub max_bytes: u64,
+}
+
+fn cap(limit: u64, max: u64) -> u64 {
+    match (limit, max) {
+        (x, 0) | (0, x) => x,
+        (x, y) => x.min(y),
+    }
+}
+
+impl Limits {
+    pub fn capped_by(&self, max: &Limits) -> Limits {
+        Limits {
+            max_memory: cap(self.max_memory, max.max_memory),
+            max_instances: cap(self.max_instances, max.max_instances),
+            max_data_frames: cap(self.max_data_frames, max.max_data_frames),
+            max_rows: cap(self.max_rows, max.max_rows),
+            max_bytes: cap(self.max_bytes, max.max_bytes),
+        }
+    }
+
+    pub fn store_limits(&self) -> StoreLimits {
+        let mut builder = StoreLimitsBuilder::new();
+        if self.max_memory > 0 {
+            builder = builder.memory_size(self.max_memory as usize);
+        }
+        if self.max_instances > 0 {
+            builder = builder.instances(self.max_instances as usize);
+        }
+        builder.build()
+    }
+}
+
+impl From<ResourceLimits> for Limits {
+    fn from(x: ResourceLimits) -> Self {
+        Limits {
+            max_memory: x.max_memory,
+            max_instances: x.max_instances as u64,
+            max_data_frames: x.max_data_frames as u64,
+            max_rows: x.max_rows,
+            max_bytes: x.max_bytes,
+        }
+    }
+}
+
+/// What a run has returned so far, checked against its `Limits`.
+#[derive(Debug, Default, Clone)]
+pub struct Usage {
+    pub rows: u64,
+    pub bytes: u64,
+}
+
+impl Usage {
+    /// Accounts for `rows` and `bytes` more in `data_frames` tables, unless
+    /// that would exceed `limits`.
+    pub fn charge(
+        &mut self,
+        limits: &Limits,
+        data_frames: usize,
+        rows: u64,
+        bytes: u64,
+    ) -> Result<(), String> {
+        let rows = self.rows + rows;
+        let bytes = self.bytes + bytes;
+        if limits.max_data_frames > 0 && data_frames as u64 > limits.max_data_frames {
+            return Err(format!("too many data-frames: {}", data_frames));
+        }
+        if limits.max_rows > 0 && rows > limits.max_rows {
+            return Err(format!("too many rows in data-frames: {}", rows));
+        }
+        if limits.max_bytes > 0 && bytes > limits.max_bytes {
+            return Err(format!("too many bytes in data-frames: {}", bytes));
+        }
+        self.rows = rows;
+        self.bytes = bytes;
+        Ok(())
+    }
+}
+
+pub fn value_bytes(v: &imports::ValueParam<'_>) -> u64 {
+    match v {
+        imports::ValueParam::Nil => 0,
+        imports::ValueParam::Bol(_) => 1,
+        imports::ValueParam::I32(_) | imports::ValueParam::F32(_) => 4,
+        imports::ValueParam::I64(_) | imports::ValueParam::F64(_) => 8,
+        imports::ValueParam::Txt(x) => x.len() as u64,
+    }
+}
+
+pub fn default_bytes(v: &imports::ValueResult) -> u64 {
+    match v {
+        imports::ValueResult::Nil => 0,
+        imports::ValueResult::Bol(_) => 1,
+        imports::ValueResult::I32(_) | imports::ValueResult::F32(_) => 4,
+        imports::ValueResult::I64(_) | imports::ValueResult::F64(_) => 8,
+        imports::ValueResult::Txt(x) => x.len() as u64,
+    }
+}
+
+pub fn vector_bytes(v: &imports::VectorResult) -> u64 {
+    match v {
+        imports::VectorResult::Nil => 0,
+        imports::VectorResult::Bol(x) => x.len() as u64,
+        imports::VectorResult::I32(x) => 4 * x.len() as u64,
+        imports::VectorResult::I64(x) => 8 * x.len() as u64,
+        imports::VectorResult::F32(x) => 4 * x.len() as u64,
+        imports::VectorResult::F64(x) => 8 * x.len() as u64,
+        imports::VectorResult::Txt(x) => x.iter().map(|s| s.len() as u64).sum(),
+    }
+}
@@ -1,22 +1,40 @@
 use crate::bindgen::*;
 
+mod cache;
+mod clock;
 mod sandbox;
-use futures::Future;
+pub use cache::SandboxCache;
+pub use clock::EpochClock;
 pub use sandbox::{Sandbox, SandboxManager};
 
-use wasmtime::Engine;
 use wasmtime_wasi::WasiCtxBuilder;
 
-// pub mod atomic_kv;
+pub mod atomic_kv;
+pub mod error;
+pub mod limits;
+pub mod runs;
+pub mod sampler;
+pub mod subgraph;
+pub mod trace;
 pub mod utils;
+pub mod validate;
 
+use crate::backend::{unix_now, Session};
+use crate::metrics;
 use crate::GLOBALS;
 
+use error::ImportError;
+use imports::StorageErrorKind;
+use limits::{Limits, Usage};
+use trace::{ImportCall, Trace, TRACE_TABLE};
+
 use anyhow::Result;
 use log;
+use tracing::Instrument;
 
 use std::collections::{BTreeMap, HashMap};
-use tokio::time;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct Storage {
@@ -25,8 +43,16 @@ pub struct Storage {
     pub token: String,
     pub return_tables: Vec<(String, BTreeMap<String, imports::VectorResult>)>,
     pub start_time: chrono::DateTime<chrono::Local>,
-    // pub statstic: BTreeMap<i64, u64>,
     pub counter: u64,
+    pub limits: Limits,
+    pub usage: Usage,
+
+    /// Why the last call of an import failed, unless a try-* import told the
+    /// guest already. Cleared by the next call.
+    pub failure: Option<ImportError>,
+
+    /// The calls of the imports, recorded if the run asked for a trace.
+    pub trace: Option<Trace>,
 }
 
 #[derive(Clone)]
@@ -36,28 +62,44 @@ pub struct StorageManager {
     pub token: String,
     pub ttl: u64,
     pub par: usize,
+    pub fuel: u64,
+    pub limits: Limits,
     vmm: SandboxManager<Storage>,
+
+    /// When the session was last kept alive, shared by the clones.
+    touched: Arc<AtomicU64>,
 }
 
 impl StorageManager {
     pub async fn new(token: &str) -> Result<Self> {
-        let mut con = GLOBALS.redis.get().await?;
-
-        let key = format!("wart:session:{}", token);
+        let Session {
+            space_name,
+            epoch,
+            module_hash,
+            ex_timeout: ttl,
+            parallel: par,
+            max_fuel: fuel,
+            limits,
+            ..
+        } = GLOBALS.kv.get_session(token).await?;
+        GLOBALS.kv.keep_alive(token).await?;
 
-        let (space_name, epoch, ttl, module, par): (String, u64, u64, Vec<u8>, usize) =
-            redis::pipe()
-                .atomic()
-                .hget(&key, "space_name")
-                .hget(&key, "epoch")
-                .hget(&key, "ex_timeout")
-                .hget(&key, "module")
-                .hget(&key, "parallel")
-                .query_async(&mut *con)
-                .await?;
+        // the session may have been opened by a worker with looser caps
+        let limits = limits.capped_by(&GLOBALS.config.limits);
 
-        let config = SandboxManager::<Storage>::default_config();
-        let vmm = SandboxManager::<Storage>::from_module(&module, &config)?;
+        let vmm = match GLOBALS.sandboxes.get(token, module_hash) {
+            Some(vmm) => vmm,
+            None => {
+                let module = GLOBALS.kv.get_module(token).await?;
+                let timer = metrics::compile_timer("deserialize");
+                let version = GLOBALS.config.engine.version();
+                let vmm =
+                    SandboxManager::<Storage>::from_module(&GLOBALS.engine, version, &module)?;
+                timer.observe_duration();
+                GLOBALS.sandboxes.insert(token, module_hash, vmm.clone());
+                vmm
+            }
+        };
 
         Ok(Self {
             space_name,
@@ -65,11 +107,26 @@ impl StorageManager {
             token: token.into(),
             ttl,
             par,
+            fuel,
+            limits,
             vmm,
+            touched: Arc::new(AtomicU64::new(unix_now())),
         })
     }
 
-    pub async fn get_sandbox(&self, args: &[String]) -> Result<Sandbox<Storage>> {
+    /// Postpones the expiry of the session, at most once a second since the
+    /// runs of a stream come much faster.
+    pub async fn keep_alive(&self) {
+        let now = unix_now();
+        if self.touched.swap(now, Ordering::Relaxed) == now {
+            return;
+        }
+        if let Err(err) = GLOBALS.kv.keep_alive(&self.token).await {
+            log::error!("can't keep session {} alive: {}", self.token, err);
+        }
+    }
+
+    pub async fn get_sandbox(&self, args: &[String], trace: bool) -> Result<Sandbox<Storage>> {
         let wasi_ctx = WasiCtxBuilder::new().args(&args)?.build();
 
         let imports = Storage {
@@ -78,39 +135,19 @@ impl StorageManager {
             token: self.token.clone(),
             return_tables: vec![],
             start_time: chrono::Local::now(),
-            // statstic: Default::default(),
             counter: 0,
+            limits: self.limits,
+            usage: Usage::default(),
+            failure: None,
+            trace: if trace { Some(Trace::default()) } else { None },
         };
 
-        self.vmm.instantiate(wasi_ctx, imports).await
-    }
-
-    pub fn get_engine(&self) -> Engine {
-        self.vmm.engine.clone()
-    }
-
-    pub fn spawn_clk<F>(&self, signal: F)
-    where
-        F: Future + Send + 'static,
-        F::Output: Send + 'static,
-    {
-        let engine = self.get_engine();
-        let mut signal = Box::pin(signal);
-        tokio::spawn(async move {
-            let duration = time::Duration::from_millis(100);
-            let mut interval = time::interval(duration);
-            loop {
-                tokio::select! {
-                    _ = interval.tick() => {
-                        engine.increment_epoch();
-                    },
-                    _ = &mut signal => {
-                        break
-                    },
-                }
-            }
-            log::info!("clk aborted");
-        });
+        let limits = self.limits.store_limits();
+        let _timer = metrics::instantiate_timer(&self.space_name);
+        self.vmm
+            .instantiate(wasi_ctx, imports, self.fuel, limits)
+            .instrument(tracing::info_span!("instantiate"))
+            .await
     }
 }
 
@@ -158,33 +195,566 @@ impl Storage {
             tokio::task::yield_now().await;
         }
 
-        // {
-        //     let ts = self.statstic.keys().map(|v| *v).collect();
-        //     let ct = self.statstic.values().map(|v| *v as i64).collect();
-        //     let headers = vec!["timestamp".into(), "count".into()];
-        //     let columns = vec![
-        //         Series {
-        //             values: Some(series::Values::Int64Values(series::Int64Series {
-        //                 data: ts,
-        //             })),
-        //         },
-        //         Series {
-        //             values: Some(series::Values::Int64Values(series::Int64Series {
-        //                 data: ct,
-        //             })),
-        //         },
-        //     ];
-
-        //     dfs.push(DataFrame {
-        //         headers,
-        //         columns,
-        //         comment: "__statstic__".into(),
-        //     });
-        // }
+        if let Some(trace) = self.trace {
+            dfs.push(trace::into_table(trace));
+        }
         dfs
     }
 }
 
+impl Storage {
+    /// Starts a call of `import` passing `arg_size` ids, keys or values. The
+    /// failure of an earlier call is no longer the one to blame for a trap.
+    fn call(&mut self, import: &'static str, arg_size: usize) -> ImportCall {
+        self.failure = None;
+        ImportCall::new(import, &self.space_name, arg_size, self.trace.clone())
+    }
+
+    /// Starts a call of an import outside of `storage` and `edge-scanner`,
+    /// which is only recorded in the trace and isn't blamed for traps.
+    fn traced(&self, import: &'static str, arg_size: usize) -> ImportCall {
+        ImportCall::traced(import, arg_size, self.trace.clone())
+    }
+
+    /// Records why `import` failed, see `ImportError`.
+    fn fail<T>(
+        &mut self,
+        import: &'static str,
+        kind: imports::StorageErrorKind,
+        message: String,
+    ) -> Result<T, imports::StorageError> {
+        self.fail_with(ImportError::new(import, kind, message))
+    }
+
+    fn fail_with<T>(&mut self, err: ImportError) -> Result<T, imports::StorageError> {
+        log::error!("{}: {}", err.import, err.message);
+        metrics::import_failed(err.import, &self.space_name);
+        self.failure = Some(err.clone());
+        Err(err.into())
+    }
+
+    /// What a try-* import returns. The guest is told why it failed, so a trap
+    /// following it isn't blamed on the call.
+    fn returned<T>(
+        &mut self,
+        result: Result<T, imports::StorageError>,
+    ) -> Result<T, imports::StorageError> {
+        self.failure = None;
+        result
+    }
+
+    fn node_ids(
+        &mut self,
+        import: &'static str,
+        ids: imports::VectorParam<'_>,
+    ) -> Result<series::Values, imports::StorageError> {
+        match ids {
+            imports::VectorParam::I64(x) => Ok(series::Values::Int64Values(series::Int64Series {
+                data: x.iter().map(|v| v.get()).collect(),
+            })),
+            imports::VectorParam::Txt(x) => {
+                Ok(series::Values::StringValues(series::StringSeries {
+                    data: x.into_iter().map(|v| v.into()).collect(),
+                }))
+            }
+            _ => {
+                let message = format!("unrecognized type of node_ids: {:?}", ids);
+                self.fail(import, StorageErrorKind::InvalidArgument, message)
+            }
+        }
+    }
+
+    /// Fetches the neighbors of `id`, failures are reported as ones of `import`.
+    async fn fetch_neighbors(
+        &mut self,
+        import: &'static str,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        reversely: bool,
+    ) -> Result<(imports::VectorResult, imports::Table), imports::StorageError> {
+        let request = FetchNeighborsRequest {
+            space_name: self.space_name.clone(),
+            node_id: Some(match id {
+                imports::ValueParam::I64(x) => fetch_neighbors_request::NodeId::AsInt(x),
+                imports::ValueParam::Txt(x) => fetch_neighbors_request::NodeId::AsStr(x.into()),
+                _ => {
+                    let message = format!("unrecognized type of node_id: {:?}", id);
+                    return self.fail(import, StorageErrorKind::InvalidArgument, message);
+                }
+            }),
+            tag: tag.into(),
+            keys: keys.into_iter().map(|x| x.into()).collect(),
+            reversely,
+        };
+
+        let data = match GLOBALS.graph.fetch_neighbors(request).await {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(ImportError::backend(import, &err)),
+        };
+
+        let data = utils::dump_to_imports_table(data);
+
+        let mut it = data.into_iter();
+        let dst = match it.next() {
+            Some(s) => s.val,
+            None => {
+                let message = "empty table in response".into();
+                return self.fail(import, StorageErrorKind::Internal, message);
+            }
+        };
+
+        Ok((dst, it.collect()))
+    }
+
+    async fn choice_nodes(
+        &mut self,
+        tag: &str,
+        number: i32,
+    ) -> Result<imports::VectorResult, imports::StorageError> {
+        const IMPORT: &str = "storage::choice-nodes";
+        let call = self.call(IMPORT, number.max(0) as usize);
+
+        let request = ChoiceNodesRequest {
+            space_name: self.space_name.clone(),
+            tag: tag.into(),
+            number,
+        };
+
+        let data = match GLOBALS.graph.choice_nodes(request).await {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+
+        self.counter += 1;
+
+        match utils::dump_to_imports_table(data).into_iter().nth(0) {
+            Some(s) => {
+                call.finish(sampler::len(&s.val));
+                Ok(s.val)
+            }
+            None => {
+                let message = "empty table in response".into();
+                self.fail(IMPORT, StorageErrorKind::Internal, message)
+            }
+        }
+    }
+
+    async fn query_nodes(
+        &mut self,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+    ) -> Result<imports::RowResult, imports::StorageError> {
+        const IMPORT: &str = "storage::query-nodes";
+        let call = self.call(IMPORT, keys.len());
+
+        let request = FetchNodeRequest {
+            space_name: self.space_name.clone(),
+            node_id: Some(match id {
+                imports::ValueParam::I64(x) => fetch_node_request::NodeId::AsInt(x),
+                imports::ValueParam::Txt(x) => fetch_node_request::NodeId::AsStr(x.into()),
+                _ => {
+                    let message = format!("unrecognized type of node_id: {:?}", id);
+                    return self.fail(IMPORT, StorageErrorKind::InvalidArgument, message);
+                }
+            }),
+            tag: tag.into(),
+            keys: keys.into_iter().map(|x| x.into()).collect(),
+        };
+
+        let data = match GLOBALS.graph.fetch_node(request).await {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+
+        self.counter += 1;
+
+        call.finish(1);
+        Ok(utils::dump_to_imports_row(data))
+    }
+
+    async fn query_neighbors(
+        &mut self,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        reversely: bool,
+    ) -> Result<(imports::VectorResult, imports::Table), imports::StorageError> {
+        const IMPORT: &str = "storage::query-neighbors";
+        let call = self.call(IMPORT, keys.len());
+
+        let (dst, attr) = self
+            .fetch_neighbors(IMPORT, id, tag, keys, reversely)
+            .await?;
+
+        self.counter += 1;
+
+        call.finish(sampler::len(&dst));
+        Ok((dst, attr))
+    }
+
+    async fn sample_neighbors(
+        &mut self,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        k: i32,
+        strategy: imports::SampleStrategy,
+        weight_key: &str,
+        reversely: bool,
+    ) -> Result<(imports::VectorResult, imports::Table), imports::StorageError> {
+        const IMPORT: &str = "storage::sample-neighbors";
+        let call = self.call(IMPORT, keys.len());
+        let k = k.max(0) as usize;
+
+        // the weight key is fetched along with the others and dropped afterwards
+        let weight_only = !matches!(strategy, imports::SampleStrategy::Uniform)
+            && !keys.iter().any(|key| *key == weight_key);
+        let qualified = format!("{}.{}", tag, weight_key);
+
+        let mut keys = keys.into_iter().collect::<Vec<&str>>();
+        if weight_only {
+            keys.push(weight_key);
+        }
+
+        let (dst, attr) = self
+            .fetch_neighbors(IMPORT, id, tag, keys, reversely)
+            .await?;
+
+        self.counter += 1;
+
+        let n = sampler::len(&dst);
+        if attr.iter().any(|s| sampler::len(&s.val) != n) {
+            let message = "mismatched length of neighbors and attributes".into();
+            return self.fail(IMPORT, StorageErrorKind::Internal, message);
+        }
+
+        let index = match strategy {
+            imports::SampleStrategy::Uniform => sampler::uniform(n, k),
+            imports::SampleStrategy::Weighted | imports::SampleStrategy::Recent => {
+                let weights = match attr
+                    .iter()
+                    .find(|s| s.key == weight_key || s.key == qualified)
+                {
+                    Some(weights) => weights,
+                    None => {
+                        let message = format!("unknown weight key: {}", weight_key);
+                        return self.fail(IMPORT, StorageErrorKind::NotFound, message);
+                    }
+                };
+                let weights = match sampler::to_f64(&weights.val) {
+                    Some(weights) => weights,
+                    None => {
+                        let message = format!("non-numeric weight key: {}", weight_key);
+                        return self.fail(IMPORT, StorageErrorKind::TypeMismatch, message);
+                    }
+                };
+
+                match strategy {
+                    imports::SampleStrategy::Weighted => sampler::weighted(&weights, k),
+                    _ => sampler::most_recent(&weights, k),
+                }
+            }
+        };
+
+        let attr = attr
+            .into_iter()
+            .filter(|s| !(weight_only && (s.key == weight_key || s.key == qualified)))
+            .map(|s| imports::Series {
+                val: sampler::take(&s.val, &index),
+                key: s.key,
+            })
+            .collect::<Vec<_>>();
+
+        let dst = sampler::take(&dst, &index);
+        call.finish(sampler::len(&dst));
+        Ok((dst, attr))
+    }
+
+    async fn sample_subgraph(
+        &mut self,
+        seeds: imports::VectorParam<'_>,
+        tags: Vec<&str>,
+        fanouts: &[wit_bindgen_wasmtime::Le<i32>],
+        keys: Vec<&str>,
+    ) -> Result<Vec<ReturnTable>, imports::StorageError> {
+        const IMPORT: &str = "storage::sample-subgraph";
+        let call = self.call(IMPORT, trace::param_len(&seeds));
+        let seeds = match self.node_ids(IMPORT, seeds)? {
+            series::Values::Int64Values(x) => {
+                x.data.into_iter().map(subgraph::NodeKey::Int).collect()
+            }
+            series::Values::StringValues(x) => {
+                x.data.into_iter().map(subgraph::NodeKey::Str).collect()
+            }
+            _ => vec![],
+        };
+        let tags = tags.into_iter().map(|x| x.into()).collect::<Vec<String>>();
+        let fanouts = fanouts.iter().map(|x| x.get()).collect::<Vec<_>>();
+        let keys = keys.into_iter().map(|x| x.into()).collect::<Vec<String>>();
+
+        // charged to a copy, so that a failed subgraph costs nothing
+        let mut usage = self.usage.clone();
+        let limits = self.limits;
+        let data_frames = self.return_tables.len();
+        let tables = subgraph::sample_subgraph(
+            GLOBALS.graph.as_ref(),
+            &self.space_name,
+            seeds,
+            &tags,
+            &fanouts,
+            &keys,
+            |tables, rows, bytes| usage.charge(&limits, data_frames + tables, rows, bytes),
+        )
+        .await;
+        let tables = match tables {
+            Ok(tables) => tables,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+        let rows = usage.rows - self.usage.rows;
+        self.usage = usage;
+
+        self.counter += 1;
+
+        let mut handles = vec![];
+        for (name, table) in tables.into_iter() {
+            let defa = table
+                .iter()
+                .map(|(k, v)| (k.clone(), utils::default_value(v)))
+                .collect::<HashMap<_, _>>();
+
+            let index = self.return_tables.len();
+            self.return_tables.push((name, table));
+            handles.push(ReturnTable { index, defa });
+        }
+
+        call.finish(rows as usize);
+        Ok(handles)
+    }
+
+    async fn query_nodes_batch(
+        &mut self,
+        ids: imports::VectorParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+    ) -> Result<imports::Table, imports::StorageError> {
+        const IMPORT: &str = "storage::query-nodes-batch";
+        let call = self.call(IMPORT, trace::param_len(&ids));
+
+        let request = FetchNodeBatchRequest {
+            space_name: self.space_name.clone(),
+            node_ids: Some(Series {
+                values: Some(self.node_ids(IMPORT, ids)?),
+            }),
+            tag: tag.into(),
+            keys: keys.into_iter().map(|x| x.into()).collect(),
+        };
+
+        let data = match GLOBALS.graph.fetch_node_batch(request).await {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+
+        self.counter += 1;
+
+        let data = utils::dump_to_imports_table(data);
+        call.finish(trace::table_len(&data));
+        Ok(data)
+    }
+
+    async fn query_neighbors_batch(
+        &mut self,
+        ids: imports::VectorParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        reversely: bool,
+    ) -> Result<imports::Table, imports::StorageError> {
+        const IMPORT: &str = "storage::query-neighbors-batch";
+        let call = self.call(IMPORT, trace::param_len(&ids));
+
+        let request = FetchNeighborsBatchRequest {
+            space_name: self.space_name.clone(),
+            node_ids: Some(Series {
+                values: Some(self.node_ids(IMPORT, ids)?),
+            }),
+            tag: tag.into(),
+            keys: keys.into_iter().map(|x| x.into()).collect(),
+            reversely,
+        };
+
+        let data = match GLOBALS.graph.fetch_neighbors_batch(request).await {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+
+        let mut data = utils::dump_to_imports_table(data);
+        if data.len() < 2 {
+            let message = "missing offsets or dst in response".into();
+            return self.fail(IMPORT, StorageErrorKind::Internal, message);
+        }
+        data[0].key = "offsets".into();
+        data[1].key = "dst".into();
+
+        self.counter += 1;
+
+        call.finish(sampler::len(&data[1].val));
+        Ok(data)
+    }
+
+    async fn query_kv(
+        &mut self,
+        keys: Vec<&str>,
+        defva: imports::ValueParam<'_>,
+    ) -> Result<imports::VectorResult, imports::StorageError> {
+        const IMPORT: &str = "storage::query-kv";
+        let call = self.call(IMPORT, keys.len());
+
+        let token = &self.token;
+        let epoch = self.epoch;
+        let data = match defva {
+            imports::ValueParam::Bol(x) => atomic_kv::atomic_query_kv(token, epoch, &keys, &x)
+                .await
+                .map(imports::VectorResult::Bol),
+            imports::ValueParam::I32(x) => atomic_kv::atomic_query_kv(token, epoch, &keys, &x)
+                .await
+                .map(imports::VectorResult::I32),
+            imports::ValueParam::I64(x) => atomic_kv::atomic_query_kv(token, epoch, &keys, &x)
+                .await
+                .map(imports::VectorResult::I64),
+            imports::ValueParam::F32(x) => atomic_kv::atomic_query_kv(token, epoch, &keys, &x)
+                .await
+                .map(imports::VectorResult::F32),
+            imports::ValueParam::F64(x) => atomic_kv::atomic_query_kv(token, epoch, &keys, &x)
+                .await
+                .map(imports::VectorResult::F64),
+            imports::ValueParam::Txt(x) => {
+                atomic_kv::atomic_query_kv(token, epoch, &keys, &x.to_string())
+                    .await
+                    .map(imports::VectorResult::Txt)
+            }
+            _ => {
+                let message = format!("unrecognized type of default value: {:?}", defva);
+                return self.fail(IMPORT, StorageErrorKind::InvalidArgument, message);
+            }
+        };
+        let data = match data {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(err),
+        };
+
+        self.counter += 1;
+
+        call.finish(sampler::len(&data));
+        Ok(data)
+    }
+
+    async fn update_kv(
+        &mut self,
+        keys: Vec<&str>,
+        vals: imports::VectorParam<'_>,
+        ops: imports::MergeType,
+    ) -> Result<u64, imports::StorageError> {
+        const IMPORT: &str = "storage::update-kv";
+        let call = self.call(IMPORT, keys.len());
+
+        let token = &self.token;
+        let epoch = self.epoch;
+        let n = match vals {
+            imports::VectorParam::Bol(x) => {
+                atomic_kv::atomic_update_kv(token, epoch, &keys, &x, ops).await
+            }
+            imports::VectorParam::I32(x) => {
+                let x = x.iter().map(|v| v.get()).collect::<Vec<_>>();
+                atomic_kv::atomic_update_kv(token, epoch, &keys, &x, ops).await
+            }
+            imports::VectorParam::I64(x) => {
+                let x = x.iter().map(|v| v.get()).collect::<Vec<_>>();
+                atomic_kv::atomic_update_kv(token, epoch, &keys, &x, ops).await
+            }
+            imports::VectorParam::F32(x) => {
+                let x = x.iter().map(|v| v.get()).collect::<Vec<_>>();
+                atomic_kv::atomic_update_kv(token, epoch, &keys, &x, ops).await
+            }
+            imports::VectorParam::F64(x) => {
+                let x = x.iter().map(|v| v.get()).collect::<Vec<_>>();
+                atomic_kv::atomic_update_kv(token, epoch, &keys, &x, ops).await
+            }
+            imports::VectorParam::Txt(x) => {
+                let x = x.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+                atomic_kv::atomic_update_kv(token, epoch, &keys, &x, ops).await
+            }
+            imports::VectorParam::Nil => {
+                let message = "unrecognized type of vals: nil".into();
+                return self.fail(IMPORT, StorageErrorKind::InvalidArgument, message);
+            }
+        };
+        let n = match n {
+            Ok(n) => n,
+            Err(err) => return self.fail_with(err),
+        };
+
+        self.counter += 1;
+
+        call.finish(n as usize);
+        Ok(n)
+    }
+
+    async fn scan_edges(
+        &mut self,
+        tag: &str,
+        time_field: &str,
+        start_time: i64,
+        duration: i64,
+    ) -> Result<EdgeScanner, imports::StorageError> {
+        const IMPORT: &str = "edge-scanner::new";
+        let call = self.call(IMPORT, 0);
+
+        let request = StartScanEdgesRequest {
+            space_name: self.space_name.clone(),
+            tag: tag.into(),
+            time_field: time_field.into(),
+            start_time,
+            duration,
+        };
+
+        let iterator_token = match GLOBALS.graph.start_scan_edges(request).await {
+            Ok(iterator_token) => iterator_token,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+
+        self.counter += 1;
+
+        call.finish(0);
+        Ok(EdgeScanner { iterator_token })
+    }
+
+    async fn next_edges(
+        &mut self,
+        this: &EdgeScanner,
+        number: i64,
+    ) -> Result<imports::Table, imports::StorageError> {
+        const IMPORT: &str = "edge-scanner::next";
+        let call = self.call(IMPORT, number.max(0) as usize);
+
+        let request = GetNextEdgesRequest {
+            iterator_token: this.iterator_token.clone(),
+            number,
+        };
+
+        let data = match GLOBALS.graph.get_next_edges(request).await {
+            Ok(data) => data,
+            Err(err) => return self.fail_with(ImportError::backend(IMPORT, &err)),
+        };
+
+        self.counter += 1;
+
+        let data = utils::dump_to_imports_table(data);
+        call.finish(trace::table_len(&data));
+        Ok(data)
+    }
+}
+
 #[derive(Debug)]
 pub struct ReturnTable {
     index: usize,
@@ -194,16 +764,53 @@ pub struct ReturnTable {
 #[derive(Debug)]
 pub struct StorageCache {}
 
+#[derive(Debug)]
+pub struct EdgeScanner {
+    iterator_token: String,
+}
+
+impl Drop for EdgeScanner {
+    fn drop(&mut self) {
+        if self.iterator_token.is_empty() {
+            return;
+        }
+
+        let request = FinishScanEdgesRequest {
+            iterator_token: std::mem::take(&mut self.iterator_token),
+        };
+
+        GLOBALS.runtime.spawn(async move {
+            let iterator_token = request.iterator_token.clone();
+            if let Err(err) = GLOBALS.graph.finish_scan_edges(request).await {
+                log::error!("can't finish scanning edges {}: {}", iterator_token, err);
+            }
+        });
+    }
+}
+
 #[wit_bindgen_wasmtime::async_trait]
 impl imports::Imports for Storage {
     type DataFrame = ReturnTable;
     type Storage = StorageCache;
+    type EdgeScanner = EdgeScanner;
 
     fn data_frame_new(
         &mut self,
         name: &str,
         default: imports::RowParam<'_>,
     ) -> Option<Self::DataFrame> {
+        let call = self.traced("data-frame::new", default.len());
+        if name == TRACE_TABLE {
+            let message = format!("{} is reserved for the trace of the run", name);
+            return self
+                .fail(
+                    "data-frame::new",
+                    StorageErrorKind::InvalidArgument,
+                    message,
+                )
+                .ok();
+        }
+
         let defa = default
             .into_iter()
             .map(|r| {
@@ -239,8 +846,14 @@ impl imports::Imports for Storage {
             .collect::<BTreeMap<_, _>>();
 
         let index = self.return_tables.len();
+        if let Err(message) = self.usage.charge(&self.limits, index + 1, 0, 0) {
+            return self
+                .fail("data-frame::new", StorageErrorKind::LimitExceeded, message)
+                .ok();
+        }
         self.return_tables.push((name.into(), data));
 
+        call.finish(1);
         Some(Self::DataFrame { index, defa })
     }
 
@@ -249,14 +862,7 @@ impl imports::Imports for Storage {
         this: &Self::DataFrame,
         data: imports::RowParam<'_>,
     ) -> Option<u64> {
-        let table = match self.return_tables.get_mut(this.index) {
-            Some(t) => &mut t.1,
-            None => {
-                log::error!("unknown return table: {}", this.index);
-                return None;
-            }
-        };
-
+        let call = self.traced("data-frame::push", data.len());
         let data = data
             .iter()
             .map(|r| {
@@ -265,6 +871,42 @@ impl imports::Imports for Storage {
             })
             .collect::<HashMap<_, _>>();
 
+        // Checks the row against the columns before anything is pushed, so a
+        // bad row leaves the frame as it was.
+        let bytes = match self.return_tables.get(this.index) {
+            Some(t) => match data.keys().find(|k| !t.1.contains_key(*k)) {
+                Some(k) => Err(format!("unknown column: {}", k)),
+                None => t.1.keys().try_fold(0, |bytes, k| match data.get(&k[..]) {
+                    Some(vp) => Ok(bytes + limits::value_bytes(vp)),
+                    None => match this.defa.get(&k[..]) {
+                        Some(v) => Ok(bytes + limits::default_bytes(v)),
+                        None => Err(format!("missing column: {}", k)),
+                    },
+                }),
+            },
+            None => Err(format!("unknown return table: {}", this.index)),
+        };
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(message) => {
+                return self
+                    .fail(
+                        "data-frame::push",
+                        StorageErrorKind::InvalidArgument,
+                        message,
+                    )
+                    .ok();
+            }
+        };
+        let data_frames = self.return_tables.len();
+        if let Err(message) = self.usage.charge(&self.limits, data_frames, 1, bytes) {
+            return self
+                .fail("data-frame::push", StorageErrorKind::LimitExceeded, message)
+                .ok();
+        }
+
+        let table = &mut self.return_tables[this.index].1;
+
         for (k, v) in table.iter_mut() {
             if let Some(vp) = data.get(&k[..]) {
                 match (v, vp) {
@@ -278,8 +920,7 @@ impl imports::Imports for Storage {
                     }
                     _ => (),
                 };
-            } else {
-                let vp = this.defa.get(&k[..]).unwrap();
+            } else if let Some(vp) = this.defa.get(&k[..]) {
                 match (v, vp) {
                     (imports::VectorResult::Bol(v), imports::ValueResult::Bol(vp)) => v.push(*vp),
                     (imports::VectorResult::I32(v), imports::ValueResult::I32(vp)) => v.push(*vp),
@@ -294,21 +935,32 @@ impl imports::Imports for Storage {
             }
         }
 
+        call.finish(1);
         Some(0)
     }
 
     fn data_frame_size(&mut self, this: &Self::DataFrame) -> Option<u64> {
+        let call = self.traced("data-frame::size", 0);
         let table = match self.return_tables.get(this.index) {
             Some(t) => &t.1,
             None => {
-                log::error!("unknown return table: {}", this.index);
-                return None;
+                let message = format!("unknown return table: {}", this.index);
+                return self
+                    .fail(
+                        "data-frame::size",
+                        StorageErrorKind::InvalidArgument,
+                        message,
+                    )
+                    .ok();
             }
         };
-        Some(table.len() as u64)
+        let size = table.len() as u64;
+        call.finish(1);
+        Some(size)
     }
 
     async fn storage_new(&mut self) -> Option<Self::Storage> {
+        self.traced("storage::new", 0).finish(1);
         Some(Self::Storage {})
     }
 
@@ -318,49 +970,7 @@ impl imports::Imports for Storage {
         tag: &str,
         number: i32,
     ) -> Option<imports::VectorResult> {
-        let request = ChoiceNodesRequest {
-            space_name: self.space_name.clone(),
-            tag: tag.into(),
-            number,
-        };
-
-        // let now = chrono::Local::now();
-        // let v = self.statstic.entry(now.timestamp()).or_insert(0);
-        // *v += 1;
-
-        let data = GLOBALS
-            .storage
-            .get()
-            .await
-            .map_err(|err| {
-                log::error!("connection pool: {}", err);
-                err
-            })
-            .ok()?
-            .choice_nodes(request)
-            .await
-            .map_err(|err| {
-                log::error!("can't connect to storage nodes: {}", err);
-                err
-            })
-            .ok()?
-            .into_inner()
-            .data
-            .or_else(|| {
-                log::error!("empty data in response");
-                None
-            })?;
-        
-        self.counter += 1;
-
-        utils::dump_to_imports_table(data)
-            .into_iter()
-            .nth(0)
-            .map(|s| s.val)
-            .or_else(|| {
-                log::error!("empty table in response");
-                None
-            })
+        self.choice_nodes(tag, number).await.ok()
     }
 
     async fn storage_query_nodes(
@@ -370,50 +980,7 @@ impl imports::Imports for Storage {
         tag: &str,
         keys: Vec<&str>,
     ) -> Option<imports::RowResult> {
-        let request = FetchNodeRequest {
-            space_name: self.space_name.clone(),
-            node_id: Some(match id {
-                imports::ValueParam::I64(x) => fetch_node_request::NodeId::AsInt(x),
-                imports::ValueParam::Txt(x) => fetch_node_request::NodeId::AsStr(x.into()),
-                _ => {
-                    log::error!("unrecognized type of node_id: {:?}", id);
-                    return None;
-                }
-            }),
-            tag: tag.into(),
-            keys: keys.into_iter().map(|x| x.into()).collect(),
-        };
-
-        // let now = chrono::Local::now();
-        // let v = self.statstic.entry(now.timestamp()).or_insert(0);
-        // *v += 1;
-
-        let data = GLOBALS
-            .storage
-            .get()
-            .await
-            .map_err(|err| {
-                log::error!("connection pool error: {}", err);
-                err
-            })
-            .ok()?
-            .fetch_node(request)
-            .await
-            .map_err(|err| {
-                log::error!("can't connect to storage nodes: {}", err);
-                err
-            })
-            .ok()?
-            .into_inner()
-            .data
-            .or_else(|| {
-                log::error!("empty data in response");
-                None
-            })?;
-        
-        self.counter += 1;
-
-        Some(utils::dump_to_imports_row(data))
+        self.query_nodes(id, tag, keys).await.ok()
     }
 
     async fn storage_query_neighbors(
@@ -424,258 +991,58 @@ impl imports::Imports for Storage {
         keys: Vec<&str>,
         reversely: bool,
     ) -> Option<(imports::VectorResult, imports::Table)> {
-        let request = FetchNeighborsRequest {
-            space_name: self.space_name.clone(),
-            node_id: Some(match id {
-                imports::ValueParam::I64(x) => fetch_neighbors_request::NodeId::AsInt(x),
-                imports::ValueParam::Txt(x) => fetch_neighbors_request::NodeId::AsStr(x.into()),
-                _ => {
-                    log::error!("unrecognized type of node_id: {:?}", id);
-                    return None;
-                }
-            }),
-            tag: tag.into(),
-            keys: keys.into_iter().map(|x| x.into()).collect(),
-            reversely,
-        };
-
-        // let now = chrono::Local::now();
-        // let v = self.statstic.entry(now.timestamp()).or_insert(0);
-        // *v += 1;
+        self.query_neighbors(id, tag, keys, reversely).await.ok()
+    }
 
-        let data = GLOBALS
-            .storage
-            .get()
-            .await
-            .map_err(|err| {
-                log::error!("connection pool error: {}", err);
-                err
-            })
-            .ok()?
-            .fetch_neighbors(request)
+    async fn storage_sample_neighbors(
+        &mut self,
+        _this: &Self::Storage,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        k: i32,
+        strategy: imports::SampleStrategy,
+        weight_key: &str,
+        reversely: bool,
+    ) -> Option<(imports::VectorResult, imports::Table)> {
+        self.sample_neighbors(id, tag, keys, k, strategy, weight_key, reversely)
             .await
-            .map_err(|err| {
-                log::error!("can't connect to storage nodes: {}", err);
-                err
-            })
-            .ok()?
-            .into_inner()
-            .data
-            .or_else(|| {
-                log::error!("empty data in response");
-                None
-            })?;
-
-        let data = utils::dump_to_imports_table(data);
-
-        let mut it = data.into_iter();
-        let dst = it.next().map(|s| s.val).or_else(|| {
-            log::error!("empty table in response");
-            None
-        })?;
+            .ok()
+    }
 
-        let attr = it.collect::<Vec<_>>();
+    async fn storage_sample_subgraph(
+        &mut self,
+        _this: &Self::Storage,
+        seeds: imports::VectorParam<'_>,
+        tags: Vec<&str>,
+        fanouts: &[wit_bindgen_wasmtime::Le<i32>],
+        keys: Vec<&str>,
+    ) -> Option<Vec<Self::DataFrame>> {
+        self.sample_subgraph(seeds, tags, fanouts, keys).await.ok()
+    }
 
-        self.counter += 1;
+    async fn storage_query_nodes_batch(
+        &mut self,
+        _this: &Self::Storage,
+        ids: imports::VectorParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+    ) -> Option<imports::Table> {
+        self.query_nodes_batch(ids, tag, keys).await.ok()
+    }
 
-        Some((dst, attr))
-    }
-
-    // async fn storage_query_kv(
-    //     &mut self,
-    //     _this: &Self::Storage,
-    //     keys: Vec<&str>,
-    //     defva: imports::ValueParam<'_>,
-    // ) -> Option<imports::VectorResult> {
-    //     let mut con = GLOBALS
-    //         .redis
-    //         .get()
-    //         .await
-    //         .map_err(|err| {
-    //             log::error!("redis connection pool error: {}", err);
-    //         })
-    //         .ok()?;
-
-    //     let prefk = format!("wart:store:{}:", self.token);
-    //     match defva {
-    //         imports::ValueParam::Bol(x) => {
-    //             let mut data = vec![];
-    //             for field in keys.into_iter() {
-    //                 let val = atomic_kv::atomic_query_kv::<bool, _>(
-    //                     &prefk, self.epoch, &field, &x, &mut *con,
-    //                 )
-    //                 .await?;
-    //                 data.push(val);
-    //             }
-    //             Some(imports::VectorResult::Bol(data))
-    //         }
-    //         imports::ValueParam::I32(x) => {
-    //             let mut data = vec![];
-    //             for field in keys.into_iter() {
-    //                 let val = atomic_kv::atomic_query_kv::<i32, _>(
-    //                     &prefk, self.epoch, &field, &x, &mut *con,
-    //                 )
-    //                 .await?;
-    //                 data.push(val);
-    //             }
-    //             Some(imports::VectorResult::I32(data))
-    //         }
-    //         imports::ValueParam::I64(x) => {
-    //             let mut data = vec![];
-    //             for field in keys.into_iter() {
-    //                 let val = atomic_kv::atomic_query_kv::<i64, _>(
-    //                     &prefk, self.epoch, &field, &x, &mut *con,
-    //                 )
-    //                 .await?;
-    //                 data.push(val);
-    //             }
-    //             Some(imports::VectorResult::I64(data))
-    //         }
-    //         imports::ValueParam::F32(x) => {
-    //             let mut data = vec![];
-    //             for field in keys.into_iter() {
-    //                 let val = atomic_kv::atomic_query_kv::<f32, _>(
-    //                     &prefk, self.epoch, &field, &x, &mut *con,
-    //                 )
-    //                 .await?;
-    //                 data.push(val);
-    //             }
-    //             Some(imports::VectorResult::F32(data))
-    //         }
-    //         imports::ValueParam::F64(x) => {
-    //             let mut data = vec![];
-    //             for field in keys.into_iter() {
-    //                 let val = atomic_kv::atomic_query_kv::<f64, _>(
-    //                     &prefk, self.epoch, &field, &x, &mut *con,
-    //                 )
-    //                 .await?;
-    //                 data.push(val);
-    //             }
-    //             Some(imports::VectorResult::F64(data))
-    //         }
-    //         imports::ValueParam::Txt(x) => {
-    //             let mut data = vec![];
-    //             for field in keys.into_iter() {
-    //                 let val = atomic_kv::atomic_query_kv::<String, _>(
-    //                     &prefk,
-    //                     self.epoch,
-    //                     &field,
-    //                     &x.into(),
-    //                     &mut *con,
-    //                 )
-    //                 .await?;
-    //                 data.push(val);
-    //             }
-    //             Some(imports::VectorResult::Txt(data))
-    //         }
-    //         _ => None,
-    //     }
-    // }
-
-    // async fn storage_update_kv(
-    //     &mut self,
-    //     _this: &Self::Storage,
-    //     keys: Vec<&str>,
-    //     vals: imports::VectorParam<'_>,
-    //     ops: imports::MergeType,
-    // ) -> Option<u64> {
-    //     let mut con = GLOBALS
-    //         .redis
-    //         .get()
-    //         .await
-    //         .map_err(|err| {
-    //             log::error!("redis connection pool error: {}", err);
-    //         })
-    //         .ok()?;
-
-    //     let prefk = format!("wart:store:{}:", self.token);
-
-    //     match vals {
-    //         imports::VectorParam::Bol(x) => {
-    //             for (field, argum) in keys.into_iter().zip(x.into_iter()) {
-    //                 atomic_kv::atomic_update_kv::<bool, _>(
-    //                     &prefk, self.epoch, &field, &false, &argum, ops, &mut *con,
-    //                 )
-    //                 .await?;
-    //             }
-    //             Some(0)
-    //         }
-    //         imports::VectorParam::I32(x) => {
-    //             for (field, argum) in keys.into_iter().zip(x.into_iter()) {
-    //                 atomic_kv::atomic_update_kv::<i32, _>(
-    //                     &prefk,
-    //                     self.epoch,
-    //                     &field,
-    //                     &0,
-    //                     &argum.get(),
-    //                     ops,
-    //                     &mut *con,
-    //                 )
-    //                 .await?;
-    //             }
-    //             Some(0)
-    //         }
-    //         imports::VectorParam::I64(x) => {
-    //             for (field, argum) in keys.into_iter().zip(x.into_iter()) {
-    //                 atomic_kv::atomic_update_kv::<i64, _>(
-    //                     &prefk,
-    //                     self.epoch,
-    //                     &field,
-    //                     &0,
-    //                     &argum.get(),
-    //                     ops,
-    //                     &mut *con,
-    //                 )
-    //                 .await?;
-    //             }
-    //             Some(0)
-    //         }
-    //         imports::VectorParam::F32(x) => {
-    //             for (field, argum) in keys.into_iter().zip(x.into_iter()) {
-    //                 atomic_kv::atomic_update_kv::<f32, _>(
-    //                     &prefk,
-    //                     self.epoch,
-    //                     &field,
-    //                     &0.0,
-    //                     &argum.get(),
-    //                     ops,
-    //                     &mut *con,
-    //                 )
-    //                 .await?;
-    //             }
-    //             Some(0)
-    //         }
-    //         imports::VectorParam::F64(x) => {
-    //             for (field, argum) in keys.into_iter().zip(x.into_iter()) {
-    //                 atomic_kv::atomic_update_kv::<f64, _>(
-    //                     &prefk,
-    //                     self.epoch,
-    //                     &field,
-    //                     &0.0,
-    //                     &argum.get(),
-    //                     ops,
-    //                     &mut *con,
-    //                 )
-    //                 .await?;
-    //             }
-    //             Some(0)
-    //         }
-    //         imports::VectorParam::Txt(x) => {
-    //             for (field, argum) in keys.into_iter().zip(x.into_iter()) {
-    //                 let argum = argum.get(..).or_else(|| {
-    //                     log::error!("invalid string");
-    //                     None
-    //                 })?;
-    //                 atomic_kv::atomic_update_kv::<&str, _>(
-    //                     &prefk, self.epoch, &field, &"0", &argum, ops, &mut *con,
-    //                 )
-    //                 .await?;
-    //             }
-    //             Some(0)
-    //         }
-    //         _ => None,
-    //     }
-    // }
+    async fn storage_query_neighbors_batch(
+        &mut self,
+        _this: &Self::Storage,
+        ids: imports::VectorParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        reversely: bool,
+    ) -> Option<imports::Table> {
+        self.query_neighbors_batch(ids, tag, keys, reversely)
+            .await
+            .ok()
+    }
 
     async fn storage_query_kv(
         &mut self,
@@ -683,128 +1050,167 @@ impl imports::Imports for Storage {
         keys: Vec<&str>,
         defva: imports::ValueParam<'_>,
     ) -> Option<imports::VectorResult> {
-        let mut con = GLOBALS
-            .redis
-            .get()
-            .await
-            .map_err(|err| {
-                log::error!("redis connection pool: {}", err);
-            })
-            .ok()?;
-
-        let key = format!("wart:store:{}", self.token);
-        match defva {
-            imports::ValueParam::Bol(x) => {
-                let mut data = vec![];
-                for field in keys.into_iter() {
-                    let (val,) = redis::pipe()
-                        .hget(&key, &field)
-                        .query_async(&mut *con)
-                        .await
-                        .map_err(|err| {
-                            log::error!("{}", err);
-                            err
-                        })
-                        .unwrap_or((x.into(),));
-                    data.push(val);
-                }
-                Some(imports::VectorResult::Bol(data))
-            }
-            imports::ValueParam::I32(x) => {
-                let mut data = vec![];
-                for field in keys.into_iter() {
-                    let (val,) = redis::pipe()
-                        .hget(&key, &field)
-                        .query_async(&mut *con)
-                        .await
-                        .map_err(|err| {
-                            log::error!("{}", err);
-                            err
-                        })
-                        .unwrap_or((x.into(),));
-                    data.push(val);
-                }
-                Some(imports::VectorResult::I32(data))
-            }
-            imports::ValueParam::I64(x) => {
-                let mut data = vec![];
-                for field in keys.into_iter() {
-                    let (val,) = redis::pipe()
-                        .hget(&key, &field)
-                        .query_async(&mut *con)
-                        .await
-                        .map_err(|err| {
-                            log::error!("{}", err);
-                            err
-                        })
-                        .unwrap_or((x.into(),));
-                    data.push(val);
-                }
-                Some(imports::VectorResult::I64(data))
-            }
-            imports::ValueParam::F32(x) => {
-                let mut data = vec![];
-                for field in keys.into_iter() {
-                    let (val,) = redis::pipe()
-                        .hget(&key, &field)
-                        .query_async(&mut *con)
-                        .await
-                        .map_err(|err| {
-                            log::error!("{}", err);
-                            err
-                        })
-                        .unwrap_or((x.into(),));
-                    data.push(val);
-                }
-                Some(imports::VectorResult::F32(data))
-            }
-            imports::ValueParam::F64(x) => {
-                let mut data = vec![];
-                for field in keys.into_iter() {
-                    let (val,) = redis::pipe()
-                        .hget(&key, &field)
-                        .query_async(&mut *con)
-                        .await
-                        .map_err(|err| {
-                            log::error!("{}", err);
-                            err
-                        })
-                        .unwrap_or((x.into(),));
-                    data.push(val);
-                }
-                Some(imports::VectorResult::F64(data))
-            }
-            imports::ValueParam::Txt(x) => {
-                let mut data = vec![];
-                for field in keys.into_iter() {
-                    let (val,) = redis::pipe()
-                        .hget(&key, &field)
-                        .query_async(&mut *con)
-                        .await
-                        .map_err(|err| {
-                            log::error!("{}", err);
-                            err
-                        })
-                        .unwrap_or((x.into(),));
-                    data.push(val);
-                }
-                Some(imports::VectorResult::Txt(data))
-            }
-            _ => None,
-        }
+        self.query_kv(keys, defva).await.ok()
     }
 
     async fn storage_update_kv(
         &mut self,
         _this: &Self::Storage,
-        _keys: Vec<&str>,
-        _vals: imports::VectorParam<'_>,
-        _ops: imports::MergeType,
+        keys: Vec<&str>,
+        vals: imports::VectorParam<'_>,
+        ops: imports::MergeType,
     ) -> Option<u64> {
-        todo!()
+        self.update_kv(keys, vals, ops).await.ok()
+    }
+
+    async fn storage_try_choice_nodes(
+        &mut self,
+        _this: &Self::Storage,
+        tag: &str,
+        number: i32,
+    ) -> Result<imports::VectorResult, imports::StorageError> {
+        let result = self.choice_nodes(tag, number).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_query_nodes(
+        &mut self,
+        _this: &Self::Storage,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+    ) -> Result<imports::RowResult, imports::StorageError> {
+        let result = self.query_nodes(id, tag, keys).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_query_neighbors(
+        &mut self,
+        _this: &Self::Storage,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        reversely: bool,
+    ) -> Result<(imports::VectorResult, imports::Table), imports::StorageError> {
+        let result = self.query_neighbors(id, tag, keys, reversely).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_sample_neighbors(
+        &mut self,
+        _this: &Self::Storage,
+        id: imports::ValueParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        k: i32,
+        strategy: imports::SampleStrategy,
+        weight_key: &str,
+        reversely: bool,
+    ) -> Result<(imports::VectorResult, imports::Table), imports::StorageError> {
+        let result = self
+            .sample_neighbors(id, tag, keys, k, strategy, weight_key, reversely)
+            .await;
+        self.returned(result)
+    }
+
+    async fn storage_try_sample_subgraph(
+        &mut self,
+        _this: &Self::Storage,
+        seeds: imports::VectorParam<'_>,
+        tags: Vec<&str>,
+        fanouts: &[wit_bindgen_wasmtime::Le<i32>],
+        keys: Vec<&str>,
+    ) -> Result<Vec<Self::DataFrame>, imports::StorageError> {
+        let result = self.sample_subgraph(seeds, tags, fanouts, keys).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_query_nodes_batch(
+        &mut self,
+        _this: &Self::Storage,
+        ids: imports::VectorParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+    ) -> Result<imports::Table, imports::StorageError> {
+        let result = self.query_nodes_batch(ids, tag, keys).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_query_neighbors_batch(
+        &mut self,
+        _this: &Self::Storage,
+        ids: imports::VectorParam<'_>,
+        tag: &str,
+        keys: Vec<&str>,
+        reversely: bool,
+    ) -> Result<imports::Table, imports::StorageError> {
+        let result = self.query_neighbors_batch(ids, tag, keys, reversely).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_query_kv(
+        &mut self,
+        _this: &Self::Storage,
+        keys: Vec<&str>,
+        defva: imports::ValueParam<'_>,
+    ) -> Result<imports::VectorResult, imports::StorageError> {
+        let result = self.query_kv(keys, defva).await;
+        self.returned(result)
+    }
+
+    async fn storage_try_update_kv(
+        &mut self,
+        _this: &Self::Storage,
+        keys: Vec<&str>,
+        vals: imports::VectorParam<'_>,
+        ops: imports::MergeType,
+    ) -> Result<u64, imports::StorageError> {
+        let result = self.update_kv(keys, vals, ops).await;
+        self.returned(result)
+    }
+
+    async fn edge_scanner_new(
+        &mut self,
+        tag: &str,
+        time_field: &str,
+        start_time: i64,
+        duration: i64,
+    ) -> Option<Self::EdgeScanner> {
+        self.scan_edges(tag, time_field, start_time, duration)
+            .await
+            .ok()
+    }
+
+    async fn edge_scanner_next(
+        &mut self,
+        this: &Self::EdgeScanner,
+        number: i64,
+    ) -> Option<imports::Table> {
+        self.next_edges(this, number).await.ok()
+    }
+
+    async fn edge_scanner_try_new(
+        &mut self,
+        tag: &str,
+        time_field: &str,
+        start_time: i64,
+        duration: i64,
+    ) -> Result<Self::EdgeScanner, imports::StorageError> {
+        let result = self.scan_edges(tag, time_field, start_time, duration).await;
+        self.returned(result)
+    }
+
+    async fn edge_scanner_try_next(
+        &mut self,
+        this: &Self::EdgeScanner,
+        number: i64,
+    ) -> Result<imports::Table, imports::StorageError> {
+        let result = self.next_edges(this, number).await;
+        self.returned(result)
     }
 
     async fn log(&mut self, lv: imports::LogLevel, msg: &str) {
+        let call = self.traced("log", 1);
         match lv {
             imports::LogLevel::Trace => log::log!(log::Level::Trace, "{}", msg),
             imports::LogLevel::Debug => log::log!(log::Level::Debug, "{}", msg),
@@ -812,15 +1218,19 @@ impl imports::Imports for Storage {
             imports::LogLevel::Warn => log::log!(log::Level::Warn, "{}", msg),
             imports::LogLevel::Error => log::log!(log::Level::Error, "{}", msg),
         }
+        call.finish(0);
     }
 
     async fn log_enabled(&mut self, lv: imports::LogLevel) -> bool {
-        match lv {
+        let call = self.traced("log-enabled", 0);
+        let enabled = match lv {
             imports::LogLevel::Trace => log::log_enabled!(log::Level::Trace),
             imports::LogLevel::Debug => log::log_enabled!(log::Level::Debug),
             imports::LogLevel::Info => log::log_enabled!(log::Level::Info),
             imports::LogLevel::Warn => log::log_enabled!(log::Level::Warn),
             imports::LogLevel::Error => log::log_enabled!(log::Level::Error),
-        }
+        };
+        call.finish(1);
+        enabled
     }
 }
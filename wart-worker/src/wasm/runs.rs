@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use prometheus::IntGauge;
+use tokio::sync::Notify;
+
+use crate::metrics;
+
+/// A run in progress on this worker.
+#[derive(Debug, Clone)]
+pub struct RunInfo {
+    pub run_id: u64,
+    pub token: String,
+    pub request_id: String,
+    pub args: Vec<String>,
+    pub sta_time: i64,
+}
+
+struct Entry {
+    info: RunInfo,
+    kill: Arc<Notify>,
+}
+
+/// The runs of this worker, registered by `streaming_run_launch` for as long
+/// as they last so the admin service can list and kill them.
+#[derive(Default)]
+pub struct RunRegistry {
+    next_id: AtomicU64,
+    runs: Mutex<HashMap<u64, Entry>>,
+}
+
+impl RunRegistry {
+    pub fn register(
+        &self,
+        token: &str,
+        space_name: &str,
+        request_id: &str,
+        args: &[String],
+    ) -> RunGuard<'_> {
+        let run_id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        let kill = Arc::new(Notify::new());
+        let info = RunInfo {
+            run_id,
+            token: token.into(),
+            request_id: request_id.into(),
+            args: args.to_vec(),
+            sta_time: chrono::Local::now().timestamp_millis(),
+        };
+        if let Ok(mut runs) = self.runs.lock() {
+            runs.insert(
+                run_id,
+                Entry {
+                    info,
+                    kill: kill.clone(),
+                },
+            );
+        }
+        let in_flight = metrics::runs_in_flight(space_name);
+        in_flight.inc();
+        RunGuard {
+            registry: self,
+            run_id,
+            kill,
+            in_flight,
+        }
+    }
+
+    /// The runs of the session, or all of them for `None`, oldest first.
+    pub fn list(&self, token: Option<&str>) -> Vec<RunInfo> {
+        let runs = match self.runs.lock() {
+            Ok(runs) => runs,
+            Err(_) => return vec![],
+        };
+        let mut list: Vec<_> = runs
+            .values()
+            .filter(|entry| token.map_or(true, |token| entry.info.token == token))
+            .map(|entry| entry.info.clone())
+            .collect();
+        list.sort_by_key(|info| info.run_id);
+        list
+    }
+
+    pub fn count(&self, token: &str) -> usize {
+        match self.runs.lock() {
+            Ok(runs) => runs
+                .values()
+                .filter(|entry| entry.info.token == token)
+                .count(),
+            Err(_) => 0,
+        }
+    }
+
+    /// Returns whether the run was still in progress.
+    pub fn kill(&self, run_id: u64) -> bool {
+        let runs = match self.runs.lock() {
+            Ok(runs) => runs,
+            Err(_) => return false,
+        };
+        match runs.get(&run_id) {
+            Some(entry) => {
+                entry.kill.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Unregisters the run when dropped.
+pub struct RunGuard<'a> {
+    registry: &'a RunRegistry,
+    run_id: u64,
+    kill: Arc<Notify>,
+    in_flight: IntGauge,
+}
+
+impl RunGuard<'_> {
+    /// Completes once the run is killed, also if that happened earlier.
+    pub async fn killed(&self) {
+        self.kill.notified().await
+    }
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut runs) = self.registry.runs.lock() {
+            runs.remove(&self.run_id);
+        }
+        self.in_flight.dec();
+    }
+}
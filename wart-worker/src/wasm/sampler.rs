@@ -0,0 +1,95 @@
+use crate::bindgen::imports;
+
+use rand::Rng;
+
+use std::cmp::Ordering;
+
+/// Picks `k` distinct positions out of `0..n` uniformly.
+pub fn uniform(n: usize, k: usize) -> Vec<usize> {
+    if k >= n {
+        return (0..n).collect();
+    }
+
+    let mut rng = rand::thread_rng();
+    let mut index = rand::seq::index::sample(&mut rng, n, k).into_vec();
+    index.sort_unstable();
+    index
+}
+
+/// Picks `k` distinct positions with probabilities proportional to `weights`
+/// (Efraimidis-Spirakis). Positions with non-positive weights are never picked.
+pub fn weighted(weights: &[f64], k: usize) -> Vec<usize> {
+    let mut rng = rand::thread_rng();
+    let mut keys = weights
+        .iter()
+        .enumerate()
+        .filter(|(_, w)| **w > 0.0)
+        .map(|(i, w)| {
+            let u: f64 = rng.gen_range(f64::EPSILON..1.0);
+            (u.powf(1.0 / w), i)
+        })
+        .collect::<Vec<_>>();
+
+    if k < keys.len() {
+        keys.select_nth_unstable_by(k, |a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        keys.truncate(k);
+    }
+
+    let mut index = keys.into_iter().map(|(_, i)| i).collect::<Vec<_>>();
+    index.sort_unstable();
+    index
+}
+
+/// Picks the `k` positions with the largest `times`, the most recent first.
+pub fn most_recent(times: &[f64], k: usize) -> Vec<usize> {
+    let mut index = (0..times.len()).collect::<Vec<_>>();
+    index.sort_by(|a, b| times[*b].partial_cmp(&times[*a]).unwrap_or(Ordering::Equal));
+    index.truncate(k);
+    index
+}
+
+pub fn to_f64(v: &imports::VectorResult) -> Option<Vec<f64>> {
+    match v {
+        imports::VectorResult::I32(x) => Some(x.iter().map(|v| *v as f64).collect()),
+        imports::VectorResult::I64(x) => Some(x.iter().map(|v| *v as f64).collect()),
+        imports::VectorResult::F32(x) => Some(x.iter().map(|v| *v as f64).collect()),
+        imports::VectorResult::F64(x) => Some(x.clone()),
+        _ => None,
+    }
+}
+
+pub fn take(v: &imports::VectorResult, index: &[usize]) -> imports::VectorResult {
+    match v {
+        imports::VectorResult::Nil => imports::VectorResult::Nil,
+        imports::VectorResult::Bol(x) => {
+            imports::VectorResult::Bol(index.iter().map(|i| x[*i]).collect())
+        }
+        imports::VectorResult::I32(x) => {
+            imports::VectorResult::I32(index.iter().map(|i| x[*i]).collect())
+        }
+        imports::VectorResult::I64(x) => {
+            imports::VectorResult::I64(index.iter().map(|i| x[*i]).collect())
+        }
+        imports::VectorResult::F32(x) => {
+            imports::VectorResult::F32(index.iter().map(|i| x[*i]).collect())
+        }
+        imports::VectorResult::F64(x) => {
+            imports::VectorResult::F64(index.iter().map(|i| x[*i]).collect())
+        }
+        imports::VectorResult::Txt(x) => {
+            imports::VectorResult::Txt(index.iter().map(|i| x[*i].clone()).collect())
+        }
+    }
+}
+
+pub fn len(v: &imports::VectorResult) -> usize {
+    match v {
+        imports::VectorResult::Nil => 0,
+        imports::VectorResult::Bol(x) => x.len(),
+        imports::VectorResult::I32(x) => x.len(),
+        imports::VectorResult::I64(x) => x.len(),
+        imports::VectorResult::F32(x) => x.len(),
+        imports::VectorResult::F64(x) => x.len(),
+        imports::VectorResult::Txt(x) => x.len(),
+    }
+}
@@ -1,10 +1,13 @@
 use crate::bindgen::imports;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use std::sync::Arc;
 
-use wasmtime::{Config, Engine, Instance, Linker, Module, Store, Trap};
-use wasmtime_wasi::WasiCtx;
+use wasmtime::{
+    Config, Engine, ExternType, FuncType, Instance, Linker, Module, ResourceLimiter, Store,
+    StoreLimits, StoreLimitsBuilder, Trap, TrapCode, ValType,
+};
+use wasmtime_wasi::{WasiCtx, WasiCtxBuilder};
 
 pub struct Context<T>
 where
@@ -13,22 +16,130 @@ where
     pub wasi_ctx: WasiCtx,
     pub imports: T,
     imports_tables: imports::ImportsTables<T>,
+    limiter: Limiter,
+}
+
+/// `StoreLimits` remembering whether a memory or a table was denied to grow,
+/// which along with the trap code tells an out-of-memory abort of the guest.
+struct Limiter {
+    limits: StoreLimits,
+    denied: bool,
+}
+
+impl ResourceLimiter for Limiter {
+    fn memory_growing(&mut self, current: usize, desired: usize, maximum: Option<usize>) -> bool {
+        let allowed = self.limits.memory_growing(current, desired, maximum);
+        self.denied |= !allowed;
+        allowed
+    }
+
+    fn table_growing(&mut self, current: u32, desired: u32, maximum: Option<u32>) -> bool {
+        let allowed = self.limits.table_growing(current, desired, maximum);
+        self.denied |= !allowed;
+        allowed
+    }
+
+    fn instances(&self) -> usize {
+        self.limits.instances()
+    }
+
+    fn tables(&self) -> usize {
+        self.limits.tables()
+    }
+
+    fn memories(&self) -> usize {
+        self.limits.memories()
+    }
 }
 
 impl<T> Context<T>
 where
     T: imports::Imports,
 {
-    fn new(wasi_ctx: WasiCtx, imports: T) -> Self {
+    fn new(wasi_ctx: WasiCtx, imports: T, limits: StoreLimits) -> Self {
         let imports_tables = imports::ImportsTables::default();
         Self {
             wasi_ctx,
             imports,
             imports_tables,
+            limiter: Limiter {
+                limits,
+                denied: false,
+            },
         }
     }
 }
 
+/// Something that would make every run of a program fail at instantiation or
+/// at the start of `call_async`.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum ProgramError {
+    #[error("missing export `{name}`")]
+    MissingExport { name: String },
+
+    #[error("export `{name}` is {found}, expected {expected}")]
+    BadExport {
+        name: String,
+        expected: String,
+        found: String,
+    },
+
+    #[error("unknown import `{module}::{name}`")]
+    UnknownImport { module: String, name: String },
+
+    #[error("import `{module}::{name}` is {found}, the worker provides {expected}")]
+    BadImport {
+        module: String,
+        name: String,
+        expected: String,
+        found: String,
+    },
+}
+
+/// The exports `call_async` and the canonical ABI of the imports rely on.
+fn required_exports() -> Vec<(&'static str, ExternType)> {
+    use ValType::I32;
+    let unit = || FuncType::new([], []);
+    vec![
+        ("_start", unit().into()),
+        ("__wasm_call_ctors", unit().into()),
+        ("__wasm_call_dtors", unit().into()),
+        (
+            "canonical_abi_realloc",
+            FuncType::new([I32, I32, I32, I32], [I32]).into(),
+        ),
+    ]
+}
+
+/// Memories and tables only have to agree on the kind, their limits are
+/// checked by the instantiation itself.
+fn same_type(a: &ExternType, b: &ExternType) -> bool {
+    match (a, b) {
+        (ExternType::Func(a), ExternType::Func(b)) => a == b,
+        (ExternType::Global(a), ExternType::Global(b)) => a == b,
+        (ExternType::Memory(_), ExternType::Memory(_)) => true,
+        (ExternType::Table(_), ExternType::Table(_)) => true,
+        _ => false,
+    }
+}
+
+fn describe(ty: &ExternType) -> String {
+    fn list(types: impl Iterator<Item = ValType>) -> String {
+        types.map(|t| t.to_string()).collect::<Vec<_>>().join(", ")
+    }
+    match ty {
+        ExternType::Func(f) => format!("func ({}) -> ({})", list(f.params()), list(f.results())),
+        ExternType::Global(_) => "a global".into(),
+        ExternType::Table(_) => "a table".into(),
+        ExternType::Memory(_) => "a memory".into(),
+        _ => "an unsupported item".into(),
+    }
+}
+
+/// Serialized modules start with it, followed by the version of the engine
+/// settings they were compiled with, see `EngineConfig::version`.
+const MODULE_HEADER: &[u8] = b"wart-module:";
+
 #[derive(Clone)]
 pub struct SandboxManager<T>
 where
@@ -44,6 +155,7 @@ where
     T: imports::Imports + Send + 'static,
     T::DataFrame: Send + 'static,
     T::Storage: Send + 'static,
+    T::EdgeScanner: Send + 'static,
 {
     pub fn default_config() -> Config {
         let mut config = Config::default();
@@ -51,28 +163,103 @@ where
         config.epoch_interruption(true);
         config
     }
-    pub fn from_module(module: &[u8], config: &Config) -> Result<Self> {
-        let engine = Engine::new(config)?;
-        let module = unsafe { Module::deserialize(&engine, module)? };
-        let linker = Self::add_to_linker(&engine)?;
+    /// `version` is the one of the engine settings of this worker, modules
+    /// compiled with others are refused.
+    pub fn from_module(engine: &Engine, version: u64, module: &[u8]) -> Result<Self> {
+        let module = match module.strip_prefix(MODULE_HEADER) {
+            Some(rest) if rest.len() >= 8 => {
+                let (found, rest) = rest.split_at(8);
+                let found = u64::from_le_bytes(found.try_into()?);
+                if found != version {
+                    Err(anyhow!(
+                        "module compiled with engine settings {:#x}, this worker has {:#x}",
+                        found,
+                        version
+                    ))?;
+                }
+                rest
+            }
+            // serialized before the version was recorded
+            _ => module,
+        };
+        let module = unsafe { Module::deserialize(engine, module)? };
+        let linker = Self::add_to_linker(engine)?;
         Ok(Self {
-            engine,
+            engine: engine.clone(),
             module,
             linker: Arc::new(linker),
         })
     }
 
-    pub fn from_bytes(program: &[u8], config: &Config) -> Result<Self> {
-        let engine = Engine::new(config)?;
-        let module = Module::new(&engine, program)?;
-        let linker = Self::add_to_linker(&engine)?;
+    /// The module for `from_module`, along with the `version` of the engine
+    /// settings it was compiled with.
+    pub fn serialize(&self, version: u64) -> Result<Vec<u8>> {
+        let mut bytes = MODULE_HEADER.to_vec();
+        bytes.extend(version.to_le_bytes());
+        bytes.extend(self.module.serialize()?);
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(engine: &Engine, program: &[u8]) -> Result<Self> {
+        let module = Module::new(engine, program)?;
+        let linker = Self::add_to_linker(engine)?;
         Ok(Self {
-            engine,
+            engine: engine.clone(),
             module,
             linker: Arc::new(linker),
         })
     }
 
+    /// Checks the exports of the module and that the linker satisfies all of
+    /// its imports. Looking up the linker takes a store, which is built from
+    /// the throwaway `imports`.
+    pub fn check(&self, imports: T) -> Vec<ProgramError> {
+        let mut errors = vec![];
+
+        for (name, expected) in required_exports() {
+            match self.module.get_export(name) {
+                None => errors.push(ProgramError::MissingExport { name: name.into() }),
+                Some(found) if !same_type(&found, &expected) => {
+                    errors.push(ProgramError::BadExport {
+                        name: name.into(),
+                        expected: describe(&expected),
+                        found: describe(&found),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+
+        let context = Context::<T>::new(
+            WasiCtxBuilder::new().build(),
+            imports,
+            StoreLimitsBuilder::new().build(),
+        );
+        let mut store = Store::new(&self.engine, context);
+        for import in self.module.imports() {
+            let module = import.module().to_string();
+            let name = import.name().unwrap_or_default().to_string();
+            let found = import.ty();
+            let expected = match self.linker.get_by_import(&mut store, &import) {
+                Some(ext) => ext.ty(&store),
+                None => {
+                    errors.push(ProgramError::UnknownImport { module, name });
+                    continue;
+                }
+            };
+            if !same_type(&found, &expected) {
+                errors.push(ProgramError::BadImport {
+                    module,
+                    name,
+                    expected: describe(&expected),
+                    found: describe(&found),
+                });
+            }
+        }
+
+        errors
+    }
+
     fn add_to_linker(engine: &Engine) -> Result<Linker<Context<T>>> {
         let mut linker: Linker<Context<T>> = Linker::new(&engine);
         wasmtime_wasi::add_to_linker(&mut linker, |cx| &mut cx.wasi_ctx)?;
@@ -80,13 +267,43 @@ where
         Ok(linker)
     }
 
-    pub async fn instantiate(&self, wasi_ctx: WasiCtx, imports: T) -> Result<Sandbox<T>> {
-        let mut store = Store::new(&self.engine, Context::<T>::new(wasi_ctx, imports));
-        let instance = self
+    /// `fuel` bounds the instructions of the whole run, `0` for no limit. A
+    /// budget fails unless the engine consumes fuel, as it couldn't be kept,
+    /// e.g. for a session opened on a worker that does. `limits` bounds the
+    /// memories and instances the program may create.
+    pub async fn instantiate(
+        &self,
+        wasi_ctx: WasiCtx,
+        imports: T,
+        fuel: u64,
+        limits: StoreLimits,
+    ) -> Result<Sandbox<T>> {
+        let context = Context::<T>::new(wasi_ctx, imports, limits);
+        let mut store = Store::new(&self.engine, context);
+        store.limiter(|cx| &mut cx.limiter);
+        let metered = store
+            .add_fuel(if fuel > 0 { fuel } else { u64::MAX })
+            .is_ok();
+        if fuel > 0 && !metered {
+            Err(anyhow!("a fuel budget of {} needs fuel metering", fuel))?;
+        }
+        let fuel = if fuel > 0 { Some(fuel) } else { None };
+
+        let instance = match self
             .linker
             .instantiate_async(&mut store, &self.module)
-            .await?;
-        Ok(Sandbox::<T> { store, instance })
+            .await
+        {
+            Ok(instance) => instance,
+            // the initial memories or tables are larger than the limits allow
+            Err(err) if store.data().limiter.denied => Err(InstantiateError::OutOfMemory(err))?,
+            Err(err) => return Err(err),
+        };
+        Ok(Sandbox::<T> {
+            store,
+            instance,
+            fuel,
+        })
     }
 }
 
@@ -96,6 +313,26 @@ where
 {
     pub store: Store<Context<T>>,
     instance: Instance,
+    fuel: Option<u64>,
+}
+
+/// The limits of the session deny what the program needs before it starts.
+#[derive(Debug, thiserror::Error)]
+pub enum InstantiateError {
+    #[error("out of memory: {0}")]
+    OutOfMemory(anyhow::Error),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CallError {
+    #[error("out of fuel: the budget of {0} is exhausted")]
+    OutOfFuel(u64),
+
+    #[error("out of memory: {0}")]
+    OutOfMemory(Trap),
+
+    #[error(transparent)]
+    Trap(#[from] Trap),
 }
 
 impl<T> Sandbox<T>
@@ -103,6 +340,7 @@ where
     T: imports::Imports + Send + 'static,
     T::DataFrame: Send + 'static,
     T::Storage: Send + 'static,
+    T::EdgeScanner: Send + 'static,
 {
     pub async fn call_ctors(&mut self) -> Result<(), Trap> {
         let __wasm_call_ctors = self
@@ -123,10 +361,33 @@ where
         _start.call_async(&mut self.store, ()).await
     }
 
-    pub async fn call_async(&mut self) -> Result<(), Trap> {
-        self.call_ctors().await?;
-        self.call_start().await?;
-        self.call_dtors().await?;
-        Ok(())
+    pub async fn call_async(&mut self) -> Result<(), CallError> {
+        let result = async {
+            self.call_ctors().await?;
+            self.call_start().await?;
+            self.call_dtors().await
+        }
+        .await;
+
+        match (result, self.fuel) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), Some(fuel)) if self.fuel_consumed() >= Some(fuel) => {
+                Err(CallError::OutOfFuel(fuel))
+            }
+            (Err(trap), _) if self.out_of_memory(&trap) => Err(CallError::OutOfMemory(trap)),
+            (Err(trap), _) => Err(trap.into()),
+        }
+    }
+
+    /// An allocator that can't grow the memory aborts with `unreachable`. Other
+    /// traps, e.g. a timeout, are what they are even after a denial.
+    fn out_of_memory(&self, trap: &Trap) -> bool {
+        self.store.data().limiter.denied
+            && trap.trap_code() == Some(TrapCode::UnreachableCodeReached)
+    }
+
+    /// `None` unless the engine consumes fuel.
+    pub fn fuel_consumed(&self) -> Option<u64> {
+        self.store.fuel_consumed()
     }
 }
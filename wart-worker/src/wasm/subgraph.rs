@@ -0,0 +1,251 @@
+use crate::backend::{BackendError, GraphBackend, FANOUT_CONCURRENCY};
+use crate::bindgen::imports::StorageErrorKind;
+use crate::bindgen::*;
+
+use super::limits;
+use super::{sampler, utils};
+
+use anyhow::{anyhow, Context, Result};
+use futures::StreamExt;
+
+use std::collections::{BTreeMap, HashSet};
+
+pub type Block = (String, BTreeMap<String, imports::VectorResult>);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NodeKey {
+    Int(i64),
+    Str(String),
+}
+
+impl NodeKey {
+    pub fn from_vector(v: imports::VectorResult) -> Option<Vec<Self>> {
+        match v {
+            imports::VectorResult::I32(x) => {
+                Some(x.into_iter().map(|v| Self::Int(v as i64)).collect())
+            }
+            imports::VectorResult::I64(x) => Some(x.into_iter().map(Self::Int).collect()),
+            imports::VectorResult::Txt(x) => Some(x.into_iter().map(Self::Str).collect()),
+            imports::VectorResult::Nil => Some(vec![]),
+            _ => None,
+        }
+    }
+
+    pub fn to_vector(ids: &[Self]) -> imports::VectorResult {
+        if ids.iter().all(|id| matches!(id, Self::Int(_))) {
+            imports::VectorResult::I64(
+                ids.iter()
+                    .filter_map(|id| match id {
+                        Self::Int(x) => Some(*x),
+                        Self::Str(_) => None,
+                    })
+                    .collect(),
+            )
+        } else {
+            imports::VectorResult::Txt(
+                ids.iter()
+                    .map(|id| match id {
+                        Self::Int(x) => x.to_string(),
+                        Self::Str(x) => x.clone(),
+                    })
+                    .collect(),
+            )
+        }
+    }
+
+    fn to_series(ids: &[Self]) -> Series {
+        let values = match Self::to_vector(ids) {
+            imports::VectorResult::I64(data) => {
+                series::Values::Int64Values(series::Int64Series { data })
+            }
+            imports::VectorResult::Txt(data) => {
+                series::Values::StringValues(series::StringSeries { data })
+            }
+            _ => unreachable!(),
+        };
+        Series {
+            values: Some(values),
+        }
+    }
+}
+
+pub fn extend_vector(dst: &mut imports::VectorResult, src: imports::VectorResult) -> bool {
+    if let imports::VectorResult::Nil = dst {
+        *dst = src;
+        return true;
+    }
+
+    match (dst, src) {
+        (_, imports::VectorResult::Nil) => (),
+        (imports::VectorResult::Bol(x), imports::VectorResult::Bol(y)) => x.extend(y),
+        (imports::VectorResult::I32(x), imports::VectorResult::I32(y)) => x.extend(y),
+        (imports::VectorResult::I64(x), imports::VectorResult::I64(y)) => x.extend(y),
+        (imports::VectorResult::F32(x), imports::VectorResult::F32(y)) => x.extend(y),
+        (imports::VectorResult::F64(x), imports::VectorResult::F64(y)) => x.extend(y),
+        (imports::VectorResult::Txt(x), imports::VectorResult::Txt(y)) => x.extend(y),
+        _ => return false,
+    }
+    true
+}
+
+/// Rows and bytes of `tables`, as charged to `Usage`.
+fn size(tables: &[Block]) -> (u64, u64) {
+    let rows = tables
+        .iter()
+        .map(|(_, table)| table.values().map(sampler::len).max().unwrap_or(0) as u64)
+        .sum();
+    let bytes = tables
+        .iter()
+        .flat_map(|(_, table)| table.values().map(limits::vector_bytes))
+        .sum();
+    (rows, bytes)
+}
+
+/// Layer-wise uniform sampling starting from `seeds`. At hop `i` every node of
+/// the frontier draws at most `fanouts[i]` neighbors along each edge type in
+/// `tags`, and only the nodes seen for the first time make the next frontier.
+/// The neighbors of a whole frontier are fetched with one batch per edge type.
+///
+/// Returns a "nodes" table (id, hop) followed by one COO block per hop and edge
+/// type, named "hop{i}:{tag}", with columns src, dst and the edge attributes in
+/// `keys`. The "nodes" table has no node attributes, those are left to
+/// `query-nodes-batch`.
+///
+/// Every hop is passed to `charge` as the number of tables so far, their rows
+/// and bytes before the next one is fetched, so that a subgraph over the limits
+/// of the run fails early.
+pub async fn sample_subgraph(
+    backend: &dyn GraphBackend,
+    space_name: &str,
+    seeds: Vec<NodeKey>,
+    tags: &[String],
+    fanouts: &[i32],
+    keys: &[String],
+    mut charge: impl FnMut(usize, u64, u64) -> Result<(), String>,
+) -> Result<Vec<Block>> {
+    let mut visited = HashSet::new();
+    let mut nodes = vec![];
+    let mut hops = vec![];
+
+    let mut frontier = vec![];
+    for seed in seeds.into_iter() {
+        if visited.insert(seed.clone()) {
+            nodes.push(seed.clone());
+            hops.push(0);
+            frontier.push(seed);
+        }
+    }
+
+    let mut blocks = vec![];
+    for (hop, fanout) in fanouts.iter().enumerate() {
+        let k = (*fanout).max(0) as usize;
+
+        let jobs = if frontier.is_empty() {
+            vec![]
+        } else {
+            tags.to_vec()
+        };
+        let node_ids = NodeKey::to_series(&frontier);
+        let results = futures::stream::iter(jobs.into_iter().map(|tag| {
+            let request = FetchNeighborsBatchRequest {
+                space_name: space_name.into(),
+                node_ids: Some(node_ids.clone()),
+                tag: tag.clone(),
+                keys: keys.to_vec(),
+                reversely: false,
+            };
+            async move { (tag, backend.fetch_neighbors_batch(request).await) }
+        }))
+        .buffered(FANOUT_CONCURRENCY)
+        .collect::<Vec<_>>()
+        .await;
+
+        let mut hop_blocks: BTreeMap<String, BTreeMap<String, imports::VectorResult>> = tags
+            .iter()
+            .map(|tag| (tag.clone(), BTreeMap::new()))
+            .collect();
+        let mut next_frontier = vec![];
+
+        for (tag, result) in results.into_iter() {
+            let data = result.context("can't query storage nodes")?;
+
+            let mut it = utils::dump_to_imports_table(data).into_iter();
+            let (offsets, dst) = match (it.next(), it.next()) {
+                (Some(offsets), Some(dst)) => (offsets.val, dst.val),
+                _ => Err(anyhow!("too few columns in response"))?,
+            };
+            let offsets = match offsets {
+                imports::VectorResult::I64(x) if x.len() == frontier.len() + 1 => x,
+                _ => Err(anyhow!("mismatched offsets in response"))?,
+            };
+
+            // the sampled neighbors of every node of the frontier, in order
+            let mut src = vec![];
+            let mut index = vec![];
+            for (node, range) in frontier.iter().zip(offsets.windows(2)) {
+                let (start, end) = (range[0].max(0) as usize, range[1].max(0) as usize);
+                let sampled = sampler::uniform(end.saturating_sub(start), k);
+                src.extend(std::iter::repeat(node.clone()).take(sampled.len()));
+                index.extend(sampled.into_iter().map(|i| start + i));
+            }
+
+            if index.iter().any(|i| *i >= sampler::len(&dst)) {
+                Err(anyhow!("offsets out of range in response"))?;
+            }
+            let dst = sampler::take(&dst, &index);
+            let dst_ids = NodeKey::from_vector(dst.clone())
+                .ok_or_else(|| anyhow!("unrecognized type of node_id: {:?}", dst))?;
+
+            for id in dst_ids.iter() {
+                if visited.insert(id.clone()) {
+                    nodes.push(id.clone());
+                    hops.push(hop as i32 + 1);
+                    next_frontier.push(id.clone());
+                }
+            }
+
+            let block = hop_blocks.entry(tag).or_default();
+            let src = NodeKey::to_vector(&src);
+
+            let mut columns = vec![("src".to_string(), src), ("dst".to_string(), dst)];
+            columns.extend(it.map(|s| (s.key, sampler::take(&s.val, &index))));
+
+            for (key, val) in columns.into_iter() {
+                let column = block.entry(key).or_insert(imports::VectorResult::Nil);
+                if !extend_vector(column, val) {
+                    Err(anyhow!("mismatched types of columns in hop {}", hop))?;
+                }
+            }
+        }
+
+        let hop_blocks = hop_blocks
+            .into_iter()
+            .map(|(tag, block)| (format!("hop{}:{}", hop, tag), block))
+            .collect::<Vec<_>>();
+        let (rows, bytes) = size(&hop_blocks);
+        blocks.extend(hop_blocks);
+        charge(1 + blocks.len(), rows, bytes)
+            .map_err(|message| BackendError::new(StorageErrorKind::LimitExceeded, message))?;
+
+        frontier = next_frontier;
+    }
+
+    let mut table = BTreeMap::new();
+    table.insert("id".to_string(), NodeKey::to_vector(&nodes));
+    table.insert("hop".to_string(), imports::VectorResult::I32(hops));
+
+    let mut tables = vec![("nodes".to_string(), table)];
+    let (rows, bytes) = size(&tables);
+    charge(1 + blocks.len(), rows, bytes)
+        .map_err(|message| BackendError::new(StorageErrorKind::LimitExceeded, message))?;
+    tables.extend(blocks);
+
+    for (name, table) in tables.iter() {
+        let n = table.values().map(sampler::len).max().unwrap_or(0);
+        if table.values().any(|v| sampler::len(v) != n) {
+            Err(anyhow!("mismatched length of columns in {}", name))?;
+        }
+    }
+
+    Ok(tables)
+}
@@ -0,0 +1,186 @@
+use crate::bindgen::*;
+use crate::metrics;
+
+use prometheus::HistogramTimer;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use super::sampler;
+
+/// The name of the table the trace of a run is returned in, which programs
+/// may not use for their own.
+pub const TRACE_TABLE: &str = "__trace__";
+
+/// Calls recorded by a single run, the rest are dropped.
+const TRACE_CAPACITY: usize = 1 << 16;
+
+/// A call of a host import, a row of the `__trace__` table.
+#[derive(Debug)]
+pub struct TraceEntry {
+    /// Unix microseconds of the start of the call.
+    pub timestamp: i64,
+    pub import: &'static str,
+    pub arg_size: u64,
+    pub result_size: u64,
+    pub latency_us: i64,
+    pub ok: bool,
+}
+
+/// The calls of a run, shared with the `ImportCall`s in progress since
+/// those can't borrow the `Storage` they are made on.
+pub type Trace = Arc<Mutex<Vec<TraceEntry>>>;
+
+/// A call of a host import in progress. It observes the latency metrics, if
+/// any, and for traced runs is appended to the trace when dropped. Calls
+/// dropped without `finish` are recorded as failed, so every return counts.
+pub struct ImportCall {
+    import: &'static str,
+    arg_size: usize,
+    result_size: usize,
+    ok: bool,
+    timestamp: i64,
+    started: Instant,
+    trace: Option<Trace>,
+    _timer: Option<HistogramTimer>,
+}
+
+impl ImportCall {
+    pub fn new(
+        import: &'static str,
+        space_name: &str,
+        arg_size: usize,
+        trace: Option<Trace>,
+    ) -> Self {
+        let timer = metrics::import_timer(import, space_name);
+        Self::start(import, arg_size, trace, Some(timer))
+    }
+
+    /// A call that is only traced. The data-frame and log imports don't reach
+    /// the backends, and are called too often to be timed on every run.
+    pub fn traced(import: &'static str, arg_size: usize, trace: Option<Trace>) -> Self {
+        Self::start(import, arg_size, trace, None)
+    }
+
+    fn start(
+        import: &'static str,
+        arg_size: usize,
+        trace: Option<Trace>,
+        timer: Option<HistogramTimer>,
+    ) -> Self {
+        let timestamp = match trace {
+            Some(_) => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_micros() as i64)
+                .unwrap_or(0),
+            None => 0,
+        };
+        Self {
+            import,
+            arg_size,
+            result_size: 0,
+            ok: false,
+            timestamp,
+            started: Instant::now(),
+            trace,
+            _timer: timer,
+        }
+    }
+
+    /// Marks the call as succeeded with `result_size` rows or values.
+    pub fn finish(mut self, result_size: usize) {
+        self.ok = true;
+        self.result_size = result_size;
+    }
+}
+
+impl Drop for ImportCall {
+    fn drop(&mut self) {
+        let trace = match &self.trace {
+            Some(trace) => trace,
+            None => return,
+        };
+        let entry = TraceEntry {
+            timestamp: self.timestamp,
+            import: self.import,
+            arg_size: self.arg_size as u64,
+            result_size: self.result_size as u64,
+            latency_us: self.started.elapsed().as_micros() as i64,
+            ok: self.ok,
+        };
+        if let Ok(mut entries) = trace.lock() {
+            if entries.len() < TRACE_CAPACITY {
+                entries.push(entry);
+            }
+        }
+    }
+}
+
+pub fn param_len(v: &imports::VectorParam<'_>) -> usize {
+    match v {
+        imports::VectorParam::Nil => 0,
+        imports::VectorParam::Bol(x) => x.len(),
+        imports::VectorParam::I32(x) => x.len(),
+        imports::VectorParam::I64(x) => x.len(),
+        imports::VectorParam::F32(x) => x.len(),
+        imports::VectorParam::F64(x) => x.len(),
+        imports::VectorParam::Txt(x) => x.len(),
+    }
+}
+
+/// Rows of a table returned by an import, all its series are as long.
+pub fn table_len(table: &imports::Table) -> usize {
+    table.first().map(|s| sampler::len(&s.val)).unwrap_or(0)
+}
+
+/// The `__trace__` table, one row per call in the order they finished.
+pub fn into_table(trace: Trace) -> DataFrame {
+    let entries = match trace.lock() {
+        Ok(mut entries) => std::mem::take(&mut *entries),
+        Err(_) => vec![],
+    };
+
+    let mut timestamp = vec![];
+    let mut import = vec![];
+    let mut arg_size = vec![];
+    let mut result_size = vec![];
+    let mut latency_us = vec![];
+    let mut ok = vec![];
+    for entry in entries {
+        timestamp.push(entry.timestamp);
+        import.push(entry.import.to_string());
+        arg_size.push(entry.arg_size as i64);
+        result_size.push(entry.result_size as i64);
+        latency_us.push(entry.latency_us);
+        ok.push(entry.ok);
+    }
+
+    let int64 = |data| Series {
+        values: Some(series::Values::Int64Values(series::Int64Series { data })),
+    };
+    DataFrame {
+        headers: vec![
+            "timestamp".into(),
+            "import".into(),
+            "arg_size".into(),
+            "result_size".into(),
+            "latency_us".into(),
+            "ok".into(),
+        ],
+        columns: vec![
+            int64(timestamp),
+            Series {
+                values: Some(series::Values::StringValues(series::StringSeries {
+                    data: import,
+                })),
+            },
+            int64(arg_size),
+            int64(result_size),
+            int64(latency_us),
+            Series {
+                values: Some(series::Values::BoolValues(series::BoolSeries { data: ok })),
+            },
+        ],
+        comment: TRACE_TABLE.into(),
+    }
+}
@@ -99,3 +99,173 @@ pub fn dump_to_imports_table(data: DataFrame) -> imports::Table {
         // .filter(|s| !matches!(s.val, imports::Vector::Nil))
         .collect::<Vec<_>>()
 }
+
+pub fn series_len(values: &Option<series::Values>) -> usize {
+    match values {
+        Some(series::Values::BoolValues(x)) => x.data.len(),
+        Some(series::Values::Int32Values(x)) => x.data.len(),
+        Some(series::Values::Int64Values(x)) => x.data.len(),
+        Some(series::Values::Float32Values(x)) => x.data.len(),
+        Some(series::Values::Float64Values(x)) => x.data.len(),
+        Some(series::Values::StringValues(x)) => x.data.len(),
+        None => 0,
+    }
+}
+
+pub fn concat_series(dst: &mut Option<series::Values>, src: Option<series::Values>) -> bool {
+    let src = match src {
+        Some(src) => src,
+        None => return true,
+    };
+
+    let dst = match dst.as_mut() {
+        Some(dst) => dst,
+        None => {
+            *dst = Some(src);
+            return true;
+        }
+    };
+
+    match (dst, src) {
+        (series::Values::BoolValues(x), series::Values::BoolValues(y)) => x.data.extend(y.data),
+        (series::Values::Int32Values(x), series::Values::Int32Values(y)) => x.data.extend(y.data),
+        (series::Values::Int64Values(x), series::Values::Int64Values(y)) => x.data.extend(y.data),
+        (series::Values::Float32Values(x), series::Values::Float32Values(y)) => {
+            x.data.extend(y.data)
+        }
+        (series::Values::Float64Values(x), series::Values::Float64Values(y)) => {
+            x.data.extend(y.data)
+        }
+        (series::Values::StringValues(x), series::Values::StringValues(y)) => {
+            x.data.extend(y.data)
+        }
+        _ => return false,
+    }
+    true
+}
+
+pub fn concat_frames(frames: Vec<DataFrame>) -> Option<DataFrame> {
+    let mut it = frames.into_iter();
+    let mut data = it.next().unwrap_or_default();
+
+    for frame in it {
+        if frame.headers != data.headers {
+            log::error!(
+                "mismatched headers: {:?} != {:?}",
+                frame.headers,
+                data.headers
+            );
+            return None;
+        }
+
+        for (dst, src) in data.columns.iter_mut().zip(frame.columns.into_iter()) {
+            if !concat_series(&mut dst.values, src.values) {
+                log::error!("mismatched types of columns");
+                return None;
+            }
+        }
+    }
+
+    Some(data)
+}
+
+pub fn default_value(v: &imports::VectorResult) -> imports::ValueResult {
+    match v {
+        imports::VectorResult::Nil => imports::ValueResult::Nil,
+        imports::VectorResult::Bol(_) => imports::ValueResult::Bol(false),
+        imports::VectorResult::I32(_) => imports::ValueResult::I32(0),
+        imports::VectorResult::I64(_) => imports::ValueResult::I64(0),
+        imports::VectorResult::F32(_) => imports::ValueResult::F32(0.0),
+        imports::VectorResult::F64(_) => imports::ValueResult::F64(0.0),
+        imports::VectorResult::Txt(_) => imports::ValueResult::Txt(String::new()),
+    }
+}
+
+/// An empty series of the type of `values`, if it has one.
+pub fn empty_series(values: &Option<series::Values>) -> Series {
+    let values = values.as_ref().map(|v| match v {
+        series::Values::BoolValues(_) => series::Values::BoolValues(Default::default()),
+        series::Values::Int32Values(_) => series::Values::Int32Values(Default::default()),
+        series::Values::Int64Values(_) => series::Values::Int64Values(Default::default()),
+        series::Values::Float32Values(_) => series::Values::Float32Values(Default::default()),
+        series::Values::Float64Values(_) => series::Values::Float64Values(Default::default()),
+        series::Values::StringValues(_) => series::Values::StringValues(Default::default()),
+    });
+    Series { values }
+}
+
+/// Concatenates the responses of `FetchNeighbors` into the layout of
+/// `FetchNeighborsBatch`, i.e. with an extra leading "offsets" column. An
+/// empty batch has no response to take the columns from, so it gets `dst`
+/// and an empty column for every key.
+pub fn concat_neighbors(frames: Vec<DataFrame>, dst: Series, keys: &[String]) -> Option<DataFrame> {
+    let mut offsets = vec![0i64];
+    for frame in frames.iter() {
+        let n = frame
+            .columns
+            .first()
+            .map(|s| series_len(&s.values))
+            .unwrap_or(0);
+        offsets.push(offsets[offsets.len() - 1] + n as i64);
+    }
+
+    let mut data = if frames.is_empty() {
+        DataFrame {
+            headers: std::iter::once("dst".into())
+                .chain(keys.iter().cloned())
+                .collect(),
+            columns: std::iter::once(dst)
+                .chain(keys.iter().map(|_| Series::default()))
+                .collect(),
+            comment: String::new(),
+        }
+    } else {
+        concat_frames(frames)?
+    };
+    data.headers.insert(0, "offsets".into());
+    data.columns.insert(
+        0,
+        Series {
+            values: Some(series::Values::Int64Values(series::Int64Series {
+                data: offsets,
+            })),
+        },
+    );
+    Some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ints(data: Vec<i64>) -> Series {
+        Series {
+            values: Some(series::Values::Int64Values(series::Int64Series { data })),
+        }
+    }
+
+    #[test]
+    fn empty_batch_has_offsets_and_empty_columns() {
+        let dst = empty_series(&ints(vec![1, 2]).values);
+        let keys = vec!["weight".to_string()];
+        let data = concat_neighbors(vec![], dst, &keys).unwrap();
+        assert_eq!(data.headers, vec!["offsets", "dst", "weight"]);
+        assert_eq!(data.columns[0], ints(vec![0]));
+        assert_eq!(data.columns[1], ints(vec![]));
+        assert_eq!(series_len(&data.columns[2].values), 0);
+    }
+
+    #[test]
+    fn batch_offsets_follow_the_frames() {
+        let frame = |dst: Vec<i64>| DataFrame {
+            headers: vec!["dst".into()],
+            columns: vec![ints(dst)],
+            comment: String::new(),
+        };
+        let frames = vec![frame(vec![1, 2]), frame(vec![]), frame(vec![3])];
+        let data = concat_neighbors(frames, Series::default(), &[]).unwrap();
+        assert_eq!(data.headers, vec!["offsets", "dst"]);
+        assert_eq!(data.columns[0], ints(vec![0, 2, 2, 3]));
+        assert_eq!(data.columns[1], ints(vec![1, 2, 3]));
+    }
+}
@@ -0,0 +1,70 @@
+use super::limits::{Limits, Usage};
+use super::sandbox::ProgramError;
+use super::{SandboxManager, Storage};
+use crate::bindgen::*;
+use crate::metrics;
+
+use diagnostic::Kind;
+use wasmtime::Engine;
+
+impl From<&ProgramError> for Diagnostic {
+    fn from(err: &ProgramError) -> Self {
+        let (kind, module, name) = match err {
+            ProgramError::MissingExport { name } => (Kind::MissingExport, "", name),
+            ProgramError::BadExport { name, .. } => (Kind::BadExport, "", name),
+            ProgramError::UnknownImport { module, name } => {
+                (Kind::UnknownImport, &module[..], name)
+            }
+            ProgramError::BadImport { module, name, .. } => (Kind::BadImport, &module[..], name),
+        };
+        Diagnostic {
+            kind: kind as i32,
+            message: err.to_string(),
+            module: module.into(),
+            name: name.clone(),
+        }
+    }
+}
+
+/// Compiles the program and checks it against the imports of this worker,
+/// so that sessions are only opened for programs able to run. Returns the
+/// compiled program or everything wrong with it.
+pub fn validate(
+    engine: &Engine,
+    program: &[u8],
+) -> Result<SandboxManager<Storage>, Vec<Diagnostic>> {
+    let timer = metrics::compile_timer("compile");
+    let compiled = SandboxManager::<Storage>::from_bytes(engine, program);
+    timer.observe_duration();
+    let manager = match compiled {
+        Ok(manager) => manager,
+        Err(err) => {
+            return Err(vec![Diagnostic {
+                kind: Kind::InvalidModule as i32,
+                message: format!("{:#}", err),
+                ..Default::default()
+            }])
+        }
+    };
+
+    // only lends a store to the lookups of the linker, nothing is run
+    let probe = Storage {
+        space_name: String::new(),
+        epoch: 0,
+        token: String::new(),
+        return_tables: vec![],
+        start_time: chrono::Local::now(),
+        counter: 0,
+        limits: Limits::default(),
+        usage: Usage::default(),
+        failure: None,
+        trace: None,
+    };
+
+    let diagnostics: Vec<_> = manager.check(probe).iter().map(Diagnostic::from).collect();
+    if diagnostics.is_empty() {
+        Ok(manager)
+    } else {
+        Err(diagnostics)
+    }
+}
@@ -20,6 +20,12 @@ extern "C"
   void imports_storage_free(imports_storage_t *ptr);
   imports_storage_t imports_storage_clone(imports_storage_t *ptr);
   
+  typedef struct {
+    uint32_t idx;
+  } imports_edge_scanner_t;
+  void imports_edge_scanner_free(imports_edge_scanner_t *ptr);
+  imports_edge_scanner_t imports_edge_scanner_clone(imports_edge_scanner_t *ptr);
+  
   typedef struct {
     char *ptr;
     size_t len;
@@ -119,6 +125,22 @@ extern "C"
   typedef uint8_t imports_merge_type_t;
   #define IMPORTS_MERGE_TYPE_ADD 0
   #define IMPORTS_MERGE_TYPE_MOV 1
+  typedef uint8_t imports_sample_strategy_t;
+  #define IMPORTS_SAMPLE_STRATEGY_UNIFORM 0
+  #define IMPORTS_SAMPLE_STRATEGY_WEIGHTED 1
+  #define IMPORTS_SAMPLE_STRATEGY_RECENT 2
+  typedef uint8_t imports_storage_error_kind_t;
+  #define IMPORTS_STORAGE_ERROR_KIND_NOT_FOUND 0
+  #define IMPORTS_STORAGE_ERROR_KIND_UNAVAILABLE 1
+  #define IMPORTS_STORAGE_ERROR_KIND_INVALID_ARGUMENT 2
+  #define IMPORTS_STORAGE_ERROR_KIND_TYPE_MISMATCH 3
+  #define IMPORTS_STORAGE_ERROR_KIND_LIMIT_EXCEEDED 4
+  #define IMPORTS_STORAGE_ERROR_KIND_INTERNAL 5
+  typedef struct {
+    imports_storage_error_kind_t kind;
+    imports_string_t message;
+  } imports_storage_error_t;
+  void imports_storage_error_free(imports_storage_error_t *ptr);
   typedef uint8_t imports_log_level_t;
   #define IMPORTS_LOG_LEVEL_TRACE 0
   #define IMPORTS_LOG_LEVEL_DEBUG 1
@@ -130,6 +152,67 @@ extern "C"
     imports_table_t f1;
   } imports_tuple2_vector_table_t;
   void imports_tuple2_vector_table_free(imports_tuple2_vector_table_t *ptr);
+  typedef struct {
+    imports_data_frame_t *ptr;
+    size_t len;
+  } imports_list_data_frame_t;
+  void imports_list_data_frame_free(imports_list_data_frame_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      imports_vector_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_vector_storage_error_t;
+  void imports_expected_vector_storage_error_free(imports_expected_vector_storage_error_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      imports_row_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_row_storage_error_t;
+  void imports_expected_row_storage_error_free(imports_expected_row_storage_error_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      imports_tuple2_vector_table_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_tuple2_vector_table_storage_error_t;
+  void imports_expected_tuple2_vector_table_storage_error_free(imports_expected_tuple2_vector_table_storage_error_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      imports_list_data_frame_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_list_data_frame_storage_error_t;
+  void imports_expected_list_data_frame_storage_error_free(imports_expected_list_data_frame_storage_error_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      imports_table_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_table_storage_error_t;
+  void imports_expected_table_storage_error_free(imports_expected_table_storage_error_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      uint64_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_u64_storage_error_t;
+  void imports_expected_u64_storage_error_free(imports_expected_u64_storage_error_t *ptr);
+  typedef struct {
+    bool is_err;
+    union {
+      imports_edge_scanner_t ok;
+      imports_storage_error_t err;
+    } val;
+  } imports_expected_edge_scanner_storage_error_t;
+  void imports_expected_edge_scanner_storage_error_free(imports_expected_edge_scanner_storage_error_t *ptr);
   bool imports_data_frame_new(imports_string_t *name, imports_row_t *defa, imports_data_frame_t *ret0);
   bool imports_data_frame_push(imports_data_frame_t self, imports_row_t *data, uint64_t *ret0);
   bool imports_data_frame_size(imports_data_frame_t self, uint64_t *ret0);
@@ -137,8 +220,25 @@ extern "C"
   bool imports_storage_choice_nodes(imports_storage_t self, imports_string_t *tag, int32_t number, imports_vector_t *ret0);
   bool imports_storage_query_nodes(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, imports_row_t *ret0);
   bool imports_storage_query_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_tuple2_vector_table_t *ret0);
+  bool imports_storage_sample_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, int32_t k, imports_sample_strategy_t strategy, imports_string_t *weight_key, bool reversely, imports_tuple2_vector_table_t *ret0);
+  bool imports_storage_sample_subgraph(imports_storage_t self, imports_vector_t *seeds, imports_list_string_t *tags, imports_list_s32_t *fanouts, imports_list_string_t *keys, imports_list_data_frame_t *ret0);
+  bool imports_storage_query_nodes_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, imports_table_t *ret0);
+  bool imports_storage_query_neighbors_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_table_t *ret0);
   bool imports_storage_query_kv(imports_storage_t self, imports_list_string_t *keys, imports_value_t *defa, imports_vector_t *ret0);
   bool imports_storage_update_kv(imports_storage_t self, imports_list_string_t *keys, imports_vector_t *vals, imports_merge_type_t ops, uint64_t *ret0);
+  void imports_storage_try_choice_nodes(imports_storage_t self, imports_string_t *tag, int32_t number, imports_expected_vector_storage_error_t *ret0);
+  void imports_storage_try_query_nodes(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, imports_expected_row_storage_error_t *ret0);
+  void imports_storage_try_query_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_expected_tuple2_vector_table_storage_error_t *ret0);
+  void imports_storage_try_sample_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, int32_t k, imports_sample_strategy_t strategy, imports_string_t *weight_key, bool reversely, imports_expected_tuple2_vector_table_storage_error_t *ret0);
+  void imports_storage_try_sample_subgraph(imports_storage_t self, imports_vector_t *seeds, imports_list_string_t *tags, imports_list_s32_t *fanouts, imports_list_string_t *keys, imports_expected_list_data_frame_storage_error_t *ret0);
+  void imports_storage_try_query_nodes_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, imports_expected_table_storage_error_t *ret0);
+  void imports_storage_try_query_neighbors_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_expected_table_storage_error_t *ret0);
+  void imports_storage_try_query_kv(imports_storage_t self, imports_list_string_t *keys, imports_value_t *defa, imports_expected_vector_storage_error_t *ret0);
+  void imports_storage_try_update_kv(imports_storage_t self, imports_list_string_t *keys, imports_vector_t *vals, imports_merge_type_t ops, imports_expected_u64_storage_error_t *ret0);
+  bool imports_edge_scanner_new(imports_string_t *tag, imports_string_t *time_field, int64_t start_time, int64_t duration, imports_edge_scanner_t *ret0);
+  bool imports_edge_scanner_next(imports_edge_scanner_t self, int64_t number, imports_table_t *ret0);
+  void imports_edge_scanner_try_new(imports_string_t *tag, imports_string_t *time_field, int64_t start_time, int64_t duration, imports_expected_edge_scanner_storage_error_t *ret0);
+  void imports_edge_scanner_try_next(imports_edge_scanner_t self, int64_t number, imports_expected_table_storage_error_t *ret0);
   void imports_log(imports_log_level_t lv, imports_string_t *msg);
   bool imports_log_enabled(imports_log_level_t lv);
   #ifdef __cplusplus
@@ -50,6 +50,20 @@ uint32_t __resource_storage_clone(uint32_t idx);
 imports_storage_t imports_storage_clone(imports_storage_t *ptr) {
   return (imports_storage_t){__resource_storage_clone(ptr->idx)};
 }
+
+__attribute__((import_module("canonical_abi"), import_name("resource_drop_edge-scanner")))
+void __resource_edge_scanner_drop(uint32_t idx);
+
+void imports_edge_scanner_free(imports_edge_scanner_t *ptr) {
+  __resource_edge_scanner_drop(ptr->idx);
+}
+
+__attribute__((import_module("canonical_abi"), import_name("resource_clone_edge-scanner")))
+uint32_t __resource_edge_scanner_clone(uint32_t idx);
+
+imports_edge_scanner_t imports_edge_scanner_clone(imports_edge_scanner_t *ptr) {
+  return (imports_edge_scanner_t){__resource_edge_scanner_clone(ptr->idx)};
+}
 #include <string.h>
 
 void imports_string_set(imports_string_t *ret, const char *s) {
@@ -145,13 +159,13 @@ void imports_table_free(imports_table_t *ptr) {
   }
   canonical_abi_free(ptr->ptr, ptr->len * 20, 4);
 }
+void imports_storage_error_free(imports_storage_error_t *ptr) {
+  imports_string_free(&ptr->message);
+}
 typedef struct {
   bool is_some;
   imports_data_frame_t val;
 } imports_option_data_frame_t;
-typedef struct {
-  bool is_some;
-} imports_option_empty_t;
 typedef struct {
   bool is_some;
   uint64_t val;
@@ -162,24 +176,94 @@ typedef struct {
 } imports_option_storage_t;
 typedef struct {
   bool is_some;
-  imports_series_t val;
-} imports_option_series_t;
+  imports_vector_t val;
+} imports_option_vector_t;
 typedef struct {
   bool is_some;
   imports_row_t val;
 } imports_option_row_t;
+void imports_tuple2_vector_table_free(imports_tuple2_vector_table_t *ptr) {
+  imports_vector_free(&ptr->f0);
+  imports_table_free(&ptr->f1);
+}
+typedef struct {
+  bool is_some;
+  imports_tuple2_vector_table_t val;
+} imports_option_tuple2_vector_table_t;
+void imports_list_data_frame_free(imports_list_data_frame_t *ptr) {
+  for (size_t i = 0; i < ptr->len; i++) {
+    imports_data_frame_free(&ptr->ptr[i]);
+  }
+  canonical_abi_free(ptr->ptr, ptr->len * 4, 4);
+}
+typedef struct {
+  bool is_some;
+  imports_list_data_frame_t val;
+} imports_option_list_data_frame_t;
 typedef struct {
   bool is_some;
   imports_table_t val;
 } imports_option_table_t;
+void imports_expected_vector_storage_error_free(imports_expected_vector_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+    imports_vector_free(&ptr->val.ok);
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
+void imports_expected_row_storage_error_free(imports_expected_row_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+    imports_row_free(&ptr->val.ok);
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
+void imports_expected_tuple2_vector_table_storage_error_free(imports_expected_tuple2_vector_table_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+    imports_tuple2_vector_table_free(&ptr->val.ok);
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
+void imports_expected_list_data_frame_storage_error_free(imports_expected_list_data_frame_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+    imports_list_data_frame_free(&ptr->val.ok);
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
+void imports_expected_table_storage_error_free(imports_expected_table_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+    imports_table_free(&ptr->val.ok);
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
+void imports_expected_u64_storage_error_free(imports_expected_u64_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
+typedef struct {
+  bool is_some;
+  imports_edge_scanner_t val;
+} imports_option_edge_scanner_t;
+void imports_expected_edge_scanner_storage_error_free(imports_expected_edge_scanner_storage_error_t *ptr) {
+  if (!ptr->is_err) {
+    imports_edge_scanner_free(&ptr->val.ok);
+  } else {
+    imports_storage_error_free(&ptr->val.err);
+  }
+}
 
 __attribute__((aligned(8)))
 static uint8_t RET_AREA[24];
 __attribute__((import_module("imports"), import_name("data-frame::new")))
 void __wasm_import_imports_data_frame_new(int32_t, int32_t, int32_t, int32_t, int32_t);
-bool imports_data_frame_new(imports_string_t *name, imports_row_t *default, imports_data_frame_t *ret0) {
+bool imports_data_frame_new(imports_string_t *name, imports_row_t *defa, imports_data_frame_t *ret0) {
   int32_t ptr = (int32_t) &RET_AREA;
-  __wasm_import_imports_data_frame_new((int32_t) (*name).ptr, (int32_t) (*name).len, (int32_t) (*default).ptr, (int32_t) (*default).len, ptr);
+  __wasm_import_imports_data_frame_new((int32_t) (*name).ptr, (int32_t) (*name).len, (int32_t) (*defa).ptr, (int32_t) (*defa).len, ptr);
   imports_option_data_frame_t option;
   switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
@@ -197,11 +281,12 @@ bool imports_data_frame_new(imports_string_t *name, imports_row_t *default, impo
   return option.is_some;
 }
 __attribute__((import_module("imports"), import_name("data-frame::push")))
-int32_t __wasm_import_imports_data_frame_push(int32_t, int32_t, int32_t);
-bool imports_data_frame_push(imports_data_frame_t self, imports_row_t *data, imports_empty_t *ret0) {
-  int32_t ret = __wasm_import_imports_data_frame_push((self).idx, (int32_t) (*data).ptr, (int32_t) (*data).len);
-  imports_option_empty_t option;
-  switch (ret) {
+void __wasm_import_imports_data_frame_push(int32_t, int32_t, int32_t, int32_t);
+bool imports_data_frame_push(imports_data_frame_t self, imports_row_t *data, uint64_t *ret0) {
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_data_frame_push((self).idx, (int32_t) (*data).ptr, (int32_t) (*data).len, ptr);
+  imports_option_u64_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
       option.is_some = false;
       
@@ -210,7 +295,7 @@ bool imports_data_frame_push(imports_data_frame_t self, imports_row_t *data, imp
     case 1: {
       option.is_some = true;
       
-      
+      option.val = (uint64_t) (*((int64_t*) (ptr + 8)));
       break;
     }
   }*ret0 = option.val;
@@ -260,10 +345,10 @@ bool imports_storage_new(imports_storage_t *ret0) {
 }
 __attribute__((import_module("imports"), import_name("storage::choice-nodes")))
 void __wasm_import_imports_storage_choice_nodes(int32_t, int32_t, int32_t, int32_t, int32_t);
-bool imports_storage_choice_nodes(imports_storage_t self, imports_string_t *tag, int32_t number, imports_series_t *ret0) {
+bool imports_storage_choice_nodes(imports_storage_t self, imports_string_t *tag, int32_t number, imports_vector_t *ret0) {
   int32_t ptr = (int32_t) &RET_AREA;
   __wasm_import_imports_storage_choice_nodes((self).idx, (int32_t) (*tag).ptr, (int32_t) (*tag).len, number, ptr);
-  imports_option_series_t option;
+  imports_option_vector_t option;
   switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
       option.is_some = false;
@@ -273,49 +358,46 @@ bool imports_storage_choice_nodes(imports_storage_t self, imports_string_t *tag,
     case 1: {
       option.is_some = true;
       imports_vector_t variant;
-      variant.tag = (int32_t) (*((uint8_t*) (ptr + 12)));
+      variant.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
       switch ((int32_t) variant.tag) {
         case 0: {
           break;
         }
         case 1: {
-          variant.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) };
+          variant.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
           break;
         }
         case 2: {
-          variant.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) };
+          variant.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
           break;
         }
         case 3: {
-          variant.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) };
+          variant.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
           break;
         }
         case 4: {
-          variant.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) };
+          variant.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
           break;
         }
         case 5: {
-          variant.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) };
+          variant.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
           break;
         }
         case 6: {
-          variant.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) };
+          variant.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
           break;
         }
       }
       
-      option.val = (imports_series_t) {
-        (imports_string_t) { (char*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) },
-        variant,
-      };
+      option.val = variant;
       break;
     }
   }*ret0 = option.val;
   return option.is_some;
 }
-__attribute__((import_module("imports"), import_name("storage::query-node")))
-void __wasm_import_imports_storage_query_node(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
-bool imports_storage_query_node(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, imports_row_t *ret0) {
+__attribute__((import_module("imports"), import_name("storage::query-nodes")))
+void __wasm_import_imports_storage_query_nodes(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+bool imports_storage_query_nodes(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, imports_row_t *ret0) {
   int32_t variant;
   int64_t variant6;
   int32_t variant7;
@@ -370,7 +452,7 @@ bool imports_storage_query_node(imports_storage_t self, imports_value_t *id, imp
     }
   }
   int32_t ptr = (int32_t) &RET_AREA;
-  __wasm_import_imports_storage_query_node((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
+  __wasm_import_imports_storage_query_nodes((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
   imports_option_row_t option;
   switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
@@ -389,7 +471,7 @@ bool imports_storage_query_node(imports_storage_t self, imports_value_t *id, imp
 }
 __attribute__((import_module("imports"), import_name("storage::query-neighbors")))
 void __wasm_import_imports_storage_query_neighbors(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
-bool imports_storage_query_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_table_t *ret0) {
+bool imports_storage_query_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_tuple2_vector_table_t *ret0) {
   int32_t variant;
   int64_t variant6;
   int32_t variant7;
@@ -445,7 +527,7 @@ bool imports_storage_query_neighbors(imports_storage_t self, imports_value_t *id
   }
   int32_t ptr = (int32_t) &RET_AREA;
   __wasm_import_imports_storage_query_neighbors((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, reversely, ptr);
-  imports_option_table_t option;
+  imports_option_tuple2_vector_table_t option;
   switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
       option.is_some = false;
@@ -454,19 +536,106 @@ bool imports_storage_query_neighbors(imports_storage_t self, imports_value_t *id
     }
     case 1: {
       option.is_some = true;
+      imports_vector_t variant8;
+      variant8.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant8.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant8.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant8.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant8.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant8.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant8.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant8.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
       
-      option.val = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      option.val = (imports_tuple2_vector_table_t) {
+        variant8,
+        (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) },
+      };
       break;
     }
   }*ret0 = option.val;
   return option.is_some;
 }
-__attribute__((import_module("imports"), import_name("storage::query-kv")))
-void __wasm_import_imports_storage_query_kv(int32_t, int32_t, int32_t, int32_t);
-bool imports_storage_query_kv(imports_storage_t self, imports_row_t *data, imports_row_t *ret0) {
+__attribute__((import_module("imports"), import_name("storage::sample-neighbors")))
+void __wasm_import_imports_storage_sample_neighbors(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+bool imports_storage_sample_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, int32_t k, imports_sample_strategy_t strategy, imports_string_t *weight_key, bool reversely, imports_tuple2_vector_table_t *ret0) {
+  int32_t variant;
+  int64_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*id).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const bool *payload0 = &(*id).val.bol;
+      variant = 1;
+      variant6 = (int64_t) *payload0;
+      variant7 = 0;
+      break;
+    }
+    case 2: {
+      const int32_t *payload1 = &(*id).val.i32;
+      variant = 2;
+      variant6 = (int64_t) *payload1;
+      variant7 = 0;
+      break;
+    }
+    case 3: {
+      const int64_t *payload2 = &(*id).val.i64;
+      variant = 3;
+      variant6 = *payload2;
+      variant7 = 0;
+      break;
+    }
+    case 4: {
+      const float *payload3 = &(*id).val.f32;
+      variant = 4;
+      variant6 = ((union { float a; int32_t b; }){ *payload3 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 5: {
+      const double *payload4 = &(*id).val.f64;
+      variant = 5;
+      variant6 = ((union { double a; int64_t b; }){ *payload4 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 6: {
+      const imports_string_t *payload5 = &(*id).val.txt;
+      variant = 6;
+      variant6 = (int64_t) (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
   int32_t ptr = (int32_t) &RET_AREA;
-  __wasm_import_imports_storage_query_kv((self).idx, (int32_t) (*data).ptr, (int32_t) (*data).len, ptr);
-  imports_option_row_t option;
+  __wasm_import_imports_storage_sample_neighbors((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, k, (int32_t) strategy, (int32_t) (*weight_key).ptr, (int32_t) (*weight_key).len, reversely, ptr);
+  imports_option_tuple2_vector_table_t option;
   switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
       option.is_some = false;
@@ -475,19 +644,107 @@ bool imports_storage_query_kv(imports_storage_t self, imports_row_t *data, impor
     }
     case 1: {
       option.is_some = true;
+      imports_vector_t variant8;
+      variant8.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant8.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant8.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant8.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant8.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant8.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant8.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant8.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
       
-      option.val = (imports_row_t) { (imports_item_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      option.val = (imports_tuple2_vector_table_t) {
+        variant8,
+        (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) },
+      };
       break;
     }
   }*ret0 = option.val;
   return option.is_some;
 }
-__attribute__((import_module("imports"), import_name("storage::update-kv")))
-int32_t __wasm_import_imports_storage_update_kv(int32_t, int32_t, int32_t, int32_t);
-bool imports_storage_update_kv(imports_storage_t self, imports_row_t *data, imports_merge_type_t ops, imports_empty_t *ret0) {
-  int32_t ret = __wasm_import_imports_storage_update_kv((self).idx, (int32_t) (*data).ptr, (int32_t) (*data).len, (int32_t) ops);
-  imports_option_empty_t option;
-  switch (ret) {
+__attribute__((import_module("imports"), import_name("storage::sample-subgraph")))
+void __wasm_import_imports_storage_sample_subgraph(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+bool imports_storage_sample_subgraph(imports_storage_t self, imports_vector_t *seeds, imports_list_string_t *tags, imports_list_s32_t *fanouts, imports_list_string_t *keys, imports_list_data_frame_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*seeds).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*seeds).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*seeds).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*seeds).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*seeds).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*seeds).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*seeds).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_sample_subgraph((self).idx, variant, variant6, variant7, (int32_t) (*tags).ptr, (int32_t) (*tags).len, (int32_t) (*fanouts).ptr, (int32_t) (*fanouts).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
+  imports_option_list_data_frame_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
     case 0: {
       option.is_some = false;
       
@@ -496,19 +753,1207 @@ bool imports_storage_update_kv(imports_storage_t self, imports_row_t *data, impo
     case 1: {
       option.is_some = true;
       
-      
+      option.val = (imports_list_data_frame_t) { (imports_data_frame_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
       break;
     }
   }*ret0 = option.val;
   return option.is_some;
 }
-__attribute__((import_module("imports"), import_name("log")))
-void __wasm_import_imports_log(int32_t, int32_t, int32_t);
-void imports_log(imports_log_level_t lv, imports_string_t *msg) {
-  __wasm_import_imports_log((int32_t) lv, (int32_t) (*msg).ptr, (int32_t) (*msg).len);
+__attribute__((import_module("imports"), import_name("storage::query-nodes-batch")))
+void __wasm_import_imports_storage_query_nodes_batch(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+bool imports_storage_query_nodes_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, imports_table_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*ids).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*ids).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*ids).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*ids).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*ids).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*ids).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*ids).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_query_nodes_batch((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
+  imports_option_table_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      option.is_some = false;
+      
+      break;
+    }
+    case 1: {
+      option.is_some = true;
+      
+      option.val = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+  }*ret0 = option.val;
+  return option.is_some;
 }
-__attribute__((import_module("imports"), import_name("log-enabled")))
-void __wasm_import_imports_log_enabled(int32_t, int32_t, int32_t);
-void imports_log_enabled(imports_log_level_t lv, imports_string_t *msg) {
-  __wasm_import_imports_log_enabled((int32_t) lv, (int32_t) (*msg).ptr, (int32_t) (*msg).len);
+__attribute__((import_module("imports"), import_name("storage::query-neighbors-batch")))
+void __wasm_import_imports_storage_query_neighbors_batch(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+bool imports_storage_query_neighbors_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_table_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*ids).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*ids).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*ids).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*ids).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*ids).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*ids).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*ids).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_query_neighbors_batch((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, reversely, ptr);
+  imports_option_table_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      option.is_some = false;
+      
+      break;
+    }
+    case 1: {
+      option.is_some = true;
+      
+      option.val = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+  }*ret0 = option.val;
+  return option.is_some;
+}
+__attribute__((import_module("imports"), import_name("storage::query-kv")))
+void __wasm_import_imports_storage_query_kv(int32_t, int32_t, int32_t, int32_t, int64_t, int32_t, int32_t);
+bool imports_storage_query_kv(imports_storage_t self, imports_list_string_t *keys, imports_value_t *defa, imports_vector_t *ret0) {
+  int32_t variant;
+  int64_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*defa).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const bool *payload0 = &(*defa).val.bol;
+      variant = 1;
+      variant6 = (int64_t) *payload0;
+      variant7 = 0;
+      break;
+    }
+    case 2: {
+      const int32_t *payload1 = &(*defa).val.i32;
+      variant = 2;
+      variant6 = (int64_t) *payload1;
+      variant7 = 0;
+      break;
+    }
+    case 3: {
+      const int64_t *payload2 = &(*defa).val.i64;
+      variant = 3;
+      variant6 = *payload2;
+      variant7 = 0;
+      break;
+    }
+    case 4: {
+      const float *payload3 = &(*defa).val.f32;
+      variant = 4;
+      variant6 = ((union { float a; int32_t b; }){ *payload3 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 5: {
+      const double *payload4 = &(*defa).val.f64;
+      variant = 5;
+      variant6 = ((union { double a; int64_t b; }){ *payload4 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 6: {
+      const imports_string_t *payload5 = &(*defa).val.txt;
+      variant = 6;
+      variant6 = (int64_t) (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_query_kv((self).idx, (int32_t) (*keys).ptr, (int32_t) (*keys).len, variant, variant6, variant7, ptr);
+  imports_option_vector_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      option.is_some = false;
+      
+      break;
+    }
+    case 1: {
+      option.is_some = true;
+      imports_vector_t variant8;
+      variant8.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant8.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant8.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant8.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant8.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant8.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant8.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant8.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
+      
+      option.val = variant8;
+      break;
+    }
+  }*ret0 = option.val;
+  return option.is_some;
+}
+__attribute__((import_module("imports"), import_name("storage::update-kv")))
+void __wasm_import_imports_storage_update_kv(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+bool imports_storage_update_kv(imports_storage_t self, imports_list_string_t *keys, imports_vector_t *vals, imports_merge_type_t ops, uint64_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*vals).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*vals).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*vals).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*vals).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*vals).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*vals).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*vals).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_update_kv((self).idx, (int32_t) (*keys).ptr, (int32_t) (*keys).len, variant, variant6, variant7, (int32_t) ops, ptr);
+  imports_option_u64_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      option.is_some = false;
+      
+      break;
+    }
+    case 1: {
+      option.is_some = true;
+      
+      option.val = (uint64_t) (*((int64_t*) (ptr + 8)));
+      break;
+    }
+  }*ret0 = option.val;
+  return option.is_some;
+}
+__attribute__((import_module("imports"), import_name("storage::try-choice-nodes")))
+void __wasm_import_imports_storage_try_choice_nodes(int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_choice_nodes(imports_storage_t self, imports_string_t *tag, int32_t number, imports_expected_vector_storage_error_t *ret0) {
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_choice_nodes((self).idx, (int32_t) (*tag).ptr, (int32_t) (*tag).len, number, ptr);
+  imports_expected_vector_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      imports_vector_t variant;
+      variant.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
+      
+      expected.val.ok = variant;
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-query-nodes")))
+void __wasm_import_imports_storage_try_query_nodes(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_query_nodes(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, imports_expected_row_storage_error_t *ret0) {
+  int32_t variant;
+  int64_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*id).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const bool *payload0 = &(*id).val.bol;
+      variant = 1;
+      variant6 = (int64_t) *payload0;
+      variant7 = 0;
+      break;
+    }
+    case 2: {
+      const int32_t *payload1 = &(*id).val.i32;
+      variant = 2;
+      variant6 = (int64_t) *payload1;
+      variant7 = 0;
+      break;
+    }
+    case 3: {
+      const int64_t *payload2 = &(*id).val.i64;
+      variant = 3;
+      variant6 = *payload2;
+      variant7 = 0;
+      break;
+    }
+    case 4: {
+      const float *payload3 = &(*id).val.f32;
+      variant = 4;
+      variant6 = ((union { float a; int32_t b; }){ *payload3 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 5: {
+      const double *payload4 = &(*id).val.f64;
+      variant = 5;
+      variant6 = ((union { double a; int64_t b; }){ *payload4 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 6: {
+      const imports_string_t *payload5 = &(*id).val.txt;
+      variant = 6;
+      variant6 = (int64_t) (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_query_nodes((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
+  imports_expected_row_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (imports_row_t) { (imports_item_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-query-neighbors")))
+void __wasm_import_imports_storage_try_query_neighbors(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_query_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_expected_tuple2_vector_table_storage_error_t *ret0) {
+  int32_t variant;
+  int64_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*id).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const bool *payload0 = &(*id).val.bol;
+      variant = 1;
+      variant6 = (int64_t) *payload0;
+      variant7 = 0;
+      break;
+    }
+    case 2: {
+      const int32_t *payload1 = &(*id).val.i32;
+      variant = 2;
+      variant6 = (int64_t) *payload1;
+      variant7 = 0;
+      break;
+    }
+    case 3: {
+      const int64_t *payload2 = &(*id).val.i64;
+      variant = 3;
+      variant6 = *payload2;
+      variant7 = 0;
+      break;
+    }
+    case 4: {
+      const float *payload3 = &(*id).val.f32;
+      variant = 4;
+      variant6 = ((union { float a; int32_t b; }){ *payload3 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 5: {
+      const double *payload4 = &(*id).val.f64;
+      variant = 5;
+      variant6 = ((union { double a; int64_t b; }){ *payload4 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 6: {
+      const imports_string_t *payload5 = &(*id).val.txt;
+      variant = 6;
+      variant6 = (int64_t) (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_query_neighbors((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, reversely, ptr);
+  imports_expected_tuple2_vector_table_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      imports_vector_t variant8;
+      variant8.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant8.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant8.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant8.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant8.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant8.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant8.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant8.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
+      
+      expected.val.ok = (imports_tuple2_vector_table_t) {
+        variant8,
+        (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) },
+      };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-sample-neighbors")))
+void __wasm_import_imports_storage_try_sample_neighbors(int32_t, int32_t, int64_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_sample_neighbors(imports_storage_t self, imports_value_t *id, imports_string_t *tag, imports_list_string_t *keys, int32_t k, imports_sample_strategy_t strategy, imports_string_t *weight_key, bool reversely, imports_expected_tuple2_vector_table_storage_error_t *ret0) {
+  int32_t variant;
+  int64_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*id).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const bool *payload0 = &(*id).val.bol;
+      variant = 1;
+      variant6 = (int64_t) *payload0;
+      variant7 = 0;
+      break;
+    }
+    case 2: {
+      const int32_t *payload1 = &(*id).val.i32;
+      variant = 2;
+      variant6 = (int64_t) *payload1;
+      variant7 = 0;
+      break;
+    }
+    case 3: {
+      const int64_t *payload2 = &(*id).val.i64;
+      variant = 3;
+      variant6 = *payload2;
+      variant7 = 0;
+      break;
+    }
+    case 4: {
+      const float *payload3 = &(*id).val.f32;
+      variant = 4;
+      variant6 = ((union { float a; int32_t b; }){ *payload3 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 5: {
+      const double *payload4 = &(*id).val.f64;
+      variant = 5;
+      variant6 = ((union { double a; int64_t b; }){ *payload4 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 6: {
+      const imports_string_t *payload5 = &(*id).val.txt;
+      variant = 6;
+      variant6 = (int64_t) (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_sample_neighbors((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, k, (int32_t) strategy, (int32_t) (*weight_key).ptr, (int32_t) (*weight_key).len, reversely, ptr);
+  imports_expected_tuple2_vector_table_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      imports_vector_t variant8;
+      variant8.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant8.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant8.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant8.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant8.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant8.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant8.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant8.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
+      
+      expected.val.ok = (imports_tuple2_vector_table_t) {
+        variant8,
+        (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 16))), (size_t)(*((int32_t*) (ptr + 20))) },
+      };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-sample-subgraph")))
+void __wasm_import_imports_storage_try_sample_subgraph(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_sample_subgraph(imports_storage_t self, imports_vector_t *seeds, imports_list_string_t *tags, imports_list_s32_t *fanouts, imports_list_string_t *keys, imports_expected_list_data_frame_storage_error_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*seeds).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*seeds).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*seeds).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*seeds).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*seeds).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*seeds).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*seeds).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_sample_subgraph((self).idx, variant, variant6, variant7, (int32_t) (*tags).ptr, (int32_t) (*tags).len, (int32_t) (*fanouts).ptr, (int32_t) (*fanouts).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
+  imports_expected_list_data_frame_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (imports_list_data_frame_t) { (imports_data_frame_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-query-nodes-batch")))
+void __wasm_import_imports_storage_try_query_nodes_batch(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_query_nodes_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, imports_expected_table_storage_error_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*ids).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*ids).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*ids).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*ids).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*ids).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*ids).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*ids).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_query_nodes_batch((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, ptr);
+  imports_expected_table_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-query-neighbors-batch")))
+void __wasm_import_imports_storage_try_query_neighbors_batch(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_query_neighbors_batch(imports_storage_t self, imports_vector_t *ids, imports_string_t *tag, imports_list_string_t *keys, bool reversely, imports_expected_table_storage_error_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*ids).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*ids).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*ids).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*ids).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*ids).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*ids).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*ids).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_query_neighbors_batch((self).idx, variant, variant6, variant7, (int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*keys).ptr, (int32_t) (*keys).len, reversely, ptr);
+  imports_expected_table_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-query-kv")))
+void __wasm_import_imports_storage_try_query_kv(int32_t, int32_t, int32_t, int32_t, int64_t, int32_t, int32_t);
+void imports_storage_try_query_kv(imports_storage_t self, imports_list_string_t *keys, imports_value_t *defa, imports_expected_vector_storage_error_t *ret0) {
+  int32_t variant;
+  int64_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*defa).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const bool *payload0 = &(*defa).val.bol;
+      variant = 1;
+      variant6 = (int64_t) *payload0;
+      variant7 = 0;
+      break;
+    }
+    case 2: {
+      const int32_t *payload1 = &(*defa).val.i32;
+      variant = 2;
+      variant6 = (int64_t) *payload1;
+      variant7 = 0;
+      break;
+    }
+    case 3: {
+      const int64_t *payload2 = &(*defa).val.i64;
+      variant = 3;
+      variant6 = *payload2;
+      variant7 = 0;
+      break;
+    }
+    case 4: {
+      const float *payload3 = &(*defa).val.f32;
+      variant = 4;
+      variant6 = ((union { float a; int32_t b; }){ *payload3 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 5: {
+      const double *payload4 = &(*defa).val.f64;
+      variant = 5;
+      variant6 = ((union { double a; int64_t b; }){ *payload4 }).b;
+      variant7 = 0;
+      break;
+    }
+    case 6: {
+      const imports_string_t *payload5 = &(*defa).val.txt;
+      variant = 6;
+      variant6 = (int64_t) (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_query_kv((self).idx, (int32_t) (*keys).ptr, (int32_t) (*keys).len, variant, variant6, variant7, ptr);
+  imports_expected_vector_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      imports_vector_t variant8;
+      variant8.tag = (int32_t) (*((uint8_t*) (ptr + 4)));
+      switch ((int32_t) variant8.tag) {
+        case 0: {
+          break;
+        }
+        case 1: {
+          variant8.val.bol = (imports_list_bool_t) { (bool*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 2: {
+          variant8.val.i32 = (imports_list_s32_t) { (int32_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 3: {
+          variant8.val.i64 = (imports_list_s64_t) { (int64_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 4: {
+          variant8.val.f32 = (imports_list_float32_t) { (float*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 5: {
+          variant8.val.f64 = (imports_list_float64_t) { (double*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+        case 6: {
+          variant8.val.txt = (imports_list_string_t) { (imports_string_t*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) };
+          break;
+        }
+      }
+      
+      expected.val.ok = variant8;
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("storage::try-update-kv")))
+void __wasm_import_imports_storage_try_update_kv(int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t, int32_t);
+void imports_storage_try_update_kv(imports_storage_t self, imports_list_string_t *keys, imports_vector_t *vals, imports_merge_type_t ops, imports_expected_u64_storage_error_t *ret0) {
+  int32_t variant;
+  int32_t variant6;
+  int32_t variant7;
+  switch ((int32_t) (*vals).tag) {
+    case 0: {
+      variant = 0;
+      variant6 = 0;
+      variant7 = 0;
+      break;
+    }
+    case 1: {
+      const imports_list_bool_t *payload0 = &(*vals).val.bol;
+      variant = 1;
+      variant6 = (int32_t) (*payload0).ptr;
+      variant7 = (int32_t) (*payload0).len;
+      break;
+    }
+    case 2: {
+      const imports_list_s32_t *payload1 = &(*vals).val.i32;
+      variant = 2;
+      variant6 = (int32_t) (*payload1).ptr;
+      variant7 = (int32_t) (*payload1).len;
+      break;
+    }
+    case 3: {
+      const imports_list_s64_t *payload2 = &(*vals).val.i64;
+      variant = 3;
+      variant6 = (int32_t) (*payload2).ptr;
+      variant7 = (int32_t) (*payload2).len;
+      break;
+    }
+    case 4: {
+      const imports_list_float32_t *payload3 = &(*vals).val.f32;
+      variant = 4;
+      variant6 = (int32_t) (*payload3).ptr;
+      variant7 = (int32_t) (*payload3).len;
+      break;
+    }
+    case 5: {
+      const imports_list_float64_t *payload4 = &(*vals).val.f64;
+      variant = 5;
+      variant6 = (int32_t) (*payload4).ptr;
+      variant7 = (int32_t) (*payload4).len;
+      break;
+    }
+    case 6: {
+      const imports_list_string_t *payload5 = &(*vals).val.txt;
+      variant = 6;
+      variant6 = (int32_t) (*payload5).ptr;
+      variant7 = (int32_t) (*payload5).len;
+      break;
+    }
+  }
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_storage_try_update_kv((self).idx, (int32_t) (*keys).ptr, (int32_t) (*keys).len, variant, variant6, variant7, (int32_t) ops, ptr);
+  imports_expected_u64_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (uint64_t) (*((int64_t*) (ptr + 8)));
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 8))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 12))), (size_t)(*((int32_t*) (ptr + 16))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("edge-scanner::new")))
+void __wasm_import_imports_edge_scanner_new(int32_t, int32_t, int32_t, int32_t, int64_t, int64_t, int32_t);
+bool imports_edge_scanner_new(imports_string_t *tag, imports_string_t *time_field, int64_t start_time, int64_t duration, imports_edge_scanner_t *ret0) {
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_edge_scanner_new((int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*time_field).ptr, (int32_t) (*time_field).len, start_time, duration, ptr);
+  imports_option_edge_scanner_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      option.is_some = false;
+      
+      break;
+    }
+    case 1: {
+      option.is_some = true;
+      
+      option.val = (imports_edge_scanner_t){ *((int32_t*) (ptr + 4)) };
+      break;
+    }
+  }*ret0 = option.val;
+  return option.is_some;
+}
+__attribute__((import_module("imports"), import_name("edge-scanner::next")))
+void __wasm_import_imports_edge_scanner_next(int32_t, int64_t, int32_t);
+bool imports_edge_scanner_next(imports_edge_scanner_t self, int64_t number, imports_table_t *ret0) {
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_edge_scanner_next((self).idx, number, ptr);
+  imports_option_table_t option;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      option.is_some = false;
+      
+      break;
+    }
+    case 1: {
+      option.is_some = true;
+      
+      option.val = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+  }*ret0 = option.val;
+  return option.is_some;
+}
+__attribute__((import_module("imports"), import_name("edge-scanner::try-new")))
+void __wasm_import_imports_edge_scanner_try_new(int32_t, int32_t, int32_t, int32_t, int64_t, int64_t, int32_t);
+void imports_edge_scanner_try_new(imports_string_t *tag, imports_string_t *time_field, int64_t start_time, int64_t duration, imports_expected_edge_scanner_storage_error_t *ret0) {
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_edge_scanner_try_new((int32_t) (*tag).ptr, (int32_t) (*tag).len, (int32_t) (*time_field).ptr, (int32_t) (*time_field).len, start_time, duration, ptr);
+  imports_expected_edge_scanner_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (imports_edge_scanner_t){ *((int32_t*) (ptr + 4)) };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("edge-scanner::try-next")))
+void __wasm_import_imports_edge_scanner_try_next(int32_t, int64_t, int32_t);
+void imports_edge_scanner_try_next(imports_edge_scanner_t self, int64_t number, imports_expected_table_storage_error_t *ret0) {
+  int32_t ptr = (int32_t) &RET_AREA;
+  __wasm_import_imports_edge_scanner_try_next((self).idx, number, ptr);
+  imports_expected_table_storage_error_t expected;
+  switch ((int32_t) (*((uint8_t*) (ptr + 0)))) {
+    case 0: {
+      expected.is_err = false;
+      
+      expected.val.ok = (imports_table_t) { (imports_series_t*)(*((int32_t*) (ptr + 4))), (size_t)(*((int32_t*) (ptr + 8))) };
+      break;
+    }
+    case 1: {
+      expected.is_err = true;
+      
+      expected.val.err = (imports_storage_error_t) {
+        (int32_t) (*((uint8_t*) (ptr + 4))),
+        (imports_string_t) { (char*)(*((int32_t*) (ptr + 8))), (size_t)(*((int32_t*) (ptr + 12))) },
+      };
+      break;
+    }
+  }*ret0 = expected;
+}
+__attribute__((import_module("imports"), import_name("log")))
+void __wasm_import_imports_log(int32_t, int32_t, int32_t);
+void imports_log(imports_log_level_t lv, imports_string_t *msg) {
+  __wasm_import_imports_log((int32_t) lv, (int32_t) (*msg).ptr, (int32_t) (*msg).len);
+}
+__attribute__((import_module("imports"), import_name("log-enabled")))
+int32_t __wasm_import_imports_log_enabled(int32_t);
+bool imports_log_enabled(imports_log_level_t lv) {
+  int32_t ret = __wasm_import_imports_log_enabled((int32_t) lv);
+  return ret;
 }
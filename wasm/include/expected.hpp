@@ -0,0 +1,89 @@
+#ifndef __EXPECTED_HPP__
+#define __EXPECTED_HPP__
+
+#include <string>
+#include <variant>
+
+#include <log_abort.hpp>
+#include <option.hpp>
+
+namespace imports {
+
+enum storage_error_kind {
+    not_found = IMPORTS_STORAGE_ERROR_KIND_NOT_FOUND,
+    unavailable = IMPORTS_STORAGE_ERROR_KIND_UNAVAILABLE,
+    invalid_argument = IMPORTS_STORAGE_ERROR_KIND_INVALID_ARGUMENT,
+    type_mismatch = IMPORTS_STORAGE_ERROR_KIND_TYPE_MISMATCH,
+    limit_exceeded = IMPORTS_STORAGE_ERROR_KIND_LIMIT_EXCEEDED,
+    internal = IMPORTS_STORAGE_ERROR_KIND_INTERNAL,
+};
+
+struct storage_error {
+    storage_error_kind kind;
+    std::string message;
+
+    storage_error(imports_storage_error_t* err) {
+        this->kind = static_cast<storage_error_kind>(err->kind);
+        this->message = std::string(err->message.ptr, err->message.len);
+        imports_string_free(&err->message);
+    }
+
+    // 存储暂时不可用，可以重试
+    bool is_retryable() const { return this->kind == storage_error_kind::unavailable; }
+};
+
+template<typename T>
+class expected {
+    std::variant<T, storage_error> _data;
+
+    template<typename ... Args>
+    constexpr explicit expected<T>(Args&&... args): _data(std::forward<Args>(args)...) {}
+
+public:
+    static constexpr expected<T> ok(T&& o) {
+        return expected<T>(std::in_place_index<0>, std::forward<T>(o));
+    }
+
+    static constexpr expected<T> err(storage_error&& e) {
+        return expected<T>(std::in_place_index<1>, std::move(e));
+    }
+
+    bool is_ok() const { return this->_data.index() == 0; }
+    bool is_err() const { return this->_data.index() == 1; }
+
+    const storage_error& error() const {
+        if (this->is_ok()) {
+            LOG_ABORT("no error")
+        }
+        return std::get<1>(this->_data);
+    }
+
+    T expect(std::string_view msg) {
+        if (this->is_err()) {
+            LOG_ABORT(std::string(msg) + ": " + this->error().message)
+        }
+        return std::move(std::get<0>(this->_data));
+    }
+
+    T unwrap() { return this->expect("unwrapping error"); }
+
+    // 丢弃错误原因
+    option<T> ok() {
+        if (this->is_err()) {
+            return option<T>::none();
+        }
+        return option<T>::some(std::move(std::get<0>(this->_data)));
+    }
+};
+
+template<typename T, typename R>
+inline expected<T> __get_expected_result(R& ret0) {
+    if (ret0.is_err) {
+        return expected<T>::err(&ret0.val.err);
+    }
+    return expected<T>::ok(&ret0.val.ok);
+}
+
+}
+
+#endif
@@ -5,9 +5,11 @@
 #include <utils.hpp>
 #include <log.hpp>
 #include <option.hpp>
+#include <expected.hpp>
 
 #include <span>
 #include <tuple>
+#include <vector>
 #include <memory>
 // #include <optional>
 
@@ -18,7 +20,15 @@ enum merge_type {
     mov = IMPORTS_MERGE_TYPE_MOV,
 };
 
+enum sample_strategy {
+    uniform = IMPORTS_SAMPLE_STRATEGY_UNIFORM,
+    weighted = IMPORTS_SAMPLE_STRATEGY_WEIGHTED,
+    recent = IMPORTS_SAMPLE_STRATEGY_RECENT,
+};
+
 class data_frame {
+    friend class storage;
+
     imports_data_frame_t _handle;
     bool _owner;
     data_frame(imports_data_frame_t* handle) { this->_handle = *handle; this->_owner = true; }
@@ -99,9 +109,16 @@ public:
 
     bool is_owner() const { return this->_owner; }
 
+    // 以下每个接口都有返回expected的try_版本，出错时可以通过error()得知原因，
+    // 如not_found(节点、边类型或属性不存在)、unavailable(存储不可用，可以重试)、type_mismatch(类型不符)
+
     // choice-nodes
 
     [[nodiscard]] option<vector> choice_nodes(std::string_view tag, int32_t n) {
+        return this->try_choice_nodes(tag, n).ok();
+    }
+
+    [[nodiscard]] expected<vector> try_choice_nodes(std::string_view tag, int32_t n) {
         if (!this->is_owner()) {
             LOG_ABORT("object moved")
         }
@@ -109,28 +126,34 @@ public:
         imports_string_t tag0;
         __set_string_param(tag0, tag);
 
-        imports_vector_t ret0;
-        if (imports_storage_choice_nodes(this->_handle, &tag0, n, &ret0)) {
-            return option<vector>::some(&ret0);
-        }
-        return option<vector>::none();
+        imports_expected_vector_storage_error_t ret0;
+        imports_storage_try_choice_nodes(this->_handle, &tag0, n, &ret0);
+        return __get_expected_result<vector>(ret0);
     }
 
     // query-nodes
 
     [[nodiscard]] option<row> query_nodes(int64_t id, std::string_view tag, std::span<std::string_view> keys) {
-        return this->query_nodes_impl(id, tag, keys);
+        return this->query_nodes_impl(id, tag, keys).ok();
     }
 
     [[nodiscard]] option<row> query_nodes(int64_t id, std::string_view tag, std::initializer_list<std::string_view> keys) {
-        return this->query_nodes_impl(id, tag, keys);
+        return this->query_nodes_impl(id, tag, keys).ok();
     }
 
     [[nodiscard]] option<row> query_nodes(std::string_view id, std::string_view tag, std::span<std::string_view> keys) {
-        return this->query_nodes_impl(id, tag, keys);
+        return this->query_nodes_impl(id, tag, keys).ok();
     }
 
     [[nodiscard]] option<row> query_nodes(std::string_view id, std::string_view tag, std::initializer_list<std::string_view> keys) {
+        return this->query_nodes_impl(id, tag, keys).ok();
+    }
+
+    [[nodiscard]] expected<row> try_query_nodes(value_param id, std::string_view tag, std::span<std::string_view> keys) {
+        return this->query_nodes_impl(id, tag, keys);
+    }
+
+    [[nodiscard]] expected<row> try_query_nodes(value_param id, std::string_view tag, std::initializer_list<std::string_view> keys) {
         return this->query_nodes_impl(id, tag, keys);
     }
 
@@ -139,42 +162,174 @@ public:
     [[nodiscard]] option<std::tuple<vector, table>> query_neighbors(int64_t id, std::string_view tag,
         std::span<std::string_view> keys, bool reversely = false)
     {
-        return this->query_neighbors_impl(id, tag, keys, reversely);
+        return this->query_neighbors_impl(id, tag, keys, reversely).ok();
     }
 
     [[nodiscard]] option<std::tuple<vector, table>> query_neighbors(int64_t id, std::string_view tag,
         std::initializer_list<std::string_view> keys, bool reversely = false)
     {
-        return this->query_neighbors_impl(id, tag, keys, reversely);
+        return this->query_neighbors_impl(id, tag, keys, reversely).ok();
     }
 
     [[nodiscard]] option<std::tuple<vector, table>> query_neighbors(std::string_view id, std::string_view tag,
         std::span<std::string_view> keys, bool reversely = false)
     {
-        return this->query_neighbors_impl(id, tag, keys, reversely);
+        return this->query_neighbors_impl(id, tag, keys, reversely).ok();
     }
 
     [[nodiscard]] option<std::tuple<vector, table>> query_neighbors(std::string_view id, std::string_view tag,
         std::initializer_list<std::string_view> keys, bool reversely = false)
+    {
+        return this->query_neighbors_impl(id, tag, keys, reversely).ok();
+    }
+
+    [[nodiscard]] expected<std::tuple<vector, table>> try_query_neighbors(value_param id, std::string_view tag,
+        std::span<std::string_view> keys, bool reversely = false)
     {
         return this->query_neighbors_impl(id, tag, keys, reversely);
     }
 
+    [[nodiscard]] expected<std::tuple<vector, table>> try_query_neighbors(value_param id, std::string_view tag,
+        std::initializer_list<std::string_view> keys, bool reversely = false)
+    {
+        return this->query_neighbors_impl(id, tag, keys, reversely);
+    }
+
+    // sample-neighbors
+    // weighted按weight_key的值加权采样，recent选取weight_key(时间戳)最大的k个邻居
+
+    [[nodiscard]] option<std::tuple<vector, table>> sample_neighbors(value_param id, std::string_view tag,
+        std::span<std::string_view> keys, int32_t k, sample_strategy strategy = sample_strategy::uniform,
+        std::string_view weight_key = "", bool reversely = false)
+    {
+        return this->sample_neighbors_impl(id, tag, keys, k, strategy, weight_key, reversely).ok();
+    }
+
+    [[nodiscard]] option<std::tuple<vector, table>> sample_neighbors(value_param id, std::string_view tag,
+        std::initializer_list<std::string_view> keys, int32_t k, sample_strategy strategy = sample_strategy::uniform,
+        std::string_view weight_key = "", bool reversely = false)
+    {
+        return this->sample_neighbors_impl(id, tag, keys, k, strategy, weight_key, reversely).ok();
+    }
+
+    [[nodiscard]] expected<std::tuple<vector, table>> try_sample_neighbors(value_param id, std::string_view tag,
+        std::span<std::string_view> keys, int32_t k, sample_strategy strategy = sample_strategy::uniform,
+        std::string_view weight_key = "", bool reversely = false)
+    {
+        return this->sample_neighbors_impl(id, tag, keys, k, strategy, weight_key, reversely);
+    }
+
+    [[nodiscard]] expected<std::tuple<vector, table>> try_sample_neighbors(value_param id, std::string_view tag,
+        std::initializer_list<std::string_view> keys, int32_t k, sample_strategy strategy = sample_strategy::uniform,
+        std::string_view weight_key = "", bool reversely = false)
+    {
+        return this->sample_neighbors_impl(id, tag, keys, k, strategy, weight_key, reversely);
+    }
+
+    // sample-subgraph
+    // 采样结果直接作为返回表，包括nodes(id, hop)和每一跳每种边的hop{i}:{tag}(src, dst, keys...)
+
+    [[nodiscard]] option<std::vector<data_frame>> sample_subgraph(vector_param seeds, std::span<std::string_view> tags,
+        std::span<int32_t> fanouts, std::span<std::string_view> keys)
+    {
+        return this->sample_subgraph_impl(seeds, tags, fanouts, keys).ok();
+    }
+
+    [[nodiscard]] option<std::vector<data_frame>> sample_subgraph(vector_param seeds, std::initializer_list<std::string_view> tags,
+        std::initializer_list<int32_t> fanouts, std::initializer_list<std::string_view> keys)
+    {
+        return this->sample_subgraph_impl(seeds, tags, fanouts, keys).ok();
+    }
+
+    [[nodiscard]] expected<std::vector<data_frame>> try_sample_subgraph(vector_param seeds, std::span<std::string_view> tags,
+        std::span<int32_t> fanouts, std::span<std::string_view> keys)
+    {
+        return this->sample_subgraph_impl(seeds, tags, fanouts, keys);
+    }
+
+    [[nodiscard]] expected<std::vector<data_frame>> try_sample_subgraph(vector_param seeds, std::initializer_list<std::string_view> tags,
+        std::initializer_list<int32_t> fanouts, std::initializer_list<std::string_view> keys)
+    {
+        return this->sample_subgraph_impl(seeds, tags, fanouts, keys);
+    }
+
+    // query-nodes-batch
+
+    [[nodiscard]] option<table> query_nodes_batch(vector_param ids, std::string_view tag, std::span<std::string_view> keys) {
+        return this->query_nodes_batch_impl(ids, tag, keys).ok();
+    }
+
+    [[nodiscard]] option<table> query_nodes_batch(vector_param ids, std::string_view tag, std::initializer_list<std::string_view> keys) {
+        return this->query_nodes_batch_impl(ids, tag, keys).ok();
+    }
+
+    [[nodiscard]] expected<table> try_query_nodes_batch(vector_param ids, std::string_view tag, std::span<std::string_view> keys) {
+        return this->query_nodes_batch_impl(ids, tag, keys);
+    }
+
+    [[nodiscard]] expected<table> try_query_nodes_batch(vector_param ids, std::string_view tag, std::initializer_list<std::string_view> keys) {
+        return this->query_nodes_batch_impl(ids, tag, keys);
+    }
+
+    // query-neighbors-batch
+    // 返回的表包含offsets和dst两列，ids[i]的邻居位于[offsets[i], offsets[i + 1])
+
+    [[nodiscard]] option<table> query_neighbors_batch(vector_param ids, std::string_view tag,
+        std::span<std::string_view> keys, bool reversely = false)
+    {
+        return this->query_neighbors_batch_impl(ids, tag, keys, reversely).ok();
+    }
+
+    [[nodiscard]] option<table> query_neighbors_batch(vector_param ids, std::string_view tag,
+        std::initializer_list<std::string_view> keys, bool reversely = false)
+    {
+        return this->query_neighbors_batch_impl(ids, tag, keys, reversely).ok();
+    }
+
+    [[nodiscard]] expected<table> try_query_neighbors_batch(vector_param ids, std::string_view tag,
+        std::span<std::string_view> keys, bool reversely = false)
+    {
+        return this->query_neighbors_batch_impl(ids, tag, keys, reversely);
+    }
+
+    [[nodiscard]] expected<table> try_query_neighbors_batch(vector_param ids, std::string_view tag,
+        std::initializer_list<std::string_view> keys, bool reversely = false)
+    {
+        return this->query_neighbors_batch_impl(ids, tag, keys, reversely);
+    }
+
     // query-kv
 
     [[nodiscard]] option<vector> query_kv(std::span<std::string_view> keys, value_param defa) {
-        return this->query_kv_impl(keys, defa);
+        return this->query_kv_impl(keys, defa).ok();
     }
 
     [[nodiscard]] option<vector> query_kv(std::initializer_list<std::string_view> keys, value_param defa) {
+        return this->query_kv_impl(keys, defa).ok();
+    }
+
+    [[nodiscard]] expected<vector> try_query_kv(std::span<std::string_view> keys, value_param defa) {
+        return this->query_kv_impl(keys, defa);
+    }
+
+    [[nodiscard]] expected<vector> try_query_kv(std::initializer_list<std::string_view> keys, value_param defa) {
         return this->query_kv_impl(keys, defa);
     }
 
     void update_kv(std::span<std::string_view> keys, vector_param vals, merge_type ops) {
-        return this->update_kv_impl(keys, vals, ops);
+        this->update_kv_impl(keys, vals, ops).expect("storage::update_kv");
     }
 
     void update_kv(std::initializer_list<std::string_view> keys, vector_param vals, merge_type ops) {
+        this->update_kv_impl(keys, vals, ops).expect("storage::update_kv");
+    }
+
+    // 返回写入的字段数
+    [[nodiscard]] expected<uint64_t> try_update_kv(std::span<std::string_view> keys, vector_param vals, merge_type ops) {
+        return this->update_kv_impl(keys, vals, ops);
+    }
+
+    [[nodiscard]] expected<uint64_t> try_update_kv(std::initializer_list<std::string_view> keys, vector_param vals, merge_type ops) {
         return this->update_kv_impl(keys, vals, ops);
     }
 
@@ -184,51 +339,152 @@ public:
 
 private:
     template<typename K>
-    inline option<row> query_nodes_impl(value_param id, std::string_view tag, K keys) {
+    inline expected<row> query_nodes_impl(value_param id, std::string_view tag, K keys) {
+        if (!this->is_owner()) {
+            LOG_ABORT("object moved")
+        }
+
+        imports_value_t id0;
+        __set_value_param(id0, id);
+
+        imports_string_t tag0;
+        __set_string_param(tag0, tag);
+
+        imports_list_string_t keys0;
+        auto owner = __set_span_string_param(keys0, keys);
+
+        imports_expected_row_storage_error_t ret0;
+        imports_storage_try_query_nodes(this->_handle, &id0, &tag0, &keys0, &ret0);
+        return __get_expected_result<row>(ret0);
+    }
+
+    template<typename K>
+    inline expected<std::tuple<vector, table>> query_neighbors_impl(value_param id, std::string_view tag, K&& keys, bool reversely) {
         if (!this->is_owner()) {
             LOG_ABORT("object moved")
         }
 
         imports_value_t id0;
         __set_value_param(id0, id);
-        
+
         imports_string_t tag0;
         __set_string_param(tag0, tag);
 
         imports_list_string_t keys0;
         auto owner = __set_span_string_param(keys0, keys);
 
-        imports_row_t ret0;
-        if (imports_storage_query_nodes(this->_handle, &id0, &tag0, &keys0, &ret0)) {
-            return option<row>::some(&ret0);
+        imports_expected_tuple2_vector_table_storage_error_t ret0;
+        imports_storage_try_query_neighbors(this->_handle, &id0, &tag0, &keys0, reversely, &ret0);
+        if (ret0.is_err) {
+            return expected<std::tuple<vector, table>>::err(&ret0.val.err);
         }
-        return option<row>::none();
+        return expected<std::tuple<vector, table>>::ok(std::make_tuple(&ret0.val.ok.f0, &ret0.val.ok.f1));
     }
 
     template<typename K>
-    inline option<std::tuple<vector, table>> query_neighbors_impl(value_param id, std::string_view tag, K&& keys, bool reversely) {
+    inline expected<std::tuple<vector, table>> sample_neighbors_impl(value_param id, std::string_view tag, K&& keys,
+        int32_t k, sample_strategy strategy, std::string_view weight_key, bool reversely)
+    {
         if (!this->is_owner()) {
             LOG_ABORT("object moved")
         }
 
         imports_value_t id0;
         __set_value_param(id0, id);
-        
+
         imports_string_t tag0;
         __set_string_param(tag0, tag);
 
         imports_list_string_t keys0;
         auto owner = __set_span_string_param(keys0, keys);
 
-        imports_tuple2_vector_table_t ret0;
-        if (imports_storage_query_neighbors(this->_handle, &id0, &tag0, &keys0, reversely, &ret0)) {
-            return option<std::tuple<vector, table>>::some(std::make_tuple(&ret0.f0, &ret0.f1));
+        imports_string_t weight_key0;
+        __set_string_param(weight_key0, weight_key);
+
+        imports_expected_tuple2_vector_table_storage_error_t ret0;
+        imports_storage_try_sample_neighbors(this->_handle, &id0, &tag0, &keys0, k, strategy, &weight_key0, reversely, &ret0);
+        if (ret0.is_err) {
+            return expected<std::tuple<vector, table>>::err(&ret0.val.err);
+        }
+        return expected<std::tuple<vector, table>>::ok(std::make_tuple(&ret0.val.ok.f0, &ret0.val.ok.f1));
+    }
+
+    template<typename K, typename F>
+    inline expected<std::vector<data_frame>> sample_subgraph_impl(vector_param seeds, K&& tags, F&& fanouts, K&& keys) {
+        if (!this->is_owner()) {
+            LOG_ABORT("object moved")
+        }
+
+        imports_vector_t seeds0;
+        auto owner0 = __set_vector_param(seeds0, seeds);
+
+        imports_list_string_t tags0;
+        auto owner1 = __set_span_string_param(tags0, tags);
+
+        imports_list_s32_t fanouts0 = {
+            .ptr = const_cast<int32_t*>(std::data(fanouts)),
+            .len = std::size(fanouts),
+        };
+
+        imports_list_string_t keys0;
+        auto owner2 = __set_span_string_param(keys0, keys);
+
+        imports_expected_list_data_frame_storage_error_t ret0;
+        imports_storage_try_sample_subgraph(this->_handle, &seeds0, &tags0, &fanouts0, &keys0, &ret0);
+        if (ret0.is_err) {
+            return expected<std::vector<data_frame>>::err(&ret0.val.err);
+        }
+
+        std::vector<data_frame> dfs;
+        for (size_t i = 0; i < ret0.val.ok.len; i++) {
+            dfs.push_back(data_frame(&ret0.val.ok.ptr[i]));
+        }
+        free(ret0.val.ok.ptr);
+        return expected<std::vector<data_frame>>::ok(std::move(dfs));
+    }
+
+    template<typename K>
+    inline expected<table> query_nodes_batch_impl(vector_param ids, std::string_view tag, K&& keys) {
+        if (!this->is_owner()) {
+            LOG_ABORT("object moved")
+        }
+
+        imports_vector_t ids0;
+        auto owner0 = __set_vector_param(ids0, ids);
+
+        imports_string_t tag0;
+        __set_string_param(tag0, tag);
+
+        imports_list_string_t keys0;
+        auto owner1 = __set_span_string_param(keys0, keys);
+
+        imports_expected_table_storage_error_t ret0;
+        imports_storage_try_query_nodes_batch(this->_handle, &ids0, &tag0, &keys0, &ret0);
+        return __get_expected_result<table>(ret0);
+    }
+
+    template<typename K>
+    inline expected<table> query_neighbors_batch_impl(vector_param ids, std::string_view tag, K&& keys, bool reversely) {
+        if (!this->is_owner()) {
+            LOG_ABORT("object moved")
         }
-        return option<std::tuple<vector, table>>::none();
+
+        imports_vector_t ids0;
+        auto owner0 = __set_vector_param(ids0, ids);
+
+        imports_string_t tag0;
+        __set_string_param(tag0, tag);
+
+        imports_list_string_t keys0;
+        auto owner1 = __set_span_string_param(keys0, keys);
+
+        imports_expected_table_storage_error_t ret0;
+        imports_storage_try_query_neighbors_batch(this->_handle, &ids0, &tag0, &keys0, reversely, &ret0);
+        return __get_expected_result<table>(ret0);
     }
 
     template<typename K>
-    inline option<vector> query_kv_impl(K&& keys, value_param defa) {
+    inline expected<vector> query_kv_impl(K&& keys, value_param defa) {
         if (!this->is_owner()) {
             LOG_ABORT("object moved")
         }
@@ -239,30 +495,76 @@ private:
         imports_value_t defa0;
         __set_value_param(defa0, defa);
 
-        imports_vector_t ret0;
-        if (imports_storage_query_kv(this->_handle, &keys0, &defa0, &ret0)) {
-            return option<vector>::some(&ret0);
-        }
-        return option<vector>::none();
+        imports_expected_vector_storage_error_t ret0;
+        imports_storage_try_query_kv(this->_handle, &keys0, &defa0, &ret0);
+        return __get_expected_result<vector>(ret0);
     }
 
     template<typename K>
-    inline void update_kv_impl(K&& keys, vector_param vals, merge_type ops) {
+    inline expected<uint64_t> update_kv_impl(K&& keys, vector_param vals, merge_type ops) {
         if (!this->is_owner()) {
             LOG_ABORT("object moved")
         }
-        
+
         imports_list_string_t keys0;
         auto owner0 = __set_span_string_param(keys0, keys);
 
         imports_vector_t vals0;
         auto owner1 = __set_vector_param(vals0, vals);
 
-        uint64_t ret0;
-        if (!imports_storage_update_kv(this->_handle, &keys0, &vals0, ops, &ret0)) {
-            LOG_ABORT("storage::update_kv_impl")
+        imports_expected_u64_storage_error_t ret0;
+        imports_storage_try_update_kv(this->_handle, &keys0, &vals0, ops, &ret0);
+        if (ret0.is_err) {
+            return expected<uint64_t>::err(&ret0.val.err);
+        }
+        return expected<uint64_t>::ok(std::move(ret0.val.ok));
+    }
+};
+
+class edge_scanner {
+    imports_edge_scanner_t _handle;
+    bool _owner;
+    edge_scanner(imports_edge_scanner_t* handle) { this->_handle = *handle; this->_owner = true; }
+public:
+    static option<edge_scanner> open(std::string_view tag, std::string_view time_field, int64_t start_time, int64_t duration) {
+        return edge_scanner::try_open(tag, time_field, start_time, duration).ok();
+    }
+
+    static expected<edge_scanner> try_open(std::string_view tag, std::string_view time_field, int64_t start_time, int64_t duration) {
+        imports_string_t tag0;
+        __set_string_param(tag0, tag);
+
+        imports_string_t time_field0;
+        __set_string_param(time_field0, time_field);
+
+        imports_expected_edge_scanner_storage_error_t ret0;
+        imports_edge_scanner_try_new(&tag0, &time_field0, start_time, duration, &ret0);
+        if (ret0.is_err) {
+            return expected<edge_scanner>::err(&ret0.val.err);
         }
+        return expected<edge_scanner>::ok(&ret0.val.ok);
     }
+
+    bool is_owner() const { return this->_owner; }
+
+    // 返回空表时说明扫描已经结束
+    [[nodiscard]] option<table> next(int64_t n) {
+        return this->try_next(n).ok();
+    }
+
+    [[nodiscard]] expected<table> try_next(int64_t n) {
+        if (!this->is_owner()) {
+            LOG_ABORT("object moved")
+        }
+
+        imports_expected_table_storage_error_t ret0;
+        imports_edge_scanner_try_next(this->_handle, n, &ret0);
+        return __get_expected_result<table>(ret0);
+    }
+
+    edge_scanner(edge_scanner&& rhs) { this->_handle = rhs._handle; this->_owner = rhs._owner; rhs._owner = false; }
+    ~edge_scanner() { if (this->_owner) imports_edge_scanner_free(&this->_handle); }
+    edge_scanner& operator= (edge_scanner&& rhs) { this->_handle = rhs._handle; this->_owner = rhs._owner; rhs._owner = false; return *this; }
 };
 
 }